@@ -0,0 +1,162 @@
+// Copyright (C) 2020-2021 Andy Kurnia.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::prelude::*;
+use wolges::{game_config, game_state, klv, kwg, movegen};
+
+// positions come from seeded self-play, so they are the same on every run
+// but depend on the lexicon. run from a directory with lexbin/CSW19.kwg.
+struct Position {
+    board_tiles: Box<[u8]>,
+    rack: Vec<u8>,
+}
+
+fn collect_positions(
+    game_config: &game_config::GameConfig<'_>,
+    kwg: &kwg::Kwg,
+    klv: &klv::Klv,
+    num_games: usize,
+) -> Vec<Position> {
+    let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+    let mut move_generator = movegen::KurniaMoveGenerator::new(game_config);
+    let mut game_state = game_state::GameState::new(game_config);
+    let mut final_scores = vec![0; game_config.num_players() as usize];
+    let mut positions_by_game = Vec::with_capacity(num_games);
+    for _ in 0..num_games {
+        let mut positions = Vec::new();
+        game_state.reset_and_draw_tiles(game_config, &mut rng);
+        loop {
+            positions.push(Position {
+                board_tiles: game_state.board_tiles.clone(),
+                rack: game_state.current_player().rack.clone(),
+            });
+            move_generator.gen_moves_unfiltered(&movegen::GenMovesParams {
+                board_snapshot: &movegen::BoardSnapshot {
+                    board_tiles: &game_state.board_tiles,
+                    game_config,
+                    kwg,
+                    klv,
+                },
+                rack: &game_state.current_player().rack,
+                max_gen: 1,
+                always_include_pass: true,
+                num_tiles_in_bag: None,
+                constraints: None,
+            });
+            let play = move_generator.plays[0].play.clone();
+            game_state.play(game_config, &mut rng, &play).unwrap();
+            match game_state.check_game_ended(game_config, &mut final_scores) {
+                game_state::CheckGameEnded::NotEnded => game_state.next_turn(),
+                _ => break,
+            }
+        }
+        positions_by_game.push(positions);
+    }
+    // interleave the games, consecutive turns of one game would mostly hit
+    // the cross set cache.
+    let mut positions = Vec::new();
+    for turn in 0.. {
+        let len_before = positions.len();
+        for game_positions in positions_by_game.iter_mut() {
+            if let Some(position) = game_positions.get_mut(turn) {
+                positions.push(std::mem::replace(
+                    position,
+                    Position {
+                        board_tiles: Box::new([]),
+                        rack: Vec::new(),
+                    },
+                ));
+            }
+        }
+        if positions.len() == len_before {
+            break;
+        }
+    }
+    positions
+}
+
+fn bench_game_config(c: &mut Criterion, name: &str, game_config: &game_config::GameConfig<'_>) {
+    let kwg_path = "lexbin/CSW19.kwg";
+    let kwg = match std::fs::read(kwg_path) {
+        Ok(bytes) => kwg::Kwg::from_bytes_alloc(&bytes),
+        Err(err) => {
+            eprintln!(
+                "skipping {} benches, cannot read {}: {}",
+                name, kwg_path, err
+            );
+            return;
+        }
+    };
+    let klv = klv::Klv::from_bytes_alloc(klv::EMPTY_KLV_BYTES).unwrap();
+    let positions = collect_positions(game_config, &kwg, &klv, 10);
+
+    let walk_move_generator = movegen::KurniaMoveGenerator::new(game_config);
+    let mut letter_sets_move_generator = walk_move_generator.clone();
+    letter_sets_move_generator.build_letter_sets(&kwg);
+
+    // an empty rack generates no placements, leaving mostly the cross sets.
+    let mut group = c.benchmark_group(format!("{}_cross_sets", name));
+    for (variant, move_generator) in &[
+        ("walk", &walk_move_generator),
+        ("letter_sets", &letter_sets_move_generator),
+    ] {
+        let mut move_generator = (*move_generator).clone();
+        group.bench_function(BenchmarkId::from_parameter(variant), |b| {
+            b.iter(|| {
+                for position in &positions {
+                    move_generator.gen_place_moves_raw(
+                        &movegen::BoardSnapshot {
+                            board_tiles: &position.board_tiles,
+                            game_config,
+                            kwg: &kwg,
+                            klv: &klv,
+                        },
+                        &[],
+                        |_down, _lane, _idx, _word, _score, _rack_tally| true,
+                    );
+                }
+            })
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group(format!("{}_gen_moves", name));
+    group.sample_size(10);
+    for (variant, move_generator) in &[
+        ("walk", &walk_move_generator),
+        ("letter_sets", &letter_sets_move_generator),
+    ] {
+        let mut move_generator = (*move_generator).clone();
+        group.bench_function(BenchmarkId::from_parameter(variant), |b| {
+            b.iter(|| {
+                for position in &positions {
+                    move_generator.gen_moves_unfiltered(&movegen::GenMovesParams {
+                        board_snapshot: &movegen::BoardSnapshot {
+                            board_tiles: &position.board_tiles,
+                            game_config,
+                            kwg: &kwg,
+                            klv: &klv,
+                        },
+                        rack: &position.rack,
+                        max_gen: 1,
+                        always_include_pass: true,
+                        num_tiles_in_bag: None,
+                        constraints: None,
+                    });
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_classic(c: &mut Criterion) {
+    bench_game_config(
+        c,
+        "classic",
+        &game_config::make_common_english_game_config(),
+    );
+}
+
+criterion_group!(benches, bench_classic);
+criterion_main!(benches);
@@ -1,5 +1,7 @@
 // Copyright (C) 2020-2021 Andy Kurnia.
 
+use super::error;
+
 pub struct Tile<'a> {
     label: &'a str,
     blank_label: &'a str,
@@ -98,6 +100,13 @@ impl<'a> Alphabet<'a> {
     pub fn rack_score(&self, rack: &[u8]) -> i16 {
         rack.iter().map(|&t| self.score(t) as i16).sum::<i16>()
     }
+
+    pub fn rack_tally_score(&self, rack_tally: &[u8]) -> i16 {
+        (0u8..)
+            .zip(rack_tally)
+            .map(|(tile, &num)| num as i16 * self.score(tile) as i16)
+            .sum::<i16>()
+    }
 }
 
 pub struct WriteableRack<'a> {
@@ -438,6 +447,345 @@ pub fn make_spanish_alphabet<'a>() -> Alphabet<'a> {
     })
 }
 
+// https://en.wikipedia.org/wiki/Scrabble_letter_distributions#Catalan
+// L·L, NY and QU are single tiles
+pub fn make_catalan_alphabet<'a>() -> Alphabet<'a> {
+    Alphabet::new_static(StaticAlphabet {
+        tiles: &[
+            tile!("?", "?", 2, 0, 0),
+            tile!("A", "a", 12, 1, 1),
+            tile!("B", "b", 2, 3, 0),
+            tile!("C", "c", 3, 2, 0),
+            tile!("Ç", "ç", 1, 10, 0),
+            tile!("D", "d", 3, 2, 0),
+            tile!("E", "e", 13, 1, 1),
+            tile!("F", "f", 1, 4, 0),
+            tile!("G", "g", 2, 3, 0),
+            tile!("H", "h", 1, 8, 0),
+            tile!("I", "i", 8, 1, 1),
+            tile!("J", "j", 1, 8, 0),
+            tile!("L", "l", 4, 1, 0),
+            tile!("L·L", "l·l", 1, 10, 0),
+            tile!("M", "m", 3, 2, 0),
+            tile!("N", "n", 6, 1, 0),
+            tile!("NY", "ny", 1, 10, 0),
+            tile!("O", "o", 5, 1, 1),
+            tile!("P", "p", 2, 3, 0),
+            tile!("QU", "qu", 1, 8, 0),
+            tile!("R", "r", 8, 1, 0),
+            tile!("S", "s", 8, 1, 0),
+            tile!("T", "t", 5, 1, 0),
+            tile!("U", "u", 4, 1, 1),
+            tile!("V", "v", 1, 4, 0),
+            tile!("X", "x", 1, 10, 0),
+            tile!("Z", "z", 1, 8, 0),
+        ],
+        ..Default::default()
+    })
+}
+
+// https://en.wikipedia.org/wiki/Scrabble_letter_distributions#Danish
+pub fn make_danish_alphabet<'a>() -> Alphabet<'a> {
+    Alphabet::new_static(StaticAlphabet {
+        tiles: &[
+            tile!("?", "?", 2, 0, 0),
+            tile!("A", "a", 7, 1, 1),
+            tile!("B", "b", 4, 3, 0),
+            tile!("C", "c", 2, 8, 0),
+            tile!("D", "d", 5, 2, 0),
+            tile!("E", "e", 9, 1, 1),
+            tile!("F", "f", 3, 3, 0),
+            tile!("G", "g", 3, 3, 0),
+            tile!("H", "h", 2, 4, 0),
+            tile!("I", "i", 4, 3, 1),
+            tile!("J", "j", 2, 4, 0),
+            tile!("K", "k", 4, 3, 0),
+            tile!("L", "l", 5, 2, 0),
+            tile!("M", "m", 3, 3, 0),
+            tile!("N", "n", 6, 1, 0),
+            tile!("O", "o", 5, 2, 1),
+            tile!("P", "p", 2, 4, 0),
+            tile!("R", "r", 6, 1, 0),
+            tile!("S", "s", 5, 2, 0),
+            tile!("T", "t", 5, 2, 0),
+            tile!("U", "u", 3, 3, 1),
+            tile!("V", "v", 3, 3, 0),
+            tile!("X", "x", 1, 8, 0),
+            tile!("Y", "y", 2, 4, 1),
+            tile!("Z", "z", 1, 8, 0),
+            tile!("Æ", "æ", 2, 4, 1),
+            tile!("Ø", "ø", 2, 4, 1),
+            tile!("Å", "å", 2, 4, 1),
+        ],
+        ..Default::default()
+    })
+}
+
+// https://en.wikipedia.org/wiki/Scrabble_letter_distributions#Dutch
+pub fn make_dutch_alphabet<'a>() -> Alphabet<'a> {
+    Alphabet::new_static(StaticAlphabet {
+        tiles: &[
+            tile!("?", "?", 2, 0, 0),
+            tile!("A", "a", 6, 1, 1),
+            tile!("B", "b", 2, 3, 0),
+            tile!("C", "c", 2, 5, 0),
+            tile!("D", "d", 5, 2, 0),
+            tile!("E", "e", 18, 1, 1),
+            tile!("F", "f", 2, 4, 0),
+            tile!("G", "g", 3, 3, 0),
+            tile!("H", "h", 2, 4, 0),
+            tile!("I", "i", 4, 1, 1),
+            tile!("J", "j", 2, 4, 0),
+            tile!("K", "k", 3, 3, 0),
+            tile!("L", "l", 3, 3, 0),
+            tile!("M", "m", 3, 3, 0),
+            tile!("N", "n", 10, 1, 0),
+            tile!("O", "o", 6, 1, 1),
+            tile!("P", "p", 2, 3, 0),
+            tile!("Q", "q", 1, 10, 0),
+            tile!("R", "r", 5, 2, 0),
+            tile!("S", "s", 5, 2, 0),
+            tile!("T", "t", 5, 2, 0),
+            tile!("U", "u", 3, 4, 1),
+            tile!("V", "v", 2, 4, 0),
+            tile!("W", "w", 2, 5, 0),
+            tile!("X", "x", 1, 8, 0),
+            tile!("Y", "y", 1, 8, 1),
+            tile!("Z", "z", 2, 4, 0),
+        ],
+        ..Default::default()
+    })
+}
+
+// https://en.wikipedia.org/wiki/Scrabble_letter_distributions#Finnish
+pub fn make_finnish_alphabet<'a>() -> Alphabet<'a> {
+    Alphabet::new_static(StaticAlphabet {
+        tiles: &[
+            tile!("?", "?", 2, 0, 0),
+            tile!("A", "a", 10, 1, 1),
+            tile!("B", "b", 1, 8, 0),
+            tile!("C", "c", 1, 10, 0),
+            tile!("D", "d", 1, 7, 0),
+            tile!("E", "e", 8, 1, 1),
+            tile!("F", "f", 1, 8, 0),
+            tile!("G", "g", 1, 8, 0),
+            tile!("H", "h", 2, 4, 0),
+            tile!("I", "i", 10, 1, 1),
+            tile!("J", "j", 2, 4, 0),
+            tile!("K", "k", 5, 2, 0),
+            tile!("L", "l", 5, 2, 0),
+            tile!("M", "m", 3, 3, 0),
+            tile!("N", "n", 9, 1, 0),
+            tile!("O", "o", 5, 2, 1),
+            tile!("P", "p", 2, 4, 0),
+            tile!("R", "r", 2, 4, 0),
+            tile!("S", "s", 7, 1, 0),
+            tile!("T", "t", 9, 1, 0),
+            tile!("U", "u", 4, 3, 1),
+            tile!("V", "v", 2, 4, 0),
+            tile!("Y", "y", 2, 4, 1),
+            tile!("Ä", "ä", 5, 2, 1),
+            tile!("Ö", "ö", 1, 7, 1),
+        ],
+        ..Default::default()
+    })
+}
+
+// https://en.wikipedia.org/wiki/Scrabble_letter_distributions#Italian
+pub fn make_italian_alphabet<'a>() -> Alphabet<'a> {
+    Alphabet::new_static(StaticAlphabet {
+        tiles: &[
+            tile!("?", "?", 2, 0, 0),
+            tile!("A", "a", 14, 1, 1),
+            tile!("B", "b", 3, 5, 0),
+            tile!("C", "c", 6, 2, 0),
+            tile!("D", "d", 3, 5, 0),
+            tile!("E", "e", 11, 1, 1),
+            tile!("F", "f", 3, 5, 0),
+            tile!("G", "g", 2, 8, 0),
+            tile!("H", "h", 2, 8, 0),
+            tile!("I", "i", 12, 1, 1),
+            tile!("L", "l", 5, 3, 0),
+            tile!("M", "m", 5, 3, 0),
+            tile!("N", "n", 5, 3, 0),
+            tile!("O", "o", 15, 1, 1),
+            tile!("P", "p", 3, 5, 0),
+            tile!("Q", "q", 1, 10, 0),
+            tile!("R", "r", 6, 2, 0),
+            tile!("S", "s", 6, 2, 0),
+            tile!("T", "t", 6, 2, 0),
+            tile!("U", "u", 5, 3, 1),
+            tile!("V", "v", 3, 5, 0),
+            tile!("Z", "z", 2, 8, 0),
+        ],
+        ..Default::default()
+    })
+}
+
+// https://en.wikipedia.org/wiki/Scrabble_letter_distributions#Portuguese
+// Brazilian set, accents other than the cedilla are ignored
+pub fn make_portuguese_alphabet<'a>() -> Alphabet<'a> {
+    Alphabet::new_static(StaticAlphabet {
+        tiles: &[
+            tile!("?", "?", 3, 0, 0),
+            tile!("A", "a", 14, 1, 1),
+            tile!("B", "b", 3, 3, 0),
+            tile!("C", "c", 4, 2, 0),
+            tile!("Ç", "ç", 2, 3, 0),
+            tile!("D", "d", 5, 2, 0),
+            tile!("E", "e", 11, 1, 1),
+            tile!("F", "f", 2, 4, 0),
+            tile!("G", "g", 2, 4, 0),
+            tile!("H", "h", 2, 4, 0),
+            tile!("I", "i", 10, 1, 1),
+            tile!("J", "j", 2, 5, 0),
+            tile!("L", "l", 5, 2, 0),
+            tile!("M", "m", 6, 1, 0),
+            tile!("N", "n", 4, 3, 0),
+            tile!("O", "o", 10, 1, 1),
+            tile!("P", "p", 4, 2, 0),
+            tile!("Q", "q", 1, 6, 0),
+            tile!("R", "r", 6, 1, 0),
+            tile!("S", "s", 8, 1, 0),
+            tile!("T", "t", 5, 1, 0),
+            tile!("U", "u", 7, 1, 1),
+            tile!("V", "v", 2, 4, 0),
+            tile!("X", "x", 1, 8, 0),
+            tile!("Z", "z", 1, 8, 0),
+        ],
+        ..Default::default()
+    })
+}
+
+// https://en.wikipedia.org/wiki/Scrabble_letter_distributions#Slovene
+pub fn make_slovene_alphabet<'a>() -> Alphabet<'a> {
+    Alphabet::new_static(StaticAlphabet {
+        tiles: &[
+            tile!("?", "?", 2, 0, 0),
+            tile!("A", "a", 10, 1, 1),
+            tile!("B", "b", 2, 4, 0),
+            tile!("C", "c", 1, 8, 0),
+            tile!("Č", "č", 1, 5, 0),
+            tile!("D", "d", 4, 2, 0),
+            tile!("E", "e", 11, 1, 1),
+            tile!("F", "f", 1, 10, 0),
+            tile!("G", "g", 2, 4, 0),
+            tile!("H", "h", 1, 5, 0),
+            tile!("I", "i", 9, 1, 1),
+            tile!("J", "j", 4, 1, 0),
+            tile!("K", "k", 3, 3, 0),
+            tile!("L", "l", 4, 1, 0),
+            tile!("M", "m", 2, 3, 0),
+            tile!("N", "n", 7, 1, 0),
+            tile!("O", "o", 8, 1, 1),
+            tile!("P", "p", 2, 3, 0),
+            tile!("R", "r", 6, 1, 0),
+            tile!("S", "s", 6, 1, 0),
+            tile!("Š", "š", 1, 6, 0),
+            tile!("T", "t", 4, 1, 0),
+            tile!("U", "u", 2, 3, 1),
+            tile!("V", "v", 4, 2, 0),
+            tile!("Z", "z", 2, 4, 0),
+            tile!("Ž", "ž", 1, 8, 0),
+        ],
+        ..Default::default()
+    })
+}
+
+// https://en.wikipedia.org/wiki/Scrabble_letter_distributions#Swedish
+pub fn make_swedish_alphabet<'a>() -> Alphabet<'a> {
+    Alphabet::new_static(StaticAlphabet {
+        tiles: &[
+            tile!("?", "?", 2, 0, 0),
+            tile!("A", "a", 8, 1, 1),
+            tile!("B", "b", 2, 4, 0),
+            tile!("C", "c", 1, 8, 0),
+            tile!("D", "d", 5, 1, 0),
+            tile!("E", "e", 7, 1, 1),
+            tile!("F", "f", 2, 3, 0),
+            tile!("G", "g", 3, 2, 0),
+            tile!("H", "h", 2, 2, 0),
+            tile!("I", "i", 5, 1, 1),
+            tile!("J", "j", 1, 7, 0),
+            tile!("K", "k", 3, 2, 0),
+            tile!("L", "l", 5, 1, 0),
+            tile!("M", "m", 3, 2, 0),
+            tile!("N", "n", 6, 1, 0),
+            tile!("O", "o", 5, 2, 1),
+            tile!("P", "p", 2, 4, 0),
+            tile!("R", "r", 8, 1, 0),
+            tile!("S", "s", 8, 1, 0),
+            tile!("T", "t", 8, 1, 0),
+            tile!("U", "u", 3, 4, 1),
+            tile!("V", "v", 2, 3, 0),
+            tile!("X", "x", 1, 8, 0),
+            tile!("Y", "y", 1, 7, 1),
+            tile!("Z", "z", 1, 10, 0),
+            tile!("Å", "å", 2, 4, 1),
+            tile!("Ä", "ä", 2, 3, 1),
+            tile!("Ö", "ö", 2, 4, 1),
+        ],
+        ..Default::default()
+    })
+}
+
+pub fn make_alphabet_by_name<'a>(name: &str) -> Option<Alphabet<'a>> {
+    match name {
+        "catalan" => Some(make_catalan_alphabet()),
+        "danish" => Some(make_danish_alphabet()),
+        "dutch" => Some(make_dutch_alphabet()),
+        "english" => Some(make_english_alphabet()),
+        "finnish" => Some(make_finnish_alphabet()),
+        "french" => Some(make_french_alphabet()),
+        "german" => Some(make_german_alphabet()),
+        "hong_kong_english" => Some(make_hong_kong_english_alphabet()),
+        "italian" => Some(make_italian_alphabet()),
+        "norwegian" => Some(make_norwegian_alphabet()),
+        "polish" => Some(make_polish_alphabet()),
+        "portuguese" => Some(make_portuguese_alphabet()),
+        "slovene" => Some(make_slovene_alphabet()),
+        "spanish" => Some(make_spanish_alphabet()),
+        "super_english" => Some(make_super_english_alphabet()),
+        "swedish" => Some(make_swedish_alphabet()),
+        _ => None,
+    }
+}
+
+// One tile per line, blank first, each line is
+// label blank_label freq score is_vowel
+// such as "A a 9 1 1". Blank lines and #-comments are ignored.
+// This leaks the labels, so it should only be done once per process.
+pub fn make_alphabet_from_text(text: &str) -> error::Returns<Alphabet<'static>> {
+    let mut tiles = Vec::new();
+    for (line_num, line) in text.lines().enumerate() {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.is_empty() || fields[0].starts_with('#') {
+            continue;
+        }
+        if fields.len() != 5 {
+            return_error!(format!(
+                "line {}: need label, blank label, freq, score, is_vowel",
+                line_num + 1
+            ));
+        }
+        let parse_err = |_| error::new(format!("line {}: invalid number", line_num + 1));
+        tiles.push(Tile {
+            label: Box::leak(fields[0].into()),
+            blank_label: Box::leak(fields[1].into()),
+            freq: fields[2].parse().map_err(parse_err)?,
+            score: fields[3].parse().map_err(parse_err)?,
+            is_vowel: fields[4].parse::<u8>().map_err(parse_err)? != 0,
+        });
+    }
+    if tiles.len() < 2 {
+        return_error!("alphabet needs a blank and at least one letter".into());
+    }
+    Ok(Alphabet::new_static(StaticAlphabet {
+        tiles: Box::leak(tiles.into_boxed_slice()),
+        ..Default::default()
+    }))
+}
+
 pub struct AlphabetReader<'a> {
     supported_tiles: Box<[(u8, &'a [u8])]>,
     by_first_byte: [Option<(Option<u8>, usize, usize)>; 256],
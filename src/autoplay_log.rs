@@ -0,0 +1,800 @@
+// Copyright (C) 2020-2021 Andy Kurnia.
+
+use super::{alphabet, display, error, movegen};
+
+// autoplay logs are either csv, or a compact binary form of the same rows
+// where racks, leaves and plays are tiles instead of labels. the binary form
+// starts with a magic line, so readers can tell them apart.
+// integers are little-endian, tile lists are prefixed by their length.
+
+pub static LOG_MAGIC: &[u8] = b"wolges autoplay log 1\n";
+pub static GAMES_MAGIC: &[u8] = b"wolges autoplay games 1\n";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Bin,
+}
+
+impl std::str::FromStr for Format {
+    type Err = error::MyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "bin" => Ok(Self::Bin),
+            _ => Err(error::new(format!("invalid log format {:?}", s))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Log,
+    Games,
+}
+
+// one move. player and first are 0-based, they are "p1" etc in csv.
+pub struct LogRow {
+    pub player: u8,
+    pub game_id: String,
+    pub turn: u16,
+    pub rack: Vec<u8>,
+    pub play: movegen::Play,
+    pub score: i16,
+    pub total_score: i16,
+    pub tiles_played: u8,
+    pub leave: Vec<u8>,
+    // csv keeps 3 decimal places, so this should already be rounded to that.
+    pub equity: f32,
+    pub tiles_remaining: u16,
+    pub opp_score: i16,
+}
+
+impl Default for LogRow {
+    fn default() -> Self {
+        Self {
+            player: 0,
+            game_id: String::new(),
+            turn: 0,
+            rack: Vec::new(),
+            play: movegen::Play::Exchange {
+                tiles: [][..].into(),
+            },
+            score: 0,
+            total_score: 0,
+            tiles_played: 0,
+            leave: Vec::new(),
+            equity: 0.0,
+            tiles_remaining: 0,
+            opp_score: 0,
+        }
+    }
+}
+
+// one finished game.
+#[derive(Default)]
+pub struct GameRow {
+    pub game_id: String,
+    pub scores: Vec<i16>,
+    pub bingos: Vec<u16>,
+    pub first: u8,
+}
+
+pub fn round_equity(equity: f32) -> f32 {
+    // going through the string matches what csv readers get.
+    format!("{:.3}", equity).parse().unwrap()
+}
+
+pub fn fmt_play(alphabet: &alphabet::Alphabet, play: &movegen::Play, s: &mut String) {
+    use std::fmt::Write;
+    match play {
+        movegen::Play::Exchange { tiles } => {
+            if tiles.is_empty() {
+                s.push_str("(Pass)");
+            } else {
+                s.push_str("(exch ");
+                for &tile in tiles.iter() {
+                    s.push_str(alphabet.from_rack(tile).unwrap());
+                }
+                s.push(')');
+            }
+        }
+        movegen::Play::Place {
+            down,
+            lane,
+            idx,
+            word,
+            ..
+        } => {
+            if *down {
+                write!(s, "{}{} ", display::column(*lane), idx + 1).unwrap();
+            } else {
+                write!(s, "{}{} ", lane + 1, display::column(*idx)).unwrap();
+            }
+            for &tile in word.iter() {
+                if tile == 0 {
+                    s.push('.');
+                } else {
+                    s.push_str(alphabet.from_board(tile).unwrap());
+                }
+            }
+        }
+    }
+}
+
+fn fmt_tiles(alphabet: &alphabet::Alphabet, tiles: &[u8], s: &mut String) {
+    s.clear();
+    for &tile in tiles {
+        s.push_str(alphabet.from_rack(tile).unwrap());
+    }
+}
+
+fn fmt_player(player: u8) -> String {
+    format!("p{}", player as u32 + 1)
+}
+
+fn parse_player(s: &str) -> error::Returns<u8> {
+    match s.strip_prefix('p').and_then(|x| x.parse::<u8>().ok()) {
+        Some(x) if x > 0 => Ok(x - 1),
+        _ => Err(error::new(format!("invalid player {:?}", s)).into()),
+    }
+}
+
+fn parse_tiles(
+    alphabet_reader: &alphabet::AlphabetReader,
+    s: &str,
+    v: &mut Vec<u8>,
+) -> error::Returns<()> {
+    v.clear();
+    let sb = s.as_bytes();
+    let mut ix = 0;
+    while ix < sb.len() {
+        if let Some((tile, end_ix)) = alphabet_reader.next_tile(sb, ix) {
+            v.push(tile);
+            ix = end_ix;
+        } else {
+            return_error!(format!("invalid tiles {:?}", s));
+        }
+    }
+    Ok(())
+}
+
+fn parse_play(
+    rack_reader: &alphabet::AlphabetReader,
+    play_reader: &alphabet::AlphabetReader,
+    s: &str,
+    score: i16,
+    v: &mut Vec<u8>,
+) -> error::Returns<movegen::Play> {
+    if s == "(Pass)" {
+        return Ok(movegen::Play::Exchange {
+            tiles: [][..].into(),
+        });
+    }
+    if let Some(tiles) = s.strip_prefix("(exch ").and_then(|x| x.strip_suffix(')')) {
+        parse_tiles(rack_reader, tiles, v)?;
+        return Ok(movegen::Play::Exchange {
+            tiles: v[..].into(),
+        });
+    }
+    let (coord, word) = match s.split_once(' ') {
+        Some(x) => x,
+        None => {
+            return_error!(format!("invalid play {:?}", s));
+        }
+    };
+    let cb = coord.as_bytes();
+    let digits_len = cb.iter().take_while(|c| c.is_ascii_digit()).count();
+    let (down, row, col) = if digits_len > 0 {
+        (false, &coord[..digits_len], &coord[digits_len..])
+    } else {
+        let letters_len = cb.iter().take_while(|c| c.is_ascii_uppercase()).count();
+        (true, &coord[letters_len..], &coord[..letters_len])
+    };
+    let (row, col) = match (
+        row.parse::<i8>().ok().filter(|&x| x > 0),
+        display::str_to_column_usize(col.as_bytes()).filter(|&x| x < i8::MAX as usize),
+    ) {
+        (Some(row), Some(col)) => (row - 1, col as i8),
+        _ => {
+            return_error!(format!("invalid coordinate in {:?}", s));
+        }
+    };
+    v.clear();
+    let wb = word.as_bytes();
+    let mut ix = 0;
+    while ix < wb.len() {
+        if wb[ix] == b'.' {
+            v.push(0);
+            ix += 1;
+        } else if let Some((tile, end_ix)) = play_reader.next_tile(wb, ix) {
+            v.push(tile);
+            ix = end_ix;
+        } else {
+            return_error!(format!("invalid word in {:?}", s));
+        }
+    }
+    Ok(if down {
+        movegen::Play::Place {
+            down,
+            lane: col,
+            idx: row,
+            word: v[..].into(),
+            score,
+        }
+    } else {
+        movegen::Play::Place {
+            down,
+            lane: row,
+            idx: col,
+            word: v[..].into(),
+            score,
+        }
+    })
+}
+
+pub enum Writer<W: std::io::Write> {
+    Csv(Box<csv::Writer<W>>),
+    Bin(W),
+}
+
+impl<W: std::io::Write + 'static> Writer<W> {
+    pub fn new(format: Format, w: W) -> Self {
+        match format {
+            Format::Csv => Self::Csv(Box::new(csv::Writer::from_writer(w))),
+            Format::Bin => Self::Bin(w),
+        }
+    }
+
+    pub fn write_log_header(&mut self) -> error::Returns<()> {
+        match self {
+            Self::Csv(w) => w.serialize((
+                "playerID",
+                "gameID",
+                "turn",
+                "rack",
+                "play",
+                "score",
+                "totalscore",
+                "tilesplayed",
+                "leave",
+                "equity",
+                "tilesremaining",
+                "oppscore",
+            ))?,
+            Self::Bin(w) => w.write_all(LOG_MAGIC)?,
+        }
+        Ok(())
+    }
+
+    pub fn write_games_header(&mut self, num_players: u8) -> error::Returns<()> {
+        match self {
+            Self::Csv(w) => w.serialize((
+                "gameID",
+                (0..num_players)
+                    .map(|x| format!("{}_score", fmt_player(x)))
+                    .collect::<Box<[String]>>(),
+                (0..num_players)
+                    .map(|x| format!("{}_bingos", fmt_player(x)))
+                    .collect::<Box<[String]>>(),
+                "first",
+            ))?,
+            Self::Bin(w) => {
+                w.write_all(GAMES_MAGIC)?;
+                w.write_all(&[num_players])?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_log_row(
+        &mut self,
+        alphabet: &alphabet::Alphabet,
+        row: &LogRow,
+    ) -> error::Returns<()> {
+        match self {
+            Self::Csv(w) => {
+                let mut rack = String::new();
+                let mut play = String::new();
+                let mut leave = String::new();
+                fmt_tiles(alphabet, &row.rack, &mut rack);
+                fmt_play(alphabet, &row.play, &mut play);
+                fmt_tiles(alphabet, &row.leave, &mut leave);
+                w.serialize((
+                    fmt_player(row.player),
+                    &row.game_id,
+                    row.turn,
+                    rack,
+                    play,
+                    row.score,
+                    row.total_score,
+                    row.tiles_played,
+                    leave,
+                    format!("{:.3}", row.equity),
+                    row.tiles_remaining,
+                    row.opp_score,
+                ))?;
+            }
+            Self::Bin(w) => {
+                let mut buf = Vec::with_capacity(64);
+                buf.push(row.player);
+                put_bytes(&mut buf, row.game_id.as_bytes());
+                buf.extend_from_slice(&row.turn.to_le_bytes());
+                put_bytes(&mut buf, &row.rack);
+                match &row.play {
+                    movegen::Play::Exchange { tiles } => {
+                        buf.push(0);
+                        put_bytes(&mut buf, tiles);
+                    }
+                    movegen::Play::Place {
+                        down,
+                        lane,
+                        idx,
+                        word,
+                        ..
+                    } => {
+                        buf.push(1 + *down as u8);
+                        buf.push(*lane as u8);
+                        buf.push(*idx as u8);
+                        put_bytes(&mut buf, word);
+                    }
+                }
+                buf.extend_from_slice(&row.score.to_le_bytes());
+                buf.extend_from_slice(&row.total_score.to_le_bytes());
+                buf.push(row.tiles_played);
+                put_bytes(&mut buf, &row.leave);
+                buf.extend_from_slice(&row.equity.to_le_bytes());
+                buf.extend_from_slice(&row.tiles_remaining.to_le_bytes());
+                buf.extend_from_slice(&row.opp_score.to_le_bytes());
+                w.write_all(&buf)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write_game_row(&mut self, row: &GameRow) -> error::Returns<()> {
+        match self {
+            Self::Csv(w) => w.serialize((
+                &row.game_id,
+                &row.scores,
+                &row.bingos,
+                fmt_player(row.first),
+            ))?,
+            Self::Bin(w) => {
+                let mut buf = Vec::with_capacity(32);
+                put_bytes(&mut buf, row.game_id.as_bytes());
+                for score in row.scores.iter() {
+                    buf.extend_from_slice(&score.to_le_bytes());
+                }
+                for bingos in row.bingos.iter() {
+                    buf.extend_from_slice(&bingos.to_le_bytes());
+                }
+                buf.push(row.first);
+                w.write_all(&buf)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> error::Returns<W> {
+        match self {
+            Self::Csv(w) => Ok((*w).into_inner()?),
+            Self::Bin(w) => Ok(w),
+        }
+    }
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.push(bytes.len() as u8);
+    buf.extend_from_slice(bytes);
+}
+
+struct CountingReader<R> {
+    r: R,
+    pos: u64,
+}
+
+impl<R: std::io::BufRead> CountingReader<R> {
+    // like read_exact, but also counts what a failed read consumed.
+    fn read_exact(&mut self, buf: &mut [u8]) -> std::io::Result<()> {
+        let mut num_read = 0;
+        while num_read < buf.len() {
+            match self.r.read(&mut buf[num_read..]) {
+                Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => {
+                    num_read += n;
+                    self.pos += n as u64;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    fn bytes<const N: usize>(&mut self) -> std::io::Result<[u8; N]> {
+        let mut buf = [0u8; N];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn u8(&mut self) -> std::io::Result<u8> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn vec(&mut self, v: &mut Vec<u8>) -> std::io::Result<()> {
+        let len = self.u8()? as usize;
+        v.resize(len, 0);
+        self.read_exact(v)
+    }
+
+    fn at_eof(&mut self) -> std::io::Result<bool> {
+        Ok(self.r.fill_buf()?.is_empty())
+    }
+}
+
+enum Source<R> {
+    Csv(csv::Reader<R>, csv::StringRecord),
+    Bin(CountingReader<R>),
+}
+
+pub struct Reader<'a, R> {
+    source: Source<R>,
+    kind: Kind,
+    num_players: u8,
+    rack_reader: alphabet::AlphabetReader<'a>,
+    play_reader: alphabet::AlphabetReader<'a>,
+    buf: Vec<u8>,
+}
+
+impl<'a, R: std::io::BufRead> Reader<'a, R> {
+    // detects the format and kind, and consumes the header.
+    pub fn new(alphabet: &alphabet::Alphabet<'a>, mut r: R) -> error::Returns<Self> {
+        let head = r.fill_buf()?;
+        let (source, kind, num_players) = if head.starts_with(LOG_MAGIC) {
+            r.consume(LOG_MAGIC.len());
+            let r = CountingReader {
+                r,
+                pos: LOG_MAGIC.len() as u64,
+            };
+            (Source::Bin(r), Kind::Log, 0)
+        } else if head.starts_with(GAMES_MAGIC) {
+            r.consume(GAMES_MAGIC.len());
+            let mut r = CountingReader {
+                r,
+                pos: GAMES_MAGIC.len() as u64,
+            };
+            let num_players = r.u8()?;
+            (Source::Bin(r), Kind::Games, num_players)
+        } else {
+            let mut csv_reader = csv::ReaderBuilder::new().has_headers(true).from_reader(r);
+            let headers = csv_reader.headers()?;
+            let (kind, num_players) = match headers.get(0) {
+                Some("playerID") => (Kind::Log, 0),
+                Some("gameID") if headers.len() >= 2 => {
+                    (Kind::Games, ((headers.len() - 2) / 2) as u8)
+                }
+                _ => {
+                    return_error!("not an autoplay log".into());
+                }
+            };
+            (
+                Source::Csv(csv_reader, csv::StringRecord::new()),
+                kind,
+                num_players,
+            )
+        };
+        Ok(Self {
+            source,
+            kind,
+            num_players,
+            rack_reader: alphabet::AlphabetReader::new_for_racks(alphabet),
+            play_reader: alphabet::AlphabetReader::new_for_plays(alphabet),
+            buf: Vec::new(),
+        })
+    }
+
+    pub fn format(&self) -> Format {
+        match self.source {
+            Source::Csv(..) => Format::Csv,
+            Source::Bin(..) => Format::Bin,
+        }
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    // only meaningful for games.
+    pub fn num_players(&self) -> u8 {
+        self.num_players
+    }
+
+    // bytes consumed so far, including the header and any failed row.
+    pub fn position(&self) -> u64 {
+        match &self.source {
+            Source::Csv(r, _) => r.position().byte(),
+            Source::Bin(r) => r.pos,
+        }
+    }
+
+    // returns false at the end.
+    pub fn read_log_row(&mut self, row: &mut LogRow) -> error::Returns<bool> {
+        if self.kind != Kind::Log {
+            return_error!("not a log".into());
+        }
+        match &mut self.source {
+            Source::Csv(r, record) => {
+                if !r.read_record(record)? {
+                    return Ok(false);
+                }
+                if record.len() != 12 {
+                    return_error!(format!("invalid log row {:?}", record));
+                }
+                row.player = parse_player(&record[0])?;
+                row.game_id.clear();
+                row.game_id.push_str(&record[1]);
+                row.turn = record[2].parse()?;
+                parse_tiles(&self.rack_reader, &record[3], &mut row.rack)?;
+                row.score = record[5].parse()?;
+                row.play = parse_play(
+                    &self.rack_reader,
+                    &self.play_reader,
+                    &record[4],
+                    row.score,
+                    &mut self.buf,
+                )?;
+                row.total_score = record[6].parse()?;
+                row.tiles_played = record[7].parse()?;
+                parse_tiles(&self.rack_reader, &record[8], &mut row.leave)?;
+                row.equity = record[9].parse()?;
+                row.tiles_remaining = record[10].parse()?;
+                row.opp_score = record[11].parse()?;
+            }
+            Source::Bin(r) => {
+                if r.at_eof()? {
+                    return Ok(false);
+                }
+                row.player = r.u8()?;
+                r.vec(&mut self.buf)?;
+                row.game_id.clear();
+                row.game_id.push_str(std::str::from_utf8(&self.buf)?);
+                row.turn = u16::from_le_bytes(r.bytes()?);
+                r.vec(&mut row.rack)?;
+                let play_kind = r.u8()?;
+                let (lane, idx) = if play_kind == 0 {
+                    (0, 0)
+                } else {
+                    (r.u8()? as i8, r.u8()? as i8)
+                };
+                r.vec(&mut self.buf)?;
+                row.score = i16::from_le_bytes(r.bytes()?);
+                row.play = match play_kind {
+                    0 => movegen::Play::Exchange {
+                        tiles: self.buf[..].into(),
+                    },
+                    1 | 2 => movegen::Play::Place {
+                        down: play_kind == 2,
+                        lane,
+                        idx,
+                        word: self.buf[..].into(),
+                        score: row.score,
+                    },
+                    _ => {
+                        return_error!(format!("invalid play kind {}", play_kind));
+                    }
+                };
+                row.total_score = i16::from_le_bytes(r.bytes()?);
+                row.tiles_played = r.u8()?;
+                r.vec(&mut row.leave)?;
+                row.equity = f32::from_le_bytes(r.bytes()?);
+                row.tiles_remaining = u16::from_le_bytes(r.bytes()?);
+                row.opp_score = i16::from_le_bytes(r.bytes()?);
+            }
+        }
+        Ok(true)
+    }
+
+    // returns false at the end.
+    pub fn read_game_row(&mut self, row: &mut GameRow) -> error::Returns<bool> {
+        if self.kind != Kind::Games {
+            return_error!("not a games log".into());
+        }
+        let num_players = self.num_players as usize;
+        row.scores.clear();
+        row.bingos.clear();
+        match &mut self.source {
+            Source::Csv(r, record) => {
+                if !r.read_record(record)? {
+                    return Ok(false);
+                }
+                if record.len() != 2 * num_players + 2 {
+                    return_error!(format!("invalid games row {:?}", record));
+                }
+                row.game_id.clear();
+                row.game_id.push_str(&record[0]);
+                for i in 0..num_players {
+                    row.scores.push(record[1 + i].parse()?);
+                    row.bingos.push(record[1 + num_players + i].parse()?);
+                }
+                row.first = parse_player(&record[2 * num_players + 1])?;
+            }
+            Source::Bin(r) => {
+                if r.at_eof()? {
+                    return Ok(false);
+                }
+                r.vec(&mut self.buf)?;
+                row.game_id.clear();
+                row.game_id.push_str(std::str::from_utf8(&self.buf)?);
+                for _ in 0..num_players {
+                    row.scores.push(i16::from_le_bytes(r.bytes()?));
+                }
+                for _ in 0..num_players {
+                    row.bingos.push(u16::from_le_bytes(r.bytes()?));
+                }
+                row.first = r.u8()?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game_config;
+    use super::*;
+
+    fn log_rows() -> Vec<LogRow> {
+        vec![
+            LogRow {
+                player: 0,
+                game_id: "abc".into(),
+                turn: 1,
+                rack: vec![0, 1, 5, 8, 9, 20, 26],
+                play: movegen::Play::Place {
+                    down: false,
+                    lane: 7,
+                    idx: 6,
+                    word: [8, 9, 0x81][..].into(),
+                    score: 12,
+                },
+                score: 12,
+                total_score: 12,
+                tiles_played: 3,
+                leave: vec![5, 20, 26, 1],
+                equity: round_equity(15.4321),
+                tiles_remaining: 86,
+                opp_score: 0,
+            },
+            LogRow {
+                player: 1,
+                game_id: "abc".into(),
+                turn: 2,
+                rack: vec![17, 17, 21, 22, 22, 23, 24],
+                play: movegen::Play::Exchange {
+                    tiles: [17, 17, 22][..].into(),
+                },
+                score: 0,
+                total_score: 0,
+                tiles_played: 0,
+                leave: vec![21, 22, 23, 24],
+                equity: round_equity(-3.25),
+                tiles_remaining: 86,
+                opp_score: 12,
+            },
+        ]
+    }
+
+    fn write_log(alphabet: &alphabet::Alphabet, format: Format, rows: &[LogRow]) -> Vec<u8> {
+        let mut writer = Writer::new(format, Vec::new());
+        writer.write_log_header().unwrap();
+        for row in rows {
+            writer.write_log_row(alphabet, row).unwrap();
+        }
+        writer.into_inner().unwrap()
+    }
+
+    fn read_log(alphabet: &alphabet::Alphabet, bytes: &[u8]) -> Vec<LogRow> {
+        let mut reader = Reader::new(alphabet, bytes).unwrap();
+        let mut rows = Vec::new();
+        let mut row = LogRow::default();
+        while reader.read_log_row(&mut row).unwrap() {
+            rows.push(std::mem::take(&mut row));
+        }
+        rows
+    }
+
+    // reads all rows, returning how the first failure left the reader.
+    fn read_log_until_error(alphabet: &alphabet::Alphabet, bytes: &[u8]) -> Option<u64> {
+        let mut reader = Reader::new(alphabet, bytes).unwrap();
+        let mut row = LogRow::default();
+        loop {
+            match reader.read_log_row(&mut row) {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(_) => return Some(reader.position()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_log_round_trip() {
+        let game_config = game_config::make_common_english_game_config();
+        let alphabet = game_config.alphabet();
+        let csv = write_log(alphabet, Format::Csv, &log_rows());
+        let bin = write_log(alphabet, Format::Bin, &read_log(alphabet, &csv));
+        assert!(bin.starts_with(LOG_MAGIC));
+        assert_eq!(
+            write_log(alphabet, Format::Csv, &read_log(alphabet, &bin)),
+            csv
+        );
+    }
+
+    #[test]
+    fn test_games_round_trip() {
+        let row = GameRow {
+            game_id: "1f-0-2".into(),
+            scores: vec![432, -5],
+            bingos: vec![2, 0],
+            first: 1,
+        };
+        let mut csv = Writer::new(Format::Csv, Vec::new());
+        csv.write_games_header(2).unwrap();
+        csv.write_game_row(&row).unwrap();
+        let csv = csv.into_inner().unwrap();
+        let game_config = game_config::make_common_english_game_config();
+        let mut reader = Reader::new(game_config.alphabet(), &csv[..]).unwrap();
+        assert!(reader.kind() == Kind::Games);
+        assert_eq!(reader.num_players(), 2);
+        let mut read_row = GameRow::default();
+        assert!(reader.read_game_row(&mut read_row).unwrap());
+        assert!(!reader.read_game_row(&mut GameRow::default()).unwrap());
+        let mut bin = Writer::new(Format::Bin, Vec::new());
+        bin.write_games_header(2).unwrap();
+        bin.write_game_row(&read_row).unwrap();
+        let bin = bin.into_inner().unwrap();
+        let mut reader = Reader::new(game_config.alphabet(), &bin[..]).unwrap();
+        let mut read_row = GameRow::default();
+        assert!(reader.read_game_row(&mut read_row).unwrap());
+        assert_eq!(read_row.game_id, row.game_id);
+        assert_eq!(read_row.scores, row.scores);
+        assert_eq!(read_row.bingos, row.bingos);
+        assert_eq!(read_row.first, row.first);
+        assert_eq!(reader.position(), bin.len() as u64);
+    }
+
+    #[test]
+    fn test_torn_tail() {
+        let game_config = game_config::make_common_english_game_config();
+        let alphabet = game_config.alphabet();
+        for &format in &[Format::Csv, Format::Bin] {
+            let rows = log_rows();
+            let first_row_len = write_log(alphabet, format, &rows[..1]).len();
+            let bytes = write_log(alphabet, format, &rows);
+            // a row cut short fails only at the end, if at all.
+            for len in first_row_len..bytes.len() {
+                if let Some(position) = read_log_until_error(alphabet, &bytes[..len]) {
+                    assert_eq!(position, len as u64);
+                }
+            }
+            assert_eq!(read_log_until_error(alphabet, &bytes), None);
+        }
+    }
+
+    #[test]
+    fn test_invalid_row_is_not_torn() {
+        let game_config = game_config::make_common_english_game_config();
+        let alphabet = game_config.alphabet();
+        let rows = log_rows();
+        let mut csv = write_log(alphabet, Format::Csv, &rows);
+        let header_len = write_log(alphabet, Format::Csv, &[]).len();
+        // player "p1" becomes "x1".
+        csv[header_len] = b'x';
+        assert!(read_log_until_error(alphabet, &csv).unwrap() < csv.len() as u64);
+        let mut bin = write_log(alphabet, Format::Bin, &rows);
+        // the first byte of the first game id is not utf-8.
+        bin[LOG_MAGIC.len() + 2] = 0xff;
+        assert!(read_log_until_error(alphabet, &bin).unwrap() < bin.len() as u64);
+    }
+}
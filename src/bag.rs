@@ -39,6 +39,12 @@ impl Bag {
         }
     }
 
+    // put back the tiles where they are drawn last, so the rest of the bag is
+    // drawn in the same order as if they had not been exchanged.
+    pub fn put_back_at_bottom(&mut self, tiles: &[u8]) {
+        self.0.splice(0..0, tiles.iter().copied());
+    }
+
     // put back the tiles in random order. keep the rest of the bag in the same order.
     pub fn put_back(&mut self, mut rng: &mut dyn RngCore, tiles: &[u8]) {
         let mut num_new_tiles = tiles.len();
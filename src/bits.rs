@@ -0,0 +1,43 @@
+// Copyright (C) 2020-2021 Andy Kurnia.
+
+// A set of tiles, bit n = tile n.
+// u64 fits alphabets of up to 64 tiles (including the blank), u128 up to 128.
+pub trait Bits:
+    Copy
+    + Eq
+    + From<bool>
+    + std::ops::BitAnd<Output = Self>
+    + std::ops::BitAndAssign
+    + std::ops::BitOr<Output = Self>
+    + std::ops::BitOrAssign
+    + std::ops::Not<Output = Self>
+    + std::ops::Shl<u8, Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const NUM_BITS: usize;
+
+    fn count_ones(self) -> u32;
+}
+
+impl Bits for u64 {
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+    const NUM_BITS: usize = 64;
+
+    #[inline(always)]
+    fn count_ones(self) -> u32 {
+        u64::count_ones(self)
+    }
+}
+
+impl Bits for u128 {
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+    const NUM_BITS: usize = 128;
+
+    #[inline(always)]
+    fn count_ones(self) -> u32 {
+        u128::count_ones(self)
+    }
+}
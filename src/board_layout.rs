@@ -43,7 +43,10 @@ pub struct StaticBoardLayout {
     dim: matrix::Dim,
     star_row: i8,
     star_col: i8,
+    blocked: Box<[bool]>,                   // empty defaults to nothing blocked
+    start_squares: Option<Box<[(i8, i8)]>>, // None means just the star
     transposed_premiums: Box<[Premium]>,
+    transposed_blocked: Box<[bool]>,
     danger_star_across: Box<[bool]>,
     danger_star_down: Box<[bool]>,
     is_symmetric: bool,
@@ -54,16 +57,27 @@ pub enum BoardLayout {
 }
 
 impl BoardLayout {
-    pub fn new_static(x: StaticBoardLayout) -> Self {
+    pub fn new_static(mut x: StaticBoardLayout) -> Self {
         let rows_times_cols = (x.dim.rows as isize * x.dim.cols as isize) as usize;
+        if x.blocked.is_empty() {
+            x.blocked = vec![false; rows_times_cols].into_boxed_slice();
+        }
+        let start_squares = x
+            .start_squares
+            .take()
+            .unwrap_or_else(|| Box::new([(x.star_row, x.star_col)]));
         let mut transposed_premiums = Vec::with_capacity(rows_times_cols);
+        let mut transposed_blocked = Vec::with_capacity(rows_times_cols);
         for col in 0..x.dim.cols {
             for row in 0..x.dim.rows {
                 transposed_premiums.push(x.premiums[x.dim.at_row_col(row, col)]);
+                transposed_blocked.push(x.blocked[x.dim.at_row_col(row, col)]);
             }
         }
+        // without a start square there is no star to be dangerous.
+        let has_star = !start_squares.is_empty();
         let mut danger_star_across = vec![false; x.dim.cols as usize];
-        if x.star_row > 0 {
+        if has_star && x.star_row > 0 {
             let range_start = ((x.star_row as isize - 1) * x.dim.cols as isize) as usize;
             (0..)
                 .zip(x.premiums[range_start..range_start + x.dim.cols as usize].iter())
@@ -73,7 +87,7 @@ impl BoardLayout {
                     }
                 });
         }
-        if x.star_row < x.dim.rows - 1 {
+        if has_star && x.star_row < x.dim.rows - 1 {
             let range_start = ((x.star_row as isize + 1) * x.dim.cols as isize) as usize;
             (0..)
                 .zip(x.premiums[range_start..range_start + x.dim.cols as usize].iter())
@@ -84,7 +98,7 @@ impl BoardLayout {
                 });
         }
         let mut danger_star_down = vec![false; x.dim.rows as usize];
-        if x.star_col > 0 {
+        if has_star && x.star_col > 0 {
             let range_start = ((x.star_col as isize - 1) * x.dim.rows as isize) as usize;
             (0..)
                 .zip(transposed_premiums[range_start..range_start + x.dim.rows as usize].iter())
@@ -94,7 +108,7 @@ impl BoardLayout {
                     }
                 });
         }
-        if x.star_col < x.dim.cols - 1 {
+        if has_star && x.star_col < x.dim.cols - 1 {
             let range_start = ((x.star_col as isize + 1) * x.dim.rows as isize) as usize;
             (0..)
                 .zip(transposed_premiums[range_start..range_start + x.dim.rows as usize].iter())
@@ -104,24 +118,69 @@ impl BoardLayout {
                     }
                 });
         }
+        let is_symmetric = x.dim.rows == x.dim.cols
+            && start_squares
+                .iter()
+                .all(|&(row, col)| start_squares.contains(&(col, row)))
+            && (0..x.dim.rows).all(|row| {
+                (0..row).all(|col| {
+                    let p1 = x.premiums[x.dim.at_row_col(row, col)];
+                    let p2 = x.premiums[x.dim.at_row_col(col, row)];
+                    p1.word_multiplier == p2.word_multiplier
+                        && p1.tile_multiplier == p2.tile_multiplier
+                        && x.blocked[x.dim.at_row_col(row, col)]
+                            == x.blocked[x.dim.at_row_col(col, row)]
+                })
+            });
         Self::Static(StaticBoardLayout {
+            start_squares: Some(start_squares),
             transposed_premiums: transposed_premiums.into_boxed_slice(),
+            transposed_blocked: transposed_blocked.into_boxed_slice(),
             danger_star_across: danger_star_across.into_boxed_slice(),
             danger_star_down: danger_star_down.into_boxed_slice(),
-            is_symmetric: x.dim.rows == x.dim.cols
-                && x.star_row == x.star_col
-                && (0..x.dim.rows).all(|row| {
-                    (0..row).all(|col| {
-                        let p1 = x.premiums[x.dim.at_row_col(row, col)];
-                        let p2 = x.premiums[x.dim.at_row_col(col, row)];
-                        p1.word_multiplier == p2.word_multiplier
-                            && p1.tile_multiplier == p2.tile_multiplier
-                    })
-                }),
+            is_symmetric,
             ..x
         })
     }
 
+    // Squares are (row, col). Blocked squares cannot hold tiles and split
+    // words like the board edge does.
+    pub fn with_blocked_squares(self, blocked_squares: &[(i8, i8)]) -> Self {
+        match self {
+            BoardLayout::Static(mut x) => {
+                let mut blocked = vec![false; x.blocked.len()];
+                for &(row, col) in blocked_squares {
+                    blocked[x.dim.at_row_col(row, col)] = true;
+                }
+                x.blocked = blocked.into_boxed_slice();
+                x.start_squares = x.start_squares.take().map(|start_squares| {
+                    start_squares
+                        .iter()
+                        .filter(|&&(row, col)| !x.blocked[x.dim.at_row_col(row, col)])
+                        .copied()
+                        .collect()
+                });
+                Self::new_static(x)
+            }
+        }
+    }
+
+    // The first move must cover one of these squares (row, col).
+    // If empty, the first move may be anywhere.
+    // The first square is the star for the purposes of danger_star.
+    pub fn with_start_squares(self, start_squares: &[(i8, i8)]) -> Self {
+        match self {
+            BoardLayout::Static(mut x) => {
+                if let Some(&(row, col)) = start_squares.first() {
+                    x.star_row = row;
+                    x.star_col = col;
+                }
+                x.start_squares = Some(start_squares.into());
+                Self::new_static(x)
+            }
+        }
+    }
+
     #[inline(always)]
     pub fn dim(&self) -> matrix::Dim {
         match self {
@@ -150,6 +209,38 @@ impl BoardLayout {
         }
     }
 
+    #[inline(always)]
+    pub fn blocked(&self) -> &[bool] {
+        match self {
+            BoardLayout::Static(x) => &x.blocked,
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_blocked(&self, row: i8, col: i8) -> bool {
+        self.blocked()[self.dim().at_row_col(row, col)]
+    }
+
+    // empty if the first move may be anywhere
+    #[inline(always)]
+    pub fn start_squares(&self) -> &[(i8, i8)] {
+        match self {
+            BoardLayout::Static(x) => x.start_squares.as_deref().unwrap_or(&[]),
+        }
+    }
+
+    #[inline(always)]
+    pub fn is_start_square(&self, row: i8, col: i8) -> bool {
+        self.start_squares().contains(&(row, col))
+    }
+
+    #[inline(always)]
+    pub fn transposed_blocked(&self) -> &[bool] {
+        match self {
+            BoardLayout::Static(x) => &x.transposed_blocked,
+        }
+    }
+
     #[inline(always)]
     pub fn transposed_premiums(&self) -> &[Premium] {
         match self {
@@ -174,7 +265,8 @@ impl BoardLayout {
     // This should return false if any of these is true:
     // - dim.rows != dim.cols
     // - exists (r,c) premium at (r,c) != premium at (c,r)
-    // - star_row != star_col
+    // - exists (r,c) blocked at (r,c) != blocked at (c,r)
+    // - exists (r,c) in start_squares but (c,r) is not
     #[inline(always)]
     pub fn is_symmetric(&self) -> bool {
         match self {
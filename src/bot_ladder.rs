@@ -0,0 +1,110 @@
+// Copyright (C) 2020-2021 Andy Kurnia.
+
+use super::{klv, kwg, tournament};
+
+// From weakest to strongest. A level may:
+// - only know the words in a smaller word list (such as common words),
+// - ignore leaves and only go for score,
+// - pick any of the top n moves instead of the top move.
+// Ratings are not set here, calibrate them with a ladder run.
+pub struct Level {
+    pub name: &'static str,
+    pub limited_vocab: bool,
+    pub use_leaves: bool,
+    pub top_n: usize,
+}
+
+pub static LEVELS: &[Level] = &[
+    Level {
+        name: "level1",
+        limited_vocab: true,
+        use_leaves: false,
+        top_n: 8,
+    },
+    Level {
+        name: "level2",
+        limited_vocab: true,
+        use_leaves: false,
+        top_n: 4,
+    },
+    Level {
+        name: "level3",
+        limited_vocab: true,
+        use_leaves: false,
+        top_n: 2,
+    },
+    Level {
+        name: "level4",
+        limited_vocab: true,
+        use_leaves: false,
+        top_n: 1,
+    },
+    Level {
+        name: "level5",
+        limited_vocab: true,
+        use_leaves: true,
+        top_n: 1,
+    },
+    Level {
+        name: "level6",
+        limited_vocab: false,
+        use_leaves: false,
+        top_n: 2,
+    },
+    Level {
+        name: "level7",
+        limited_vocab: false,
+        use_leaves: false,
+        top_n: 1,
+    },
+    Level {
+        name: "level8",
+        limited_vocab: false,
+        use_leaves: true,
+        top_n: 2,
+    },
+    Level {
+        name: "level9",
+        limited_vocab: false,
+        use_leaves: true,
+        top_n: 1,
+    },
+];
+
+impl Level {
+    pub fn make_bot(
+        &self,
+        kwg: &std::sync::Arc<kwg::Kwg>,
+        vocab_kwg: &std::sync::Arc<kwg::Kwg>,
+        klv: &std::sync::Arc<klv::Klv>,
+        no_klv: &std::sync::Arc<klv::Klv>,
+    ) -> tournament::Bot {
+        tournament::Bot {
+            name: self.name.into(),
+            kwg: std::sync::Arc::clone(kwg),
+            klv: std::sync::Arc::clone(if self.use_leaves { klv } else { no_klv }),
+            filter: if self.limited_vocab {
+                tournament::Filter::LimitedVocab(std::sync::Arc::clone(vocab_kwg))
+            } else {
+                tournament::Filter::Unfiltered
+            },
+            picker: if self.top_n > 1 {
+                tournament::Picker::RandomTopN(self.top_n)
+            } else {
+                tournament::Picker::Hasty
+            },
+        }
+    }
+}
+
+// the rating difference implied by a score rate (wins plus half the draws),
+// under the usual logistic model.
+pub fn elo_difference(score_rate: f64) -> f64 {
+    400.0 * (score_rate / (1.0 - score_rate)).log10()
+}
+
+// how much elo_difference changes per unit of score rate, to turn the
+// standard error of a score rate into that of a rating difference.
+pub fn elo_difference_slope(score_rate: f64) -> f64 {
+    400.0 / (std::f64::consts::LN_10 * score_rate * (1.0 - score_rate))
+}
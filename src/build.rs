@@ -1,6 +1,6 @@
 // Copyright (C) 2020-2021 Andy Kurnia.
 
-use super::{bites, error, fash};
+use super::{bites, error, fash, kwg};
 
 // Unconfirmed entries.
 // Memory wastage notes:
@@ -144,7 +144,9 @@ fn gen_machine_drowwords(machine_words: &[bites::Bites]) -> Box<[bites::Bites]>
         }
     }
     drop(reverse_buffer);
-    let mut machine_drowwords = machine_drowword_set.into_iter().collect::<Box<_>>();
+    let mut machine_drowwords = machine_drowword_set
+        .into_iter()
+        .collect::<Box<[bites::Bites]>>();
     machine_drowwords.sort_unstable();
     machine_drowwords
 }
@@ -160,7 +162,9 @@ pub fn make_alphagrams(machine_words: &[bites::Bites]) -> Box<[bites::Bites]> {
         machine_dorws_set.insert(rearrange_buffer[..].into());
     }
     drop(rearrange_buffer);
-    let mut machine_dorws = machine_dorws_set.into_iter().collect::<Box<_>>();
+    let mut machine_dorws = machine_dorws_set
+        .into_iter()
+        .collect::<Box<[bites::Bites]>>();
     machine_dorws.sort_unstable();
     machine_dorws
 }
@@ -372,3 +376,142 @@ pub fn build(
 
     Ok(states_defragger.to_vec(build_format, dawg_start_state, gaddag_start_state)[..].into())
 }
+
+// Reads back the sorted machine words from the dawg part of a kwg.
+// For a kad, these are the alphagrams.
+pub fn read_machine_words_from_kwg(kwg: &kwg::Kwg) -> Box<[bites::Bites]> {
+    struct Env<'a> {
+        kwg: &'a kwg::Kwg,
+        word: &'a mut Vec<u8>,
+        machine_words: &'a mut Vec<bites::Bites>,
+    }
+    fn iter(env: &mut Env, mut p: i32) {
+        loop {
+            let node = env.kwg[p];
+            env.word.push(node.tile());
+            if node.accepts() {
+                env.machine_words.push(env.word[..].into());
+            }
+            if node.arc_index() != 0 {
+                iter(env, node.arc_index());
+            }
+            env.word.pop();
+            if node.is_end() {
+                break;
+            }
+            p += 1;
+        }
+    }
+    let mut machine_words = Vec::new();
+    let dawg_root = kwg[0].arc_index();
+    if dawg_root != 0 {
+        iter(
+            &mut Env {
+                kwg,
+                word: &mut Vec::new(),
+                machine_words: &mut machine_words,
+            },
+            dawg_root,
+        );
+    }
+    machine_words.into_boxed_slice()
+}
+
+// All three inputs must be sorted and deduplicated.
+// Added words already present and removed words not present are ignored.
+// Removal wins if a word is both added and removed.
+pub fn edit_machine_words(
+    machine_words: &[bites::Bites],
+    added_machine_words: &[bites::Bites],
+    removed_machine_words: &[bites::Bites],
+) -> Box<[bites::Bites]> {
+    let mut ret = Vec::with_capacity(machine_words.len() + added_machine_words.len());
+    let mut i = 0;
+    let mut j = 0;
+    let mut k = 0;
+    loop {
+        let next_word = match (machine_words.get(i), added_machine_words.get(j)) {
+            (Some(a), Some(b)) => match a.cmp(b) {
+                std::cmp::Ordering::Less => {
+                    i += 1;
+                    a
+                }
+                std::cmp::Ordering::Greater => {
+                    j += 1;
+                    b
+                }
+                std::cmp::Ordering::Equal => {
+                    i += 1;
+                    j += 1;
+                    a
+                }
+            },
+            (Some(a), None) => {
+                i += 1;
+                a
+            }
+            (None, Some(b)) => {
+                j += 1;
+                b
+            }
+            (None, None) => break,
+        };
+        while k < removed_machine_words.len() && removed_machine_words[k] < *next_word {
+            k += 1;
+        }
+        if k < removed_machine_words.len() && removed_machine_words[k] == *next_word {
+            continue;
+        }
+        ret.push(next_word.clone());
+    }
+    ret.into_boxed_slice()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_machine_words(words: &[&[u8]]) -> Box<[bites::Bites]> {
+        words.iter().map(|&word| word.into()).collect()
+    }
+
+    #[test]
+    fn test_edit_machine_words() {
+        let edited = edit_machine_words(
+            &to_machine_words(&[&[1], &[2], &[4]]),
+            &to_machine_words(&[&[3], &[4], &[5]]),
+            &to_machine_words(&[&[2], &[5], &[6]]),
+        );
+        assert_eq!(edited, to_machine_words(&[&[1], &[3], &[4]]));
+    }
+
+    #[test]
+    fn test_edit_machine_words_round_trip() {
+        let machine_words = to_machine_words(&[&[1, 2], &[1, 2, 3], &[2, 1]]);
+        let kwg =
+            kwg::Kwg::from_bytes_alloc(&build(BuildFormat::DawgOnly, &machine_words).unwrap());
+        assert_eq!(read_machine_words_from_kwg(&kwg), machine_words);
+        let edited = edit_machine_words(
+            &read_machine_words_from_kwg(&kwg),
+            &to_machine_words(&[&[1]]),
+            &to_machine_words(&[&[1, 2, 3]]),
+        );
+        assert_eq!(edited, to_machine_words(&[&[1], &[1, 2], &[2, 1]]));
+    }
+
+    #[test]
+    fn test_edit_alphagrams_keeps_surviving_anagrams() {
+        // ATE EAT TEA, adding TAE and removing TEA leaves AET for the others.
+        let machine_words = to_machine_words(&[&[1, 20, 5], &[5, 1, 20], &[20, 5, 1]]);
+        let kwg = kwg::Kwg::from_bytes_alloc(&build(BuildFormat::Gaddawg, &machine_words).unwrap());
+        let edited = edit_machine_words(
+            &read_machine_words_from_kwg(&kwg),
+            &to_machine_words(&[&[20, 1, 5]]),
+            &to_machine_words(&[&[20, 5, 1]]),
+        );
+        assert_eq!(make_alphagrams(&edited), to_machine_words(&[&[1, 5, 20]]));
+        // removing all of them removes the alphagram.
+        let edited = edit_machine_words(&edited, &[], &edited);
+        assert!(make_alphagrams(&edited).is_empty());
+    }
+}
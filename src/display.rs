@@ -4,7 +4,10 @@ use super::{alphabet, board_layout, game_config, game_state, game_timers};
 
 #[inline(always)]
 pub fn empty_label(board_layout: &board_layout::BoardLayout, row: i8, col: i8) -> &'static str {
-    if row == board_layout.star_row() && col == board_layout.star_col() {
+    if board_layout.is_blocked(row, col) {
+        return "#";
+    }
+    if board_layout.is_start_square(row, col) {
         return "*";
     }
     let premium = board_layout.premiums()[board_layout.dim().at_row_col(row, col)];
@@ -176,7 +176,7 @@ impl<'a> EndgameSolver<'a> {
         Self {
             game_config,
             kwg,
-            klv: Box::new(klv::Klv::from_bytes_alloc(klv::EMPTY_KLV_BYTES)),
+            klv: Box::new(klv::Klv::from_bytes_alloc(klv::EMPTY_KLV_BYTES).unwrap()),
             board_tiles: Vec::new(),
             racks: [Vec::new(), Vec::new()],
             rack_scores: [0, 0],
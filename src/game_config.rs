@@ -75,6 +75,13 @@ impl<'a> GameConfig<'a> {
             GameConfig::Static(x) => &x.game_rules,
         }
     }
+
+    // for custom board shapes, see BoardLayout::with_blocked_squares.
+    pub fn with_board_layout(self, board_layout: board_layout::BoardLayout) -> Self {
+        match self {
+            GameConfig::Static(x) => GameConfig::Static(StaticGameConfig { board_layout, ..x }),
+        }
+    }
 }
 
 pub fn make_common_english_game_config<'a>() -> GameConfig<'a> {
@@ -229,3 +236,215 @@ pub fn make_jumbled_spanish_game_config<'a>() -> GameConfig<'a> {
         num_players: 2,
     })
 }
+
+#[allow(dead_code)]
+pub fn make_catalan_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Classic,
+        alphabet: alphabet::make_catalan_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_jumbled_catalan_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Jumbled,
+        alphabet: alphabet::make_catalan_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_danish_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Classic,
+        alphabet: alphabet::make_danish_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_jumbled_danish_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Jumbled,
+        alphabet: alphabet::make_danish_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_dutch_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Classic,
+        alphabet: alphabet::make_dutch_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_jumbled_dutch_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Jumbled,
+        alphabet: alphabet::make_dutch_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_finnish_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Classic,
+        alphabet: alphabet::make_finnish_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_jumbled_finnish_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Jumbled,
+        alphabet: alphabet::make_finnish_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_italian_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Classic,
+        alphabet: alphabet::make_italian_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_jumbled_italian_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Jumbled,
+        alphabet: alphabet::make_italian_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_portuguese_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Classic,
+        alphabet: alphabet::make_portuguese_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_jumbled_portuguese_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Jumbled,
+        alphabet: alphabet::make_portuguese_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_slovene_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Classic,
+        alphabet: alphabet::make_slovene_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_jumbled_slovene_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Jumbled,
+        alphabet: alphabet::make_slovene_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_swedish_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Classic,
+        alphabet: alphabet::make_swedish_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+#[allow(dead_code)]
+pub fn make_jumbled_swedish_game_config<'a>() -> GameConfig<'a> {
+    GameConfig::Static(StaticGameConfig {
+        game_rules: GameRules::Jumbled,
+        alphabet: alphabet::make_swedish_alphabet(),
+        board_layout: board_layout::make_common_board_layout(),
+        rack_size: 7,
+        num_players: 2,
+    })
+}
+
+pub fn make_game_config_by_name<'a>(name: &str) -> Option<GameConfig<'a>> {
+    match name {
+        "english" => Some(make_common_english_game_config()),
+        "jumbled_english" => Some(make_jumbled_english_game_config()),
+        "hong_kong_english" => Some(make_hong_kong_english_game_config()),
+        "super_english" => Some(make_super_english_game_config()),
+        "french" => Some(make_french_game_config()),
+        "jumbled_french" => Some(make_jumbled_french_game_config()),
+        "german" => Some(make_german_game_config()),
+        "jumbled_german" => Some(make_jumbled_german_game_config()),
+        "norwegian" => Some(make_norwegian_game_config()),
+        "jumbled_norwegian" => Some(make_jumbled_norwegian_game_config()),
+        "polish" => Some(make_polish_game_config()),
+        "jumbled_polish" => Some(make_jumbled_polish_game_config()),
+        "spanish" => Some(make_spanish_game_config()),
+        "jumbled_spanish" => Some(make_jumbled_spanish_game_config()),
+        "catalan" => Some(make_catalan_game_config()),
+        "jumbled_catalan" => Some(make_jumbled_catalan_game_config()),
+        "danish" => Some(make_danish_game_config()),
+        "jumbled_danish" => Some(make_jumbled_danish_game_config()),
+        "dutch" => Some(make_dutch_game_config()),
+        "jumbled_dutch" => Some(make_jumbled_dutch_game_config()),
+        "finnish" => Some(make_finnish_game_config()),
+        "jumbled_finnish" => Some(make_jumbled_finnish_game_config()),
+        "italian" => Some(make_italian_game_config()),
+        "jumbled_italian" => Some(make_jumbled_italian_game_config()),
+        "portuguese" => Some(make_portuguese_game_config()),
+        "jumbled_portuguese" => Some(make_jumbled_portuguese_game_config()),
+        "slovene" => Some(make_slovene_game_config()),
+        "jumbled_slovene" => Some(make_jumbled_slovene_game_config()),
+        "swedish" => Some(make_swedish_game_config()),
+        "jumbled_swedish" => Some(make_jumbled_swedish_game_config()),
+        _ => None,
+    }
+}
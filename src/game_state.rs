@@ -106,6 +106,8 @@ impl GameState {
         mut rng: &mut dyn RngCore,
     ) {
         self.reset();
+        // the same rng state deals the same tiles, whatever the last game was.
+        self.bag.0.sort_unstable();
         self.bag.shuffle(&mut rng);
         for player in self.players.iter_mut() {
             self.bag
@@ -122,6 +124,29 @@ impl GameState {
         game_config: &game_config::GameConfig,
         mut rng: &mut dyn RngCore,
         play: &movegen::Play,
+    ) -> error::Returns<()> {
+        self.play_with_put_back(game_config, play, |bag, tiles| {
+            bag.put_back(&mut rng, tiles)
+        })
+    }
+
+    // for duplicate games, tiles come out of the bag in the same order
+    // whatever is played, exchanged tiles are only drawn after all the others.
+    pub fn play_keeping_draw_order(
+        &mut self,
+        game_config: &game_config::GameConfig,
+        play: &movegen::Play,
+    ) -> error::Returns<()> {
+        self.play_with_put_back(game_config, play, |bag, tiles| {
+            bag.put_back_at_bottom(tiles)
+        })
+    }
+
+    fn play_with_put_back<PutBack: FnOnce(&mut bag::Bag, &[u8])>(
+        &mut self,
+        game_config: &game_config::GameConfig,
+        play: &movegen::Play,
+        put_back: PutBack,
     ) -> error::Returns<()> {
         let current_player = &mut self.players[self.turn as usize];
         match play {
@@ -129,7 +154,7 @@ impl GameState {
                 use_tiles(&mut current_player.rack, tiles.iter().copied())?;
                 self.bag
                     .replenish(&mut current_player.rack, game_config.rack_size() as usize);
-                self.bag.put_back(&mut rng, &tiles);
+                put_back(&mut self.bag, tiles);
                 self.zero_turns += 1;
             }
             movegen::Play::Place {
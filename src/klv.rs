@@ -1,57 +1,113 @@
 // Copyright (C) 2020-2021 Andy Kurnia.
 
-use super::kwg;
+use super::{alphabet, bites, build, error, fash, kwg};
 
+// The base table may be followed by tables for bag size buckets.
+// A bucket applies when the bag has at most its max tiles and no earlier
+// bucket applies. With more tiles than any bucket, the base table is used.
 pub struct Klv {
     pub kwg: kwg::Kwg,
     pub counts: Box<[u32]>,
     pub scaled_leaves: Box<[i16]>,
+    pub bucket_max_bags: Box<[i16]>,
+    pub num_leaves: usize,
 }
 
 pub static EMPTY_KLV_BYTES: &[u8] = b"\x01\x00\x00\x00\x00\x00\x40\x00\x00\x00\x00\x00";
 
 impl Klv {
-    pub fn from_bytes_alloc(buf: &[u8]) -> Klv {
+    pub fn from_bytes_alloc(buf: &[u8]) -> error::Returns<Klv> {
+        let read_u32 = |r: usize| -> error::Returns<u32> {
+            match buf.get(r..r + 4) {
+                Some(b) => Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+                None => {
+                    return_error!("klv file is truncated".into());
+                }
+            }
+        };
         let mut r = 0;
-        let kwg_bytes_len = (u32::from_le(
-            buf[r] as u32
-                | (buf[r + 1] as u32) << 8
-                | (buf[r + 2] as u32) << 16
-                | (buf[r + 3] as u32) << 24,
-        ) as usize)
-            * 4;
+        let kwg_bytes_len = read_u32(r)? as usize * 4;
         r += 4;
+        if buf.len() - r < kwg_bytes_len {
+            return_error!("klv file is truncated".into());
+        }
         let kwg = kwg::Kwg::from_bytes_alloc(&buf[r..r + kwg_bytes_len]);
         r += kwg_bytes_len;
-        let lv_len = u32::from_le(
-            buf[r] as u32
-                | (buf[r + 1] as u32) << 8
-                | (buf[r + 2] as u32) << 16
-                | (buf[r + 3] as u32) << 24,
-        );
+        let lv_len = read_u32(r)? as usize;
         r += 4;
-        let mut elts = Vec::with_capacity(lv_len as usize);
-        for _ in 0..lv_len {
-            elts.push(i16::from_le(
-                (buf[r] as u16 | (buf[r + 1] as u16) << 8) as i16,
-            ));
-            r += 2;
+        if (buf.len() - r) / 2 < lv_len {
+            return_error!("klv file is truncated".into());
+        }
+        // optional: num_buckets, max bag of each bucket, and their tables.
+        let mut bucket_max_bags = Vec::new();
+        let b = r + lv_len * 2;
+        if buf.len() - b >= 4 {
+            let num_buckets = read_u32(b)? as usize;
+            // each bucket has its max bag and its table.
+            if (buf.len() - b - 4) / (4 + lv_len * 2) < num_buckets {
+                return_error!(format!("klv cannot have {} buckets", num_buckets));
+            }
+            for i in 0..num_buckets {
+                bucket_max_bags.push(read_u32(b + 4 + 4 * i)? as i16);
+            }
+        }
+        let num_tables = bucket_max_bags.len() + 1;
+        let mut elts = Vec::with_capacity(lv_len * num_tables);
+        for table_idx in 0..num_tables {
+            if table_idx == 1 {
+                // skip the bucket header.
+                r += 4 * num_tables;
+            }
+            for _ in 0..lv_len {
+                elts.push(i16::from_le(
+                    (buf[r] as u16 | (buf[r + 1] as u16) << 8) as i16,
+                ));
+                r += 2;
+            }
         }
         let counts = kwg.count_words_alloc();
-        Klv {
+        Ok(Klv {
             kwg,
             counts,
             scaled_leaves: elts.into_boxed_slice(),
-        }
+            bucket_max_bags: bucket_max_bags.into_boxed_slice(),
+            num_leaves: lv_len,
+        })
     }
 
+    // leave_idx counts from the start of the base table.
     #[inline(always)]
-    pub fn leave(&self, leave_idx: u32) -> f32 {
-        self.scaled_leaves[leave_idx as usize] as f32 * (1.0 / 256.0)
+    pub fn leave(&self, leave_idx: usize) -> f32 {
+        self.scaled_leaves[leave_idx] as f32 * (1.0 / 256.0)
     }
 
+    // leaves are numbered 0..num_leaves in sorted order.
+    pub fn get_leave(&self, leave_idx: u32, out: &mut Vec<u8>) {
+        out.clear();
+        self.kwg
+            .get_word_by_index(&self.counts, self.kwg[0].arc_index(), leave_idx, |tile| {
+                out.push(tile)
+            });
+    }
+
+    // leave must be sorted, returns !0 if not found.
+    #[inline(always)]
+    pub fn leave_index(&self, leave: &[u8]) -> u32 {
+        self.kwg
+            .get_word_index(&self.counts, self.kwg[0].arc_index(), leave)
+    }
+
+    // 0 is the base table, bucket b is table b + 1.
     #[inline(always)]
-    pub fn leave_value_from_tally(&self, rack_tally: &[u8]) -> f32 {
+    pub fn table_for_bag(&self, num_tiles_in_bag: i16) -> usize {
+        self.bucket_max_bags
+            .iter()
+            .position(|&max_bag| num_tiles_in_bag <= max_bag)
+            .map_or(0, |bucket| bucket + 1)
+    }
+
+    #[inline(always)]
+    pub fn leave_value_from_tally(&self, rack_tally: &[u8], num_tiles_in_bag: i16) -> f32 {
         let leave_idx = self.kwg.get_word_index_of(
             &self.counts,
             self.kwg[0].arc_index(),
@@ -62,7 +118,193 @@ impl Klv {
         if leave_idx == !0 {
             0.0
         } else {
-            self.leave(leave_idx)
+            self.leave(self.table_for_bag(num_tiles_in_bag) * self.num_leaves + leave_idx as usize)
+        }
+    }
+}
+
+// Reads "rack,value" rows such as the output of leave generation.
+pub fn read_leaves_csv<Readable: std::io::Read>(
+    alphabet: &alphabet::Alphabet,
+    f: Readable,
+) -> error::Returns<Vec<(bites::Bites, f32)>> {
+    let alphabet_reader = alphabet::AlphabetReader::new_for_racks(alphabet);
+    let mut csv_reader = csv::ReaderBuilder::new().has_headers(false).from_reader(f);
+    let mut leaves = Vec::new();
+    let mut v = Vec::new();
+    for result in csv_reader.records() {
+        let record = result?;
+        let sb = record[0].as_bytes();
+        v.clear();
+        let mut ix = 0;
+        while ix < sb.len() {
+            if let Some((tile, end_ix)) = alphabet_reader.next_tile(sb, ix) {
+                v.push(tile);
+                ix = end_ix;
+            } else {
+                return_error!(format!("invalid tile after {:?} in {:?}", v, &record[0]));
+            }
         }
+        v.sort_unstable();
+        leaves.push((v[..].into(), record[1].parse::<f32>()?));
+    }
+    Ok(leaves)
+}
+
+fn scale_leave(leave: &[u8], value: f32) -> error::Returns<i16> {
+    let rounded_leave = (value * 256.0).round();
+    let int_leave = rounded_leave as i16;
+    if int_leave as f32 != rounded_leave {
+        return_error!(format!("leave {:?} value {} out of range", leave, value));
+    }
+    Ok(int_leave)
+}
+
+// Values are stored in 1/256 units and must fit in i16.
+// The empty leave is skipped, and later duplicates replace earlier ones.
+pub fn build_klv(leaves: Vec<(bites::Bites, f32)>) -> error::Returns<Vec<u8>> {
+    build_bucketed_klv(leaves, Vec::new())
+}
+
+// Buckets are (max tiles in bag, leaves), with ascending max tiles in bag.
+// The base leaves decide which leaves exist. A bucket without some leave
+// gets its base value, and leaves only found in buckets are ignored.
+pub fn build_bucketed_klv(
+    mut leaves: Vec<(bites::Bites, f32)>,
+    buckets: Vec<(i16, Vec<(bites::Bites, f32)>)>,
+) -> error::Returns<Vec<u8>> {
+    // stable, so the last duplicate is the last of its run.
+    leaves.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut machine_words = Vec::with_capacity(leaves.len());
+    let mut leave_values = Vec::with_capacity(leaves.len() * (buckets.len() + 1));
+    for (i, (leave, value)) in leaves.iter().enumerate() {
+        if leave.is_empty() || (i + 1 < leaves.len() && leaves[i + 1].0 == *leave) {
+            continue;
+        }
+        machine_words.push(leave.clone());
+        leave_values.push(scale_leave(leave, *value)?);
+    }
+    let num_leaves = machine_words.len();
+    for (i, (max_bag, bucket_leaves)) in buckets.iter().enumerate() {
+        if *max_bag < 0 || (i > 0 && buckets[i - 1].0 >= *max_bag) {
+            return_error!(format!(
+                "bucket max bags must be ascending, not {}",
+                max_bag
+            ));
+        }
+        let mut bucket_map = fash::MyHashMap::default();
+        for (leave, value) in bucket_leaves.iter() {
+            bucket_map.insert(&leave[..], *value);
+        }
+        for j in 0..num_leaves {
+            let leave = &machine_words[j][..];
+            leave_values.push(match bucket_map.get(leave) {
+                Some(&value) => scale_leave(leave, value)?,
+                None => leave_values[j],
+            });
+        }
+    }
+    // words are sorted, so the dawg numbers them in this order.
+    let leaves_kwg = build::build(build::BuildFormat::DawgOnly, &machine_words)?;
+    let mut bin = vec![
+        0;
+        2 * 4
+            + leaves_kwg.len()
+            + leave_values.len() * 2
+            + if buckets.is_empty() {
+                0
+            } else {
+                4 + buckets.len() * 4
+            }
+    ];
+    let mut w = 0;
+    bin[w..w + 4].copy_from_slice(&((leaves_kwg.len() / 4) as u32).to_le_bytes());
+    w += 4;
+    bin[w..w + leaves_kwg.len()].copy_from_slice(&leaves_kwg);
+    w += leaves_kwg.len();
+    bin[w..w + 4].copy_from_slice(&(num_leaves as u32).to_le_bytes());
+    w += 4;
+    for (i, v) in leave_values.iter().enumerate() {
+        if i == num_leaves && !buckets.is_empty() {
+            bin[w..w + 4].copy_from_slice(&(buckets.len() as u32).to_le_bytes());
+            w += 4;
+            for (max_bag, _) in buckets.iter() {
+                bin[w..w + 4].copy_from_slice(&(*max_bag as u32).to_le_bytes());
+                w += 4;
+            }
+        }
+        bin[w..w + 2].copy_from_slice(&v.to_le_bytes());
+        w += 2;
+    }
+    assert_eq!(w, bin.len());
+    Ok(bin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leave_value(klv: &Klv, leave: &[u8], num_tiles_in_bag: i16) -> f32 {
+        let mut rack_tally = [0u8; 27];
+        leave
+            .iter()
+            .for_each(|&tile| rack_tally[tile as usize] += 1);
+        klv.leave_value_from_tally(&rack_tally, num_tiles_in_bag)
+    }
+
+    #[test]
+    fn test_bucketed_round_trip() {
+        let leaves = || {
+            vec![
+                (b"\x01"[..].into(), 1.0),
+                (b"\x02"[..].into(), 2.0),
+                (b"\x01\x02"[..].into(), 3.0),
+            ]
+        };
+        let unbucketed = build_klv(leaves()).unwrap();
+        let bucketed = build_bucketed_klv(
+            leaves(),
+            vec![
+                (3, vec![(b"\x01"[..].into(), -1.0)]),
+                (
+                    10,
+                    vec![(b"\x02"[..].into(), -2.0), (b"\x01\x02"[..].into(), 5.0)],
+                ),
+            ],
+        )
+        .unwrap();
+
+        let klv = Klv::from_bytes_alloc(&bucketed).unwrap();
+        assert_eq!(&klv.bucket_max_bags[..], [3, 10]);
+        for &(num_tiles_in_bag, a, b, ab) in &[
+            (-1, -1.0, 2.0, 3.0),
+            (0, -1.0, 2.0, 3.0),
+            (3, -1.0, 2.0, 3.0),
+            (4, 1.0, -2.0, 5.0),
+            (10, 1.0, -2.0, 5.0),
+            (11, 1.0, 2.0, 3.0),
+            (100, 1.0, 2.0, 3.0),
+        ] {
+            assert_eq!(leave_value(&klv, &[1], num_tiles_in_bag), a);
+            assert_eq!(leave_value(&klv, &[2], num_tiles_in_bag), b);
+            assert_eq!(leave_value(&klv, &[1, 2], num_tiles_in_bag), ab);
+            assert_eq!(leave_value(&klv, &[3], num_tiles_in_bag), 0.0);
+        }
+
+        // the unbucketed format has no trailer.
+        let klv = Klv::from_bytes_alloc(&unbucketed).unwrap();
+        assert!(klv.bucket_max_bags.is_empty());
+        for &num_tiles_in_bag in &[0, 3, 10, 100] {
+            assert_eq!(leave_value(&klv, &[1], num_tiles_in_bag), 1.0);
+            assert_eq!(leave_value(&klv, &[1, 2], num_tiles_in_bag), 3.0);
+        }
+        assert!(Klv::from_bytes_alloc(EMPTY_KLV_BYTES).is_ok());
+
+        // the trailer starts where the unbucketed format ends.
+        let mut malformed = bucketed.clone();
+        malformed[unbucketed.len()..unbucketed.len() + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Klv::from_bytes_alloc(&malformed).is_err());
+        assert!(Klv::from_bytes_alloc(&bucketed[..bucketed.len() - 1]).is_err());
+        assert!(Klv::from_bytes_alloc(&unbucketed[..unbucketed.len() - 1]).is_err());
     }
 }
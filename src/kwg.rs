@@ -1,5 +1,10 @@
 // Copyright (C) 2020-2021 Andy Kurnia.
 
+use super::bits;
+
+// bits 0-21 = arc index, bit 22 = is end, bit 23 = accepts, bits 24-31 = tile.
+// The tile field holds 0x00-0xff, enough for 127 letters plus the blank
+// (tiles above 0x7f would collide with the blank flag on the board).
 #[derive(Clone, Copy)]
 pub struct Node(u32);
 
@@ -242,8 +247,8 @@ impl Kwg {
         self.completes_alpha_cross_set(0, letters_tally, 1)
     }
 
-    pub fn compute_alpha_cross_set(&self, letters_tally: &[u8]) -> u64 {
-        let mut answer = 1; // always set bit 0 here
+    pub fn compute_alpha_cross_set<B: bits::Bits>(&self, letters_tally: &[u8]) -> B {
+        let mut answer = B::ONE; // always set bit 0 here
         let mut p = self[0].arc_index() as i32;
         if p <= 0 {
             return answer;
@@ -261,7 +266,7 @@ impl Kwg {
                         }
                         std::cmp::Ordering::Less => {
                             if self.completes_alpha_cross_set(p, letters_tally, letter) {
-                                answer |= 1 << tile;
+                                answer |= B::ONE << tile;
                             }
                             if node.is_end() {
                                 return answer;
@@ -283,7 +288,7 @@ impl Kwg {
         loop {
             let node = self[p];
             if self.completes_alpha_cross_set(p, letters_tally, letters_tally_len) {
-                answer |= 1 << node.tile();
+                answer |= B::ONE << node.tile();
             }
             if node.is_end() {
                 break;
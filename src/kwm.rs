@@ -0,0 +1,371 @@
+// Copyright (C) 2020-2021 Andy Kurnia.
+
+use super::{alphabet, bites, error, kwg, prob};
+use unicode_normalization::UnicodeNormalization;
+
+// Kurnia Word Metadata is a companion to a kwg, keyed by dawg word index
+// (the one from Kwg::get_word_index).
+//
+// encoding: little endian of
+// u32 num_words
+// u32 num_tags, then for each tag: u32 len, utf8 name
+// u32 probability_index (1-based, within word length) * num_words
+// u64 tags (bit i = tag i) * num_words
+// u32 offset * (2 * num_words + 1), word i has
+//   definition = pool[offset[2i]..offset[2i+1]]
+//   inflections = pool[offset[2i+1]..offset[2i+2]]
+// u32 pool_len, utf8 pool
+pub struct Kwm {
+    pub tag_names: Box<[String]>,
+    pub probability_indexes: Box<[u32]>,
+    pub tags: Box<[u64]>,
+    offsets: Box<[u32]>,
+    pool: String,
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    r: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> error::Returns<&'a [u8]> {
+        if self.buf.len() - self.r < n {
+            return_error!("kwm file is truncated".into());
+        }
+        self.r += n;
+        Ok(&self.buf[self.r - n..self.r])
+    }
+
+    fn u32(&mut self) -> error::Returns<u32> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> error::Returns<u64> {
+        let b = self.take(8)?;
+        Ok(u64::from_le_bytes([
+            b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7],
+        ]))
+    }
+
+    fn string(&mut self, n: usize) -> error::Returns<String> {
+        Ok(String::from_utf8(self.take(n)?.to_vec())?)
+    }
+}
+
+impl Kwm {
+    pub fn from_bytes_alloc(buf: &[u8]) -> error::Returns<Kwm> {
+        let mut reader = Reader { buf, r: 0 };
+        let num_words = reader.u32()? as usize;
+        let num_tags = reader.u32()? as usize;
+        if num_tags > 64 {
+            return_error!(format!("kwm cannot have {} tags", num_tags));
+        }
+        // each word needs at least 4 + 8 + 2 * 4 bytes, check before allocating.
+        if num_words > buf.len() / 20 {
+            return_error!("kwm file is truncated".into());
+        }
+        let mut tag_names = Vec::with_capacity(num_tags);
+        for _ in 0..num_tags {
+            let len = reader.u32()? as usize;
+            tag_names.push(reader.string(len)?);
+        }
+        let mut probability_indexes = Vec::with_capacity(num_words);
+        for _ in 0..num_words {
+            probability_indexes.push(reader.u32()?);
+        }
+        let mut tags = Vec::with_capacity(num_words);
+        for _ in 0..num_words {
+            tags.push(reader.u64()?);
+        }
+        let mut offsets = Vec::with_capacity(2 * num_words + 1);
+        for _ in 0..2 * num_words + 1 {
+            offsets.push(reader.u32()?);
+        }
+        let pool_len = reader.u32()? as usize;
+        let pool = reader.string(pool_len)?;
+        if offsets
+            .iter()
+            .zip(&offsets[1..])
+            .any(|(&a, &b)| a > b || !pool.is_char_boundary(a as usize))
+            || offsets[offsets.len() - 1] as usize != pool_len
+        {
+            return_error!("kwm has invalid offsets".into());
+        }
+        Ok(Kwm {
+            tag_names: tag_names.into_boxed_slice(),
+            probability_indexes: probability_indexes.into_boxed_slice(),
+            tags: tags.into_boxed_slice(),
+            offsets: offsets.into_boxed_slice(),
+            pool,
+        })
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    #[inline(always)]
+    pub fn definition(&self, word_idx: u32) -> &str {
+        let i = word_idx as usize * 2;
+        &self.pool[self.offsets[i] as usize..self.offsets[i + 1] as usize]
+    }
+
+    #[inline(always)]
+    pub fn inflections(&self, word_idx: u32) -> &str {
+        let i = word_idx as usize * 2 + 1;
+        &self.pool[self.offsets[i] as usize..self.offsets[i + 1] as usize]
+    }
+
+    pub fn tag_names_of(&self, word_idx: u32) -> impl Iterator<Item = &str> {
+        let tags = self.tags[word_idx as usize];
+        self.tag_names
+            .iter()
+            .enumerate()
+            .filter(move |&(i, _)| tags & (1 << i) != 0)
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+// Published lists often end definitions with inflections,
+// such as "a Syrian cloth [n -S]". This separates the bracketed part.
+pub fn split_inflections(text: &str) -> (&str, &str) {
+    let text = text.trim();
+    if text.ends_with(']') {
+        if let Some(open) = text.rfind('[') {
+            return (text[..open].trim_end(), &text[open + 1..text.len() - 1]);
+        }
+    }
+    (text, "")
+}
+
+// machine_words must be sorted and deduplicated, as in the kwg.
+// definitions[i] is the raw text after word i, tags[i] uses bits from tag_names.
+pub fn build(
+    alphabet: &alphabet::Alphabet,
+    machine_words: &[bites::Bites],
+    definitions: &[&str],
+    tag_names: &[&str],
+    tags: &[u64],
+) -> error::Returns<Box<[u8]>> {
+    if tag_names.len() > 64 {
+        return_error!(format!("kwm cannot have {} tags", tag_names.len()));
+    }
+    let num_words = machine_words.len();
+
+    let probability_indexes = prob::probability_indexes_by_length(alphabet, machine_words);
+
+    let mut pool = String::new();
+    let mut offsets = Vec::with_capacity(2 * num_words + 1);
+    offsets.push(0u32);
+    for i in 0..num_words {
+        let (definition, inflections) = split_inflections(definitions.get(i).unwrap_or(&""));
+        pool.push_str(definition);
+        offsets.push(pool.len() as u32);
+        pool.push_str(inflections);
+        offsets.push(pool.len() as u32);
+    }
+
+    let mut bin = Vec::with_capacity(
+        4 + 4
+            + tag_names.iter().map(|name| 4 + name.len()).sum::<usize>()
+            + num_words * (4 + 8)
+            + offsets.len() * 4
+            + 4
+            + pool.len(),
+    );
+    bin.extend_from_slice(&(num_words as u32).to_le_bytes());
+    bin.extend_from_slice(&(tag_names.len() as u32).to_le_bytes());
+    for name in tag_names {
+        bin.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        bin.extend_from_slice(name.as_bytes());
+    }
+    for probability_index in probability_indexes.iter() {
+        bin.extend_from_slice(&probability_index.to_le_bytes());
+    }
+    for i in 0..num_words {
+        bin.extend_from_slice(&tags.get(i).unwrap_or(&0).to_le_bytes());
+    }
+    for offset in offsets {
+        bin.extend_from_slice(&offset.to_le_bytes());
+    }
+    bin.extend_from_slice(&(pool.len() as u32).to_le_bytes());
+    bin.extend_from_slice(pool.as_bytes());
+    Ok(bin.into_boxed_slice())
+}
+
+// Letters that can be prepended to form a word, using only the dawg.
+pub fn front_hooks(kwg: &kwg::Kwg, alphabet: &alphabet::Alphabet, word: &[u8]) -> Vec<u8> {
+    (1..alphabet.len())
+        .filter(|&tile| {
+            let mut p = kwg.seek(0, tile);
+            for &t in word {
+                if p <= 0 {
+                    return false;
+                }
+                p = kwg.seek(p, t);
+            }
+            p > 0 && kwg[p].accepts()
+        })
+        .collect()
+}
+
+// Letters that can be appended to form a word.
+pub fn back_hooks(kwg: &kwg::Kwg, word: &[u8]) -> Vec<u8> {
+    let mut p = 0;
+    for &tile in word {
+        p = kwg.seek(p, tile);
+        if p <= 0 {
+            return Vec::new();
+        }
+    }
+    let mut ret = Vec::new();
+    p = kwg[p].arc_index();
+    if p > 0 {
+        loop {
+            let node = kwg[p];
+            if node.accepts() {
+                ret.push(node.tile());
+            }
+            if node.is_end() {
+                break;
+            }
+            p += 1;
+        }
+    }
+    ret
+}
+
+#[derive(serde::Serialize)]
+pub struct WordInfo {
+    pub word: String,
+    pub valid: bool,
+    pub definition: String,
+    pub inflections: String,
+    pub tags: Vec<String>,
+    pub front_hooks: String,
+    pub back_hooks: String,
+    pub probability_index: u32,
+}
+
+// Reads a word to look up, in either case, into unblanked tiles.
+// alphabet_reader should be AlphabetReader::new_for_plays.
+// The word is NFC-normalized first, as the build does.
+pub fn parse_word(
+    alphabet_reader: &alphabet::AlphabetReader,
+    word: &str,
+    v: &mut Vec<u8>,
+) -> error::Returns<()> {
+    v.clear();
+    let word = word.nfc().collect::<String>().to_uppercase();
+    let sb = word.as_bytes();
+    let mut ix = 0;
+    while ix < sb.len() {
+        if let Some((tile, end_ix)) = alphabet_reader.next_tile(sb, ix) {
+            v.push(tile & 0x7f);
+            ix = end_ix;
+        } else {
+            return_error!(format!("invalid tile after {:?} in {:?}", v, word));
+        }
+    }
+    Ok(())
+}
+
+// word_counts should come from kwg.count_dawg_words_alloc().
+pub fn word_info(
+    kwg: &kwg::Kwg,
+    word_counts: &[u32],
+    kwm: &Kwm,
+    alphabet: &alphabet::Alphabet,
+    word: &[u8],
+) -> WordInfo {
+    let word_idx = kwg.get_word_index(word_counts, kwg[0].arc_index(), word);
+    let valid = word_idx != !0 && (word_idx as usize) < kwm.len();
+    WordInfo {
+        word: alphabet.fmt_rack(word).to_string(),
+        valid,
+        definition: if valid {
+            kwm.definition(word_idx).to_string()
+        } else {
+            String::new()
+        },
+        inflections: if valid {
+            kwm.inflections(word_idx).to_string()
+        } else {
+            String::new()
+        },
+        tags: if valid {
+            kwm.tag_names_of(word_idx).map(|x| x.to_string()).collect()
+        } else {
+            Vec::new()
+        },
+        front_hooks: alphabet
+            .fmt_rack(&front_hooks(kwg, alphabet, word))
+            .to_string(),
+        back_hooks: alphabet.fmt_rack(&back_hooks(kwg, word)).to_string(),
+        probability_index: if valid {
+            kwm.probability_indexes[word_idx as usize]
+        } else {
+            0
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game_config;
+    use super::*;
+
+    #[test]
+    fn test_build_round_trip() {
+        let game_config = game_config::make_common_english_game_config();
+        let alphabet = game_config.alphabet();
+        // AA AB CAB QI
+        let machine_words = [&[1, 1][..], &[1, 2], &[3, 1, 2], &[17, 9]]
+            .iter()
+            .map(|&word| word.into())
+            .collect::<Box<[bites::Bites]>>();
+        let definitions = ["rough lava [n -S]", "", "a taxi [n -S]", "vital force"];
+        let tag_names = ["new", "old"];
+        let tags = [0b01, 0, 0b11, 0b10];
+        let kwm = Kwm::from_bytes_alloc(
+            &build(alphabet, &machine_words, &definitions, &tag_names, &tags).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(kwm.len(), 4);
+        assert_eq!(&kwm.tag_names[..], ["new", "old"]);
+        assert_eq!(&kwm.tags[..], tags);
+        assert_eq!(
+            kwm.probability_indexes,
+            prob::probability_indexes_by_length(alphabet, &machine_words)
+        );
+        for (i, definition) in definitions.iter().enumerate() {
+            let (definition, inflections) = split_inflections(definition);
+            assert_eq!(kwm.definition(i as u32), definition);
+            assert_eq!(kwm.inflections(i as u32), inflections);
+        }
+        assert_eq!(kwm.inflections(0), "n -S");
+        assert_eq!(kwm.tag_names_of(2).collect::<Vec<_>>(), vec!["new", "old"]);
+        assert_eq!(kwm.tag_names_of(1).count(), 0);
+    }
+
+    #[test]
+    fn test_parse_word_normalizes() {
+        let game_config = game_config::make_spanish_game_config();
+        let alphabet = game_config.alphabet();
+        let alphabet_reader = alphabet::AlphabetReader::new_for_plays(alphabet);
+        let mut composed = Vec::new();
+        parse_word(&alphabet_reader, "a\u{f1}o", &mut composed).unwrap();
+        let mut decomposed = Vec::new();
+        parse_word(&alphabet_reader, "an\u{303}o", &mut decomposed).unwrap();
+        assert_eq!(composed, decomposed);
+        assert_eq!(composed.len(), 3);
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright (C) 2020-2021 Andy Kurnia.
+
+use super::{alphabet, error, stats};
+
+// A parametric leave value, the sum of:
+// - a value per copy of each tile,
+// - a penalty per extra copy of each tile,
+// - a synergy per pair of distinct tiles,
+// - a term per (number of vowels, number of consonants), the blank is neither.
+// With far fewer parameters than leaves, it can be fitted from fewer games.
+pub struct SynergyModel {
+    num_tiles: usize,
+    rack_size: usize,
+    is_vowel: Box<[bool]>,
+    weights: Box<[f64]>,
+}
+
+impl SynergyModel {
+    fn num_features(num_tiles: usize, rack_size: usize) -> usize {
+        2 * num_tiles + num_tiles * (num_tiles - 1) / 2 + rack_size * rack_size
+    }
+
+    // leave must be sorted.
+    #[inline(always)]
+    fn for_each_feature<F: FnMut(usize, f64)>(&self, leave: &[u8], mut f: F) {
+        let num_tiles = self.num_tiles;
+        let pairs_base = 2 * num_tiles;
+        let balance_base = pairs_base + num_tiles * (num_tiles - 1) / 2;
+        let mut num_vowels = 0;
+        let mut num_consonants = 0;
+        let mut i = 0;
+        while i < leave.len() {
+            let tile = leave[i];
+            let mut j = i + 1;
+            while j < leave.len() && leave[j] == tile {
+                j += 1;
+            }
+            let count = j - i;
+            f(tile as usize, count as f64);
+            if count > 1 {
+                f(num_tiles + tile as usize, (count - 1) as f64);
+            }
+            let mut k = 0;
+            while k < i {
+                // earlier distinct tiles are smaller.
+                let other = leave[k] as usize;
+                f(
+                    pairs_base + tile as usize * (tile as usize - 1) / 2 + other,
+                    1.0,
+                );
+                while k < i && leave[k] as usize == other {
+                    k += 1;
+                }
+            }
+            if self.is_vowel[tile as usize] {
+                num_vowels += count;
+            } else if tile != 0 {
+                num_consonants += count;
+            }
+            i = j;
+        }
+        if num_vowels < self.rack_size && num_consonants < self.rack_size {
+            f(
+                balance_base + num_vowels * self.rack_size + num_consonants,
+                1.0,
+            );
+        }
+    }
+
+    // leave must be sorted.
+    pub fn value(&self, leave: &[u8]) -> f64 {
+        let mut value = 0.0;
+        self.for_each_feature(leave, |idx, x| value += self.weights[idx] * x);
+        value
+    }
+
+    // samples are (sorted leave, value, weight). minimizes the weighted sum of
+    // squared errors plus ridge times the sum of squared parameters, so that
+    // parameters without samples stay at zero.
+    pub fn fit<'a, Samples: Iterator<Item = (&'a [u8], f64, f64)>>(
+        alphabet: &alphabet::Alphabet,
+        rack_size: i8,
+        samples: Samples,
+        ridge: f64,
+    ) -> error::Returns<Self> {
+        let num_tiles = alphabet.len() as usize;
+        let rack_size = rack_size as usize;
+        let n = Self::num_features(num_tiles, rack_size);
+        let mut ret = Self {
+            num_tiles,
+            rack_size,
+            is_vowel: (0..alphabet.len())
+                .map(|tile| alphabet.is_vowel(tile))
+                .collect(),
+            weights: vec![0.0; n].into_boxed_slice(),
+        };
+        // normal equations, only the lower triangle of xtx is accumulated.
+        let mut xtx = vec![0.0; n * n];
+        let mut xty = vec![0.0; n];
+        let mut features = Vec::new();
+        for (leave, y, weight) in samples {
+            features.clear();
+            ret.for_each_feature(leave, |idx, x| features.push((idx, x)));
+            for (fi, &(i, xi)) in features.iter().enumerate() {
+                xty[i] += weight * xi * y;
+                for &(j, xj) in &features[..=fi] {
+                    let (r, c) = if i >= j { (i, j) } else { (j, i) };
+                    xtx[r * n + c] += weight * xi * xj;
+                }
+            }
+        }
+        for i in 0..n {
+            xtx[i * n + i] += ridge;
+        }
+        if !stats::cholesky(&mut xtx, n) {
+            return_error!("least squares is singular, increase ridge".into());
+        }
+        ret.weights.copy_from_slice(&xty);
+        stats::cholesky_solve(&xtx, n, &mut ret.weights);
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game_config;
+    use super::*;
+
+    // every sorted leave of 1 to max_len of tiles.
+    fn push_leaves(tiles: &[u8], max_len: usize, leave: &mut Vec<u8>, leaves: &mut Vec<Vec<u8>>) {
+        if !leave.is_empty() {
+            leaves.push(leave.clone());
+        }
+        if leave.len() < max_len {
+            for (i, &tile) in tiles.iter().enumerate() {
+                leave.push(tile);
+                push_leaves(&tiles[i..], max_len, leave, leaves);
+                leave.pop();
+            }
+        }
+    }
+
+    #[test]
+    fn test_fit_recovers_values() {
+        let game_config = game_config::make_common_english_game_config();
+        let tiles = [0u8, 1, 2, 3, 5, 9]; // ?ABCEI
+        let tile_values = [25.0, 1.0, -3.0, 0.5, 2.0, -1.0];
+        let synergy = 2.5; // of A and E
+        let true_value = |leave: &[u8]| {
+            leave
+                .iter()
+                .map(|&tile| tile_values[tiles.iter().position(|&t| t == tile).unwrap()])
+                .sum::<f64>()
+                + if leave.contains(&1) && leave.contains(&5) {
+                    synergy
+                } else {
+                    0.0
+                }
+        };
+        let mut leaves = Vec::new();
+        push_leaves(&tiles, 4, &mut Vec::new(), &mut leaves);
+        assert_eq!(leaves.len(), 209);
+
+        let model = SynergyModel::fit(
+            game_config.alphabet(),
+            game_config.rack_size(),
+            leaves
+                .iter()
+                .map(|leave| (&leave[..], true_value(leave), 1.0)),
+            1e-6,
+        )
+        .unwrap();
+        for leave in leaves.iter() {
+            assert!(
+                (model.value(leave) - true_value(leave)).abs() < 1e-3,
+                "{:?}",
+                leave
+            );
+        }
+        // tile values and vowel balance cancel out, leaving the synergy.
+        let recovered_synergy = (model.value(&[1, 5]) - model.value(&[1, 9]))
+            - (model.value(&[2, 5]) - model.value(&[2, 9]));
+        assert!((recovered_synergy - synergy).abs() < 1e-3);
+    }
+}
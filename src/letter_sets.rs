@@ -0,0 +1,60 @@
+// Copyright (C) 2020-2021 Andy Kurnia.
+
+use super::{bits, kwg};
+
+// Per-node tile sets, so an arc list can be read with one lookup instead of
+// walking its nodes. Index by the arc_index that starts the list.
+// Entry i covers nodes i..=end of its list, so entries mid-list are partial.
+// This takes two bitsets per node, build it once per kwg and share it.
+pub struct LetterSets<B> {
+    children: Box<[B]>,
+    accepts: Box<[B]>,
+}
+
+impl<B: bits::Bits> LetterSets<B> {
+    pub fn new(kwg: &kwg::Kwg) -> Self {
+        let kwg_len = kwg.0.len();
+        let mut children = vec![B::ZERO; kwg_len].into_boxed_slice();
+        let mut accepts = vec![B::ZERO; kwg_len].into_boxed_slice();
+        // node 0 is never the start of an arc list, arc_index 0 means none.
+        for p in (1..kwg_len).rev() {
+            let node = kwg.0[p];
+            let mut children_here = B::ONE << node.tile();
+            let mut accepts_here = B::from(node.accepts()) << node.tile();
+            if !node.is_end() && p + 1 < kwg_len {
+                children_here |= children[p + 1];
+                accepts_here |= accepts[p + 1];
+            }
+            children[p] = children_here;
+            accepts[p] = accepts_here;
+        }
+        Self { children, accepts }
+    }
+
+    // same as kwg.seek, but finds the node by counting the tiles before it.
+    #[inline(always)]
+    pub fn seek(&self, kwg: &kwg::Kwg, p: i32, tile: u8) -> i32 {
+        if p >= 0 {
+            let p = kwg[p].arc_index();
+            if p > 0 {
+                let children = self.children[p as usize];
+                if children & (B::ONE << tile) != B::ZERO {
+                    return p + (children & !(!B::ZERO << tile)).count_ones() as i32;
+                }
+            }
+        }
+        -1
+    }
+
+    // tiles on the arc list at arc_index.
+    #[inline(always)]
+    pub fn children(&self, arc_index: i32) -> B {
+        self.children[arc_index as usize]
+    }
+
+    // tiles on the arc list at arc_index that complete a word.
+    #[inline(always)]
+    pub fn accepts(&self, arc_index: i32) -> B {
+        self.accepts[arc_index as usize]
+    }
+}
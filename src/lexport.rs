@@ -1,6 +1,6 @@
 // Copyright (C) 2020-2021 Andy Kurnia.
 
-use super::{alphabet, fash, kwg};
+use super::{alphabet, bites, build, error, fash, kwg};
 
 pub enum MacondoFormat {
     Dawg,
@@ -183,3 +183,421 @@ pub fn to_macondo<'a>(
 
     bin.into_boxed_slice()
 }
+
+// Converts a Macondo gaddag or dawg file back into sorted machine words.
+// Letters are matched by codepoint against the alphabet's labels.
+pub fn read_macondo_machine_words(
+    alphabet: &alphabet::Alphabet,
+    bin: &[u8],
+) -> error::Returns<Box<[bites::Bites]>> {
+    let mut r = 0;
+    let mut take = |n: usize| -> error::Returns<&[u8]> {
+        if bin.len() - r < n {
+            return_error!("macondo file is truncated".into());
+        }
+        r += n;
+        Ok(&bin[r - n..r])
+    };
+    let read_u32 = |b: &[u8]| u32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+
+    let is_gaddag = match take(4)? {
+        b"cdwg" => false,
+        b"cgdg" => true,
+        _ => {
+            return_error!("not a macondo dawg or gaddag".into());
+        }
+    };
+    let lexicon_name_len = take(1)?[0] as usize;
+    take(lexicon_name_len)?;
+
+    let num_letters = read_u32(take(4)?) as usize;
+    // letter sets are u64, so letters beyond 64 cannot be represented.
+    if num_letters > 64 {
+        return_error!(format!("macondo file has {} letters", num_letters));
+    }
+    let mut tile_mapping = Vec::with_capacity(num_letters);
+    for _ in 0..num_letters {
+        let codepoint = read_u32(take(4)?);
+        let c = match std::char::from_u32(codepoint) {
+            Some(c) => c,
+            None => {
+                return_error!(format!("invalid codepoint {}", codepoint));
+            }
+        };
+        let mut buf = [0u8; 4];
+        let c_str = c.encode_utf8(&mut buf);
+        match (1..alphabet.len()).find(|&tile| alphabet.from_rack(tile).unwrap() == c_str) {
+            Some(tile) => tile_mapping.push(tile),
+            None => {
+                return_error!(format!("letter {:?} is not in alphabet", c));
+            }
+        }
+    }
+
+    let num_letter_sets = read_u32(take(4)?) as usize;
+    let letter_sets = take(num_letter_sets * 8)?
+        .chunks_exact(8)
+        .map(|b| u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+        .collect::<Box<_>>();
+
+    let num_nodes = read_u32(take(4)?) as usize;
+    let nodes = take(num_nodes * 4)?
+        .chunks_exact(4)
+        .map(read_u32)
+        .collect::<Box<_>>();
+
+    struct Env<'a> {
+        nodes: &'a [u32],
+        letter_sets: &'a [u64],
+        tile_mapping: &'a [u8],
+        word: &'a mut Vec<u8>,
+        machine_words: &'a mut Vec<bites::Bites>,
+        depth: usize,
+    }
+    // In a gaddag, the separator-free paths spell every word reversed.
+    fn iter(env: &mut Env, p: usize) -> error::Returns<()> {
+        // words cannot be longer than the number of nodes, this catches cycles
+        if env.depth > env.nodes.len() {
+            return_error!("macondo file has a cycle".into());
+        }
+        let header = match env.nodes.get(p) {
+            Some(&header) => header,
+            None => {
+                return_error!(format!("node {} out of bounds", p));
+            }
+        };
+        let num_arcs = (header >> 24) as usize;
+        let letter_set = match env.letter_sets.get((header & 0xffffff) as usize) {
+            Some(&letter_set) => letter_set,
+            None => {
+                return_error!(format!("node {} has invalid letter set", p));
+            }
+        };
+        for (letter, &tile) in env.tile_mapping.iter().enumerate() {
+            if letter_set & (1 << letter) != 0 {
+                env.word.push(tile);
+                env.machine_words.push(env.word[..].into());
+                env.word.pop();
+            }
+        }
+        for arc_p in p + 1..p + 1 + num_arcs {
+            let arc = match env.nodes.get(arc_p) {
+                Some(&arc) => arc,
+                None => {
+                    return_error!(format!("node {} out of bounds", arc_p));
+                }
+            };
+            let letter = (arc >> 24) as usize;
+            if letter == 0x32 {
+                continue;
+            }
+            let tile = match env.tile_mapping.get(letter) {
+                Some(&tile) => tile,
+                None => {
+                    return_error!(format!("node {} has invalid letter {}", arc_p, letter));
+                }
+            };
+            env.word.push(tile);
+            env.depth += 1;
+            iter(env, (arc & 0xffffff) as usize)?;
+            env.depth -= 1;
+            env.word.pop();
+        }
+        Ok(())
+    }
+
+    let mut machine_words = Vec::new();
+    if !nodes.is_empty() {
+        iter(
+            &mut Env {
+                nodes: &nodes,
+                letter_sets: &letter_sets,
+                tile_mapping: &tile_mapping,
+                word: &mut Vec::new(),
+                machine_words: &mut machine_words,
+                depth: 0,
+            },
+            0,
+        )?;
+    }
+    if is_gaddag {
+        machine_words = machine_words
+            .into_iter()
+            .map(|word| {
+                let mut v = word.to_vec();
+                v.reverse();
+                v[..].into()
+            })
+            .collect();
+    }
+    machine_words.sort_unstable();
+    machine_words.dedup();
+    Ok(machine_words.into_boxed_slice())
+}
+
+// Converts a Macondo gaddag or dawg file into a KWG.
+pub fn from_macondo(
+    alphabet: &alphabet::Alphabet,
+    bin: &[u8],
+    build_format: build::BuildFormat,
+) -> error::Returns<bites::Bites> {
+    build::build(build_format, &read_macondo_machine_words(alphabet, bin)?)
+}
+
+pub enum WordListLabels {
+    // as on rack, "CH"
+    Labels,
+    // as on board when blanked, "ch"
+    BlankLabels,
+    // multi-codepoint tiles are bracketed, "[CH]"
+    Bracketed,
+}
+
+// Writes the words in the dawg part of a KWG, one per line, in tile order.
+pub fn to_word_list<'a>(
+    kwg: &'a kwg::Kwg,
+    alphabet: &'a alphabet::Alphabet<'a>,
+    labels: WordListLabels,
+) -> Box<[u8]> {
+    let tile_labels = (0..alphabet.len())
+        .map(|tile| {
+            if tile == 0 {
+                String::new()
+            } else {
+                match labels {
+                    WordListLabels::Labels => alphabet.from_rack(tile).unwrap().to_string(),
+                    WordListLabels::BlankLabels => {
+                        alphabet.from_board(tile | 0x80).unwrap().to_string()
+                    }
+                    WordListLabels::Bracketed => {
+                        let label = alphabet.from_rack(tile).unwrap();
+                        if label.chars().count() > 1 {
+                            format!("[{}]", label)
+                        } else {
+                            label.to_string()
+                        }
+                    }
+                }
+            }
+        })
+        .collect::<Box<_>>();
+
+    struct Env<'a> {
+        kwg: &'a kwg::Kwg,
+        tile_labels: &'a [String],
+        s: &'a mut String,
+        out: &'a mut String,
+    }
+    fn iter(env: &mut Env, mut p: i32) {
+        let l = env.s.len();
+        loop {
+            let node = env.kwg[p];
+            env.s.push_str(&env.tile_labels[node.tile() as usize]);
+            if node.accepts() {
+                env.out.push_str(env.s);
+                env.out.push('\n');
+            }
+            if node.arc_index() != 0 {
+                iter(env, node.arc_index());
+            }
+            env.s.truncate(l);
+            if node.is_end() {
+                break;
+            }
+            p += 1;
+        }
+    }
+
+    let mut out = String::new();
+    let dawg_root = kwg[0].arc_index();
+    if dawg_root != 0 {
+        iter(
+            &mut Env {
+                kwg,
+                tile_labels: &tile_labels,
+                s: &mut String::new(),
+                out: &mut out,
+            },
+            dawg_root,
+        );
+    }
+    out.into_bytes().into_boxed_slice()
+}
+
+// Node lists reachable from one root, renumbered compactly.
+// Element 0 points to the root list like kwg[0] does.
+struct CopiedNode {
+    tile: u8,
+    accepts: bool,
+    is_end: bool,
+    arc_index: u32,
+}
+
+fn copy_reachable_nodes(kwg: &kwg::Kwg, root: i32) -> Vec<CopiedNode> {
+    struct Env<'a> {
+        kwg: &'a kwg::Kwg,
+        node_indexes: &'a mut [u32],
+        nodes: &'a mut Vec<CopiedNode>,
+    }
+    fn iter(env: &mut Env, mut p: i32) -> u32 {
+        let mut w = env.node_indexes[p as usize];
+        if w != 0 {
+            return w;
+        }
+        w = env.nodes.len() as u32;
+        env.node_indexes[p as usize] = w;
+        // reserve the whole list first so it is contiguous
+        let orig_p = p;
+        loop {
+            let node = env.kwg[p];
+            env.nodes.push(CopiedNode {
+                tile: node.tile(),
+                accepts: node.accepts(),
+                is_end: node.is_end(),
+                arc_index: 0,
+            });
+            if node.is_end() {
+                break;
+            }
+            p += 1;
+        }
+        for ofs in 0..=(p - orig_p) {
+            let arc_index = env.kwg[orig_p + ofs].arc_index();
+            if arc_index != 0 {
+                env.nodes[(w + ofs as u32) as usize].arc_index = iter(env, arc_index);
+            }
+        }
+        w
+    }
+
+    let mut nodes = vec![CopiedNode {
+        tile: 0,
+        accepts: false,
+        is_end: true,
+        arc_index: 0,
+    }];
+    if root != 0 {
+        let root_index = iter(
+            &mut Env {
+                kwg,
+                node_indexes: &mut vec![0u32; kwg.0.len()],
+                nodes: &mut nodes,
+            },
+            root,
+        );
+        nodes[0].arc_index = root_index;
+    }
+    nodes
+}
+
+pub enum QuackleFormat {
+    Dawg,
+    Gaddag,
+}
+
+// Quackle numbers letters after its reserved marks, the gaddag separator is 0.
+const QUACKLE_FIRST_LETTER: u8 = 5;
+
+// Quackle project is at https://github.com/quackle/quackle/.
+// This writes Quackle-style nodes, each is big endian of
+// bits 8-31 = first child index (0 if none)
+// bits 0-5 = letter
+// bit 6 = terminal
+// bit 7 = last child
+// Node 0 is the root, same as KWG.
+pub fn to_quackle<'a>(
+    kwg: &'a kwg::Kwg,
+    alphabet: &'a alphabet::Alphabet<'a>,
+    build_format: QuackleFormat,
+) -> Box<[u8]> {
+    assert!(
+        alphabet.len() - 1 + QUACKLE_FIRST_LETTER <= 0x40,
+        "too many letters for quackle"
+    );
+    let nodes = copy_reachable_nodes(
+        kwg,
+        kwg[match build_format {
+            QuackleFormat::Dawg => 0,
+            QuackleFormat::Gaddag => 1,
+        }]
+        .arc_index(),
+    );
+    assert!(nodes.len() <= 0x1000000, "too many nodes for quackle");
+    let mut bin = vec![0u8; nodes.len() * 4];
+    for (w, node) in bin.chunks_exact_mut(4).zip(nodes.iter()) {
+        w[0..3].copy_from_slice(&node.arc_index.to_be_bytes()[1..4]);
+        w[3] = (if node.tile == 0 {
+            0
+        } else {
+            node.tile - 1 + QUACKLE_FIRST_LETTER
+        }) | (0x40 & -(node.accepts as i8) as u8)
+            | (0x80 & -(node.is_end as i8) as u8);
+    }
+    bin.into_boxed_slice()
+}
+
+// Writes the dawg part of a KWG as compact JSON for browser use:
+// {"name":...,"alphabet":["?","A",...],"nodes":[...]}
+// where each node is the same u32 as in KWG. nodes[0] points to the root list.
+pub fn to_json_trie<'a>(
+    kwg: &'a kwg::Kwg,
+    alphabet: &'a alphabet::Alphabet<'a>,
+    lexicon_name: &'a str,
+) -> error::Returns<String> {
+    #[derive(serde::Serialize)]
+    struct JsonTrie<'a> {
+        name: &'a str,
+        alphabet: Vec<&'a str>,
+        nodes: Vec<u32>,
+    }
+    let nodes = copy_reachable_nodes(kwg, kwg[0].arc_index())
+        .iter()
+        .map(|node| {
+            node.arc_index
+                | ((node.is_end as u32) << 22)
+                | ((node.accepts as u32) << 23)
+                | ((node.tile as u32) << 24)
+        })
+        .collect();
+    Ok(serde_json::to_string(&JsonTrie {
+        name: lexicon_name,
+        alphabet: (0..alphabet.len())
+            .map(|tile| alphabet.from_rack(tile).unwrap())
+            .collect(),
+        nodes,
+    })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game_config;
+    use super::*;
+
+    #[test]
+    fn test_macondo_round_trip() {
+        let game_config = game_config::make_common_english_game_config();
+        let alphabet = game_config.alphabet();
+        // A AB BA CAB QI ZA
+        let machine_words = [&[1][..], &[1, 2], &[2, 1], &[3, 1, 2], &[17, 9], &[26, 1]]
+            .iter()
+            .map(|&word| word.into())
+            .collect::<Box<[bites::Bites]>>();
+        let kwg = kwg::Kwg::from_bytes_alloc(
+            &build::build(build::BuildFormat::Gaddawg, &machine_words).unwrap(),
+        );
+        for macondo_format in [MacondoFormat::Dawg, MacondoFormat::Gaddag] {
+            let bin = to_macondo(&kwg, alphabet, "TEST", macondo_format);
+            assert_eq!(
+                read_macondo_machine_words(alphabet, &bin).unwrap(),
+                machine_words
+            );
+            let round_tripped = kwg::Kwg::from_bytes_alloc(
+                &from_macondo(alphabet, &bin, build::BuildFormat::Gaddawg).unwrap(),
+            );
+            assert_eq!(
+                build::read_machine_words_from_kwg(&round_tripped),
+                machine_words
+            );
+        }
+    }
+}
@@ -4,9 +4,12 @@
 pub mod error;
 
 pub mod alphabet;
+pub mod autoplay_log;
 pub mod bag;
 pub mod bites;
+pub mod bits;
 pub mod board_layout;
+pub mod bot_ladder;
 pub mod build;
 pub mod display;
 pub mod endgame;
@@ -17,6 +20,9 @@ pub mod game_timers;
 pub mod kibitzer;
 pub mod klv;
 pub mod kwg;
+pub mod kwm;
+pub mod leave_model;
+pub mod letter_sets;
 pub mod lexport;
 pub mod matrix;
 pub mod move_filter;
@@ -24,5 +30,7 @@ pub mod move_picker;
 pub mod movegen;
 pub mod play_scorer;
 pub mod prob;
+pub mod rating;
 pub mod simmer;
 pub mod stats;
+pub mod tournament;
@@ -2,18 +2,22 @@
 
 use rand::prelude::*;
 use wolges::{
-    display, error, game_config, game_state, game_timers, klv, kwg, move_filter, move_picker,
-    movegen, play_scorer,
+    autoplay_log, bot_ladder, display, error, fash, game_config, game_state, game_timers, klv, kwg,
+    move_filter, move_picker, movegen, play_scorer, rating, tournament,
 };
 
 pub fn main() -> error::Returns<()> {
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.len() > 1 {
+        return tournament_main(&args);
+    }
     let jumbled = true;
     let kwg = if jumbled {
         kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/CSW19.kad")?)
     } else {
         kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/CSW19.kwg")?)
     };
-    let klv = klv::Klv::from_bytes_alloc(&std::fs::read("lexbin/leaves.klv")?);
+    let klv = klv::Klv::from_bytes_alloc(&std::fs::read("lexbin/leaves.klv")?)?;
     let game_config = &if jumbled {
         game_config::make_jumbled_english_game_config()
     } else {
@@ -84,6 +88,8 @@ pub fn main() -> error::Returns<()> {
                     rack: &game_state.current_player().rack,
                     max_gen: usize::MAX,
                     always_include_pass: false,
+                    num_tiles_in_bag: Some(game_state.bag.0.len() as i16),
+                    constraints: None,
                 });
                 let plays = &mut move_generator.plays;
                 println!("{} moves found...", plays.len());
@@ -97,11 +103,14 @@ pub fn main() -> error::Returns<()> {
                 game_config::GameRules::Classic => true,
                 game_config::GameRules::Jumbled => true,
             } {
-                let leave_scale = if let move_filter::GenMoves::Tilt { tilt, .. } = filtered_movegen
-                {
-                    tilt.leave_scale
-                } else {
-                    1.0
+                let evaluator = movegen::KlvEvaluator {
+                    leave_scale: if let move_filter::GenMoves::Tilt { tilt, .. } = filtered_movegen
+                    {
+                        tilt.leave_scale
+                    } else {
+                        1.0
+                    },
+                    ..Default::default()
                 };
                 move_generator.gen_moves_filtered(
                     &movegen::GenMovesParams {
@@ -109,6 +118,8 @@ pub fn main() -> error::Returns<()> {
                         rack: &game_state.current_player().rack,
                         max_gen: usize::MAX,
                         always_include_pass: true,
+                        num_tiles_in_bag: Some(game_state.bag.0.len() as i16),
+                        constraints: None,
                     },
                     |_down: bool,
                      _lane: i8,
@@ -116,7 +127,7 @@ pub fn main() -> error::Returns<()> {
                      _word: &[u8],
                      _score: i16,
                      _rack_tally: &[u8]| true,
-                    |leave_value: f32| leave_scale * leave_value,
+                    &evaluator,
                     |_equity: f32, _play: &movegen::Play| true,
                 );
                 let plays = &mut move_generator.plays;
@@ -156,7 +167,7 @@ pub fn main() -> error::Returns<()> {
                                     board_snapshot,
                                     &game_state,
                                     &play.play,
-                                    leave_scale,
+                                    &evaluator,
                                     recounted_score,
                                 );
                                 // If leave_scale is negative these may be 0.0 and -0.0.
@@ -188,6 +199,7 @@ pub fn main() -> error::Returns<()> {
                 &board_snapshot,
                 &game_state,
                 &game_state.current_player().rack,
+                &mut rng,
             );
             let plays = &mut move_generator.plays;
             let play = &plays[0].play; // assume at least there's always Pass
@@ -231,3 +243,761 @@ pub fn main() -> error::Returns<()> {
 
     //Ok(())
 }
+
+fn print_usage() {
+    println!(
+        "args:
+  (none)
+    play and print games forever, checking the move generator
+  english-tournament bots.json games [options]
+    play games (per pair of bots) between every pair of bots, each bag twice
+    with the first player swapped, and report the results.
+    bots.json is an array of bots, such as
+      [ {{ \"name\": \"static\", \"kwg\": \"lexbin/CSW19.kwg\",
+          \"klv\": \"lexbin/leaves.klv\" }},
+        {{ \"name\": \"tilt3\", \"kwg\": \"lexbin/CSW19.kwg\", \"bot_level\": 3 }},
+        {{ \"name\": \"sim\", \"kwg\": \"lexbin/CSW19.kwg\",
+          \"klv\": \"lexbin/leaves.klv\", \"simmer\": {{ \"candidates\": 20,
+          \"plies\": 2, \"iterations\": 200, \"max_time_ms\": 1000 }} }} ]
+    where klv is optional (no leave), bot_level uses Tilt, vocab (a kwg)
+    limits the words it knows, simmer (with every setting optional) sims
+    instead of taking the top move, and top_n picks any of the top n moves.
+    names can only have letters, digits, _ and .
+    options:
+      -threads N (defaults to number of cpus)
+      -seed N (defaults to random, the same seed deals the same bags, and plays
+        the same games unless a bot sims)
+      -dir . (writes dir/games-A-vs-B per pair and dir/tournament.json)
+  english-ladder CSW19.kwg common.kwg leaves.klv games [options]
+    play games between each bot level and the next stronger one, then
+    estimate ratings for all levels, with the strongest at -rating 2000.
+    common.kwg has the words the weaker levels know (\"-\" to skip them).
+    options are as for tournament, also writes dir/ladder.json.
+  english-ratings [options] games-A-vs-B ... A,B=games-log-12345678 ...
+    fit elo ratings to the results in games files (csv or bin), from
+    tournaments (the file name says who played) or from autoplay (where A was
+    p1 and B was p2), and print a leaderboard with 95% confidence intervals.
+    options:
+      -anchor NAME (defaults to the average of everyone)
+      -rating 1500 (the anchor's rating)
+      -prior 2 (virtual drawn games each player has, keeps ratings finite)
+  (english can also be catalan, danish, dutch, finnish, french, german, italian,
+   norwegian, polish, portuguese, slovene, spanish, swedish)"
+    );
+}
+
+fn tournament_main(args: &[String]) -> error::Returns<()> {
+    let t0 = std::time::Instant::now();
+    if do_lang(
+        args,
+        "english",
+        game_config::make_common_english_game_config,
+    )? || do_lang(args, "catalan", game_config::make_catalan_game_config)?
+        || do_lang(args, "danish", game_config::make_danish_game_config)?
+        || do_lang(args, "dutch", game_config::make_dutch_game_config)?
+        || do_lang(args, "finnish", game_config::make_finnish_game_config)?
+        || do_lang(args, "french", game_config::make_french_game_config)?
+        || do_lang(args, "german", game_config::make_german_game_config)?
+        || do_lang(args, "italian", game_config::make_italian_game_config)?
+        || do_lang(args, "norwegian", game_config::make_norwegian_game_config)?
+        || do_lang(args, "polish", game_config::make_polish_game_config)?
+        || do_lang(args, "portuguese", game_config::make_portuguese_game_config)?
+        || do_lang(args, "slovene", game_config::make_slovene_game_config)?
+        || do_lang(args, "spanish", game_config::make_spanish_game_config)?
+        || do_lang(args, "swedish", game_config::make_swedish_game_config)?
+    {
+    } else {
+        print_usage();
+        return Err("invalid argument".into());
+    }
+    println!("time taken: {:?}", t0.elapsed());
+    Ok(())
+}
+
+fn do_lang<GameConfigMaker: Fn() -> game_config::GameConfig<'static>>(
+    args: &[String],
+    language_name: &str,
+    make_game_config: GameConfigMaker,
+) -> error::Returns<bool> {
+    match args[1].strip_prefix(language_name) {
+        Some("-tournament") => {
+            if args.len() < 4 {
+                print_usage();
+                return Err("not enough arguments".into());
+            }
+            let options = parse_options(&args[4..], &["-threads", "-seed", "-dir"])?;
+            let tournament_options = make_tournament_options(&args[3], &options)?;
+            let bots = load_bots(&std::fs::read_to_string(&args[2])?)?;
+            let pairings = tournament::pairings(bots.len());
+            run_tournament(
+                make_game_config(),
+                std::sync::Arc::new(bots),
+                pairings,
+                &tournament_options,
+                options.get("-dir").map_or(".", |x| x).as_ref(),
+            )?;
+            Ok(true)
+        }
+        Some("-ladder") => {
+            if args.len() < 6 {
+                print_usage();
+                return Err("not enough arguments".into());
+            }
+            let options = parse_options(&args[6..], &["-threads", "-seed", "-dir", "-rating"])?;
+            let tournament_options = make_tournament_options(&args[5], &options)?;
+            let top_rating = options.get("-rating").map_or(Ok(2000.0), |x| x.parse())?;
+            let kwg = std::sync::Arc::new(kwg::Kwg::from_bytes_alloc(&std::fs::read(&args[2])?));
+            let vocab_kwg = if args[3] == "-" {
+                None
+            } else {
+                Some(std::sync::Arc::new(kwg::Kwg::from_bytes_alloc(
+                    &std::fs::read(&args[3])?,
+                )))
+            };
+            let klv = std::sync::Arc::new(klv::Klv::from_bytes_alloc(&std::fs::read(&args[4])?)?);
+            run_ladder(
+                make_game_config(),
+                kwg,
+                vocab_kwg,
+                klv,
+                top_rating,
+                &tournament_options,
+                options.get("-dir").map_or(".", |x| x).as_ref(),
+            )?;
+            Ok(true)
+        }
+        Some("-ratings") => {
+            let mut options = fash::MyHashMap::default();
+            let mut games_files = Vec::new();
+            let mut i = 2;
+            while i < args.len() {
+                if ["-anchor", "-rating", "-prior"].contains(&args[i].as_str()) {
+                    if i + 1 >= args.len() {
+                        wolges::return_error!(format!("{} needs a value", args[i]));
+                    }
+                    options.insert(args[i].as_str(), args[i + 1].as_str());
+                    i += 2;
+                } else {
+                    games_files.push(args[i].as_str());
+                    i += 1;
+                }
+            }
+            if games_files.is_empty() {
+                print_usage();
+                return Err("not enough arguments".into());
+            }
+            print_ratings(
+                &make_game_config(),
+                &games_files,
+                options.get("-anchor").copied(),
+                options.get("-rating").map_or(Ok(1500.0), |x| x.parse())?,
+                options.get("-prior").map_or(Ok(2.0), |x| x.parse())?,
+            )?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+// options come in pairs, such as -threads 4.
+fn parse_options<'a>(
+    args: &'a [String],
+    valid_options: &[&str],
+) -> error::Returns<fash::MyHashMap<&'a str, &'a str>> {
+    let mut options = fash::MyHashMap::default();
+    for pair in args.chunks(2) {
+        if !valid_options.contains(&pair[0].as_str()) {
+            wolges::return_error!(format!("invalid option {}", pair[0]));
+        }
+        if pair.len() < 2 {
+            wolges::return_error!(format!("{} needs a value", pair[0]));
+        }
+        options.insert(pair[0].as_str(), pair[1].as_str());
+    }
+    Ok(options)
+}
+
+fn make_tournament_options(
+    games_per_pairing: &str,
+    options: &fash::MyHashMap<&str, &str>,
+) -> error::Returns<tournament::TournamentOptions> {
+    Ok(tournament::TournamentOptions {
+        games_per_pairing: games_per_pairing.parse()?,
+        num_threads: match options.get("-threads") {
+            Some(x) => x.parse()?,
+            None => num_cpus::get(),
+        },
+        seed: match options.get("-seed") {
+            Some(x) => x.parse()?,
+            None => rand_chacha::ChaCha20Rng::from_entropy().next_u64(),
+        },
+    })
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BotSpec {
+    name: String,
+    kwg: String,
+    #[serde(default)]
+    klv: Option<String>,
+    #[serde(default)]
+    bot_level: Option<i8>,
+    #[serde(default)]
+    vocab: Option<String>,
+    #[serde(default)]
+    simmer: Option<SimmerSpec>,
+    #[serde(default)]
+    top_n: Option<usize>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SimmerSpec {
+    #[serde(default)]
+    candidates: Option<usize>,
+    #[serde(default)]
+    plies: Option<usize>,
+    #[serde(default)]
+    iterations: Option<usize>,
+    #[serde(default)]
+    max_time_ms: Option<u64>,
+}
+
+// bots sharing a kwg or klv file share the loaded copy.
+fn is_valid_bot_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+fn load_bots(json: &str) -> error::Returns<Vec<tournament::Bot>> {
+    let specs = serde_json::from_str::<Vec<BotSpec>>(json)?;
+    if specs.len() < 2 {
+        wolges::return_error!("need at least two bots".into());
+    }
+    let mut kwgs = fash::MyHashMap::<String, std::sync::Arc<kwg::Kwg>>::default();
+    let mut klvs = fash::MyHashMap::<String, std::sync::Arc<klv::Klv>>::default();
+    let mut bots = Vec::with_capacity(specs.len());
+    for spec in specs {
+        if !is_valid_bot_name(&spec.name) {
+            wolges::return_error!(format!("invalid bot name {:?}", spec.name));
+        }
+        if bots
+            .iter()
+            .any(|bot: &tournament::Bot| bot.name == spec.name)
+        {
+            wolges::return_error!(format!("duplicate bot name {:?}", spec.name));
+        }
+        let kwg = match kwgs.get(&spec.kwg) {
+            Some(kwg) => std::sync::Arc::clone(kwg),
+            None => {
+                let kwg =
+                    std::sync::Arc::new(kwg::Kwg::from_bytes_alloc(&std::fs::read(&spec.kwg)?));
+                kwgs.insert(spec.kwg.clone(), std::sync::Arc::clone(&kwg));
+                kwg
+            }
+        };
+        let klv_path = spec.klv.unwrap_or_else(|| "-".into());
+        let klv = match klvs.get(&klv_path) {
+            Some(klv) => std::sync::Arc::clone(klv),
+            None => {
+                let klv = std::sync::Arc::new(klv::Klv::from_bytes_alloc(&if klv_path == "-" {
+                    klv::EMPTY_KLV_BYTES.to_vec()
+                } else {
+                    std::fs::read(&klv_path)?
+                })?);
+                klvs.insert(klv_path, std::sync::Arc::clone(&klv));
+                klv
+            }
+        };
+        let filter = match (spec.bot_level, spec.vocab) {
+            (None, None) => tournament::Filter::Unfiltered,
+            (Some(bot_level), None) => tournament::Filter::Tilt(bot_level),
+            (None, Some(vocab_path)) => {
+                tournament::Filter::LimitedVocab(match kwgs.get(&vocab_path) {
+                    Some(kwg) => std::sync::Arc::clone(kwg),
+                    None => {
+                        let kwg = std::sync::Arc::new(kwg::Kwg::from_bytes_alloc(&std::fs::read(
+                            &vocab_path,
+                        )?));
+                        kwgs.insert(vocab_path, std::sync::Arc::clone(&kwg));
+                        kwg
+                    }
+                })
+            }
+            (Some(_), Some(_)) => {
+                wolges::return_error!(format!("{} has both bot_level and vocab", spec.name));
+            }
+        };
+        let picker = match (spec.simmer, spec.top_n) {
+            (Some(_), Some(_)) => {
+                wolges::return_error!(format!("{} has both simmer and top_n", spec.name));
+            }
+            (None, Some(top_n)) => tournament::Picker::RandomTopN(top_n),
+            (Some(simmer), None) => {
+                let default_settings = move_picker::SimmerSettings::default();
+                tournament::Picker::Simmer(move_picker::SimmerSettings {
+                    num_candidates: simmer.candidates.unwrap_or(default_settings.num_candidates),
+                    num_sim_plies: simmer.plies.unwrap_or(default_settings.num_sim_plies),
+                    num_sim_iters: simmer.iterations.unwrap_or(default_settings.num_sim_iters),
+                    max_time_for_move_ms: simmer
+                        .max_time_ms
+                        .unwrap_or(default_settings.max_time_for_move_ms),
+                    verbose: false,
+                })
+            }
+            (None, None) => tournament::Picker::Hasty,
+        };
+        bots.push(tournament::Bot {
+            name: spec.name,
+            kwg,
+            klv,
+            filter,
+            picker,
+        });
+    }
+    Ok(bots)
+}
+
+#[derive(serde::Serialize)]
+struct PairingReport<'a> {
+    bots: [&'a str; 2],
+    games: u64,
+    wins: u64,
+    draws: u64,
+    losses: u64,
+    win_rate: f64,
+    win_rate_ci: [f64; 2],
+    mean_spread: f64,
+    spread_ci: [f64; 2],
+    bingos_per_game: [f64; 2],
+    ms_per_move: [f64; 2],
+}
+
+#[derive(serde::Serialize)]
+struct BotReport<'a> {
+    name: &'a str,
+    games: u64,
+    wins: u64,
+    draws: u64,
+    losses: u64,
+    win_rate: f64,
+    mean_spread: f64,
+    bingos_per_game: f64,
+    ms_per_move: f64,
+}
+
+#[derive(serde::Serialize)]
+struct TournamentReport<'a> {
+    seed: u64,
+    games_per_pairing: u64,
+    pairings: Vec<PairingReport<'a>>,
+    bots: Vec<BotReport<'a>>,
+}
+
+fn make_tournament_report<'a>(
+    bots: &'a [tournament::Bot],
+    options: &tournament::TournamentOptions,
+    results: &[tournament::PairingResults],
+) -> TournamentReport<'a> {
+    const Z: f64 = 1.96; // 95% confidence interval
+    let pairings = results
+        .iter()
+        .map(|result| PairingReport {
+            bots: [&bots[result.bots[0]].name, &bots[result.bots[1]].name],
+            games: result.num_games(),
+            wins: result.wins,
+            draws: result.draws,
+            losses: result.losses,
+            win_rate: result.pair_wins.mean(),
+            win_rate_ci: [
+                result.pair_wins.ci_min(Z).max(0.0),
+                result.pair_wins.ci_max(Z).min(1.0),
+            ],
+            mean_spread: result.pair_spreads.mean(),
+            spread_ci: [result.pair_spreads.ci_min(Z), result.pair_spreads.ci_max(Z)],
+            bingos_per_game: [
+                result.totals[0].bingos_per_game(),
+                result.totals[1].bingos_per_game(),
+            ],
+            ms_per_move: [
+                result.totals[0].ms_per_move(),
+                result.totals[1].ms_per_move(),
+            ],
+        })
+        .collect();
+    let bot_reports = bots
+        .iter()
+        .enumerate()
+        .map(|(bot_idx, bot)| {
+            let mut wins = 0;
+            let mut draws = 0;
+            let mut losses = 0;
+            let mut total_spread = 0.0;
+            let mut totals = tournament::BotTotals::default();
+            for result in results.iter() {
+                if result.bots[0] == bot_idx {
+                    wins += result.wins;
+                    losses += result.losses;
+                    total_spread += result.pair_spreads.mean() * result.num_games() as f64;
+                    totals.merge(&result.totals[0]);
+                } else if result.bots[1] == bot_idx {
+                    wins += result.losses;
+                    losses += result.wins;
+                    total_spread -= result.pair_spreads.mean() * result.num_games() as f64;
+                    totals.merge(&result.totals[1]);
+                } else {
+                    continue;
+                }
+                draws += result.draws;
+            }
+            let games = wins + draws + losses;
+            BotReport {
+                name: &bot.name,
+                games,
+                wins,
+                draws,
+                losses,
+                win_rate: (wins as f64 + draws as f64 * 0.5) / games as f64,
+                mean_spread: total_spread / games as f64,
+                bingos_per_game: totals.bingos_per_game(),
+                ms_per_move: totals.ms_per_move(),
+            }
+        })
+        .collect();
+    TournamentReport {
+        seed: options.seed,
+        games_per_pairing: options.games_per_pairing,
+        pairings,
+        bots: bot_reports,
+    }
+}
+
+fn print_tournament_report(report: &TournamentReport) {
+    let name_width = report
+        .bots
+        .iter()
+        .map(|bot| bot.name.len())
+        .max()
+        .unwrap_or(0);
+    println!(
+        "{:>w$} {:<w$} {:>7} {:>7} {:>18} {:>8} {:>20} {:>11} {:>17}",
+        "",
+        "",
+        "games",
+        "win%",
+        "(95% ci)",
+        "spread",
+        "(95% ci)",
+        "bingos/game",
+        "ms/move",
+        w = name_width
+    );
+    for pairing in report.pairings.iter() {
+        println!(
+            "{:>w$} {:<w$} {:>7} {:>7.2} ({:>6.2} to {:>6.2}) {:>8.2} ({:>7.2} to {:>7.2}) {:>5.3} {:>5.3} {:>8.2} {:>8.2}",
+            pairing.bots[0],
+            pairing.bots[1],
+            pairing.games,
+            pairing.win_rate * 100.0,
+            pairing.win_rate_ci[0] * 100.0,
+            pairing.win_rate_ci[1] * 100.0,
+            pairing.mean_spread,
+            pairing.spread_ci[0],
+            pairing.spread_ci[1],
+            pairing.bingos_per_game[0],
+            pairing.bingos_per_game[1],
+            pairing.ms_per_move[0],
+            pairing.ms_per_move[1],
+            w = name_width
+        );
+    }
+    println!();
+    println!(
+        "{:<w$} {:>7} {:>7} {:>7} {:>7} {:>7} {:>8} {:>11} {:>8}",
+        "bot",
+        "games",
+        "wins",
+        "draws",
+        "losses",
+        "win%",
+        "spread",
+        "bingos/game",
+        "ms/move",
+        w = name_width
+    );
+    for bot in report.bots.iter() {
+        println!(
+            "{:<w$} {:>7} {:>7} {:>7} {:>7} {:>7.2} {:>8.2} {:>11.3} {:>8.2}",
+            bot.name,
+            bot.games,
+            bot.wins,
+            bot.draws,
+            bot.losses,
+            bot.win_rate * 100.0,
+            bot.mean_spread,
+            bot.bingos_per_game,
+            bot.ms_per_move,
+            w = name_width
+        );
+    }
+}
+
+fn run_tournament(
+    game_config: game_config::GameConfig<'static>,
+    bots: std::sync::Arc<Vec<tournament::Bot>>,
+    pairings: Vec<[usize; 2]>,
+    options: &tournament::TournamentOptions,
+    dir: &std::path::Path,
+) -> error::Returns<Vec<tournament::PairingResults>> {
+    let mut games_writers = Vec::with_capacity(pairings.len());
+    for &[a, b] in pairings.iter() {
+        let mut games_writer = autoplay_log::Writer::new(
+            autoplay_log::Format::Csv,
+            std::io::BufWriter::new(std::fs::File::create(
+                dir.join(format!("games-{}-vs-{}", bots[a].name, bots[b].name)),
+            )?),
+        );
+        games_writer.write_games_header(2)?;
+        games_writers.push(games_writer);
+    }
+    println!(
+        "{} bots, {} pairings, seed {}",
+        bots.len(),
+        pairings.len(),
+        options.seed
+    );
+    let results = tournament::run(
+        std::sync::Arc::new(game_config),
+        std::sync::Arc::clone(&bots),
+        pairings,
+        options,
+        games_writers,
+    )?;
+    let report = make_tournament_report(&bots, options, &results);
+    print_tournament_report(&report);
+    std::fs::write(
+        dir.join("tournament.json"),
+        serde_json::to_string_pretty(&report)?,
+    )?;
+    Ok(results)
+}
+
+#[derive(serde::Serialize)]
+struct LadderLevelReport {
+    name: &'static str,
+    limited_vocab: bool,
+    use_leaves: bool,
+    top_n: usize,
+    rating: f64,
+    rating_ci: [f64; 2],
+}
+
+// each level plays the next stronger level, and the rating differences are
+// chained down from the strongest level, so the uncertainty adds up.
+fn run_ladder(
+    game_config: game_config::GameConfig<'static>,
+    kwg: std::sync::Arc<kwg::Kwg>,
+    vocab_kwg: Option<std::sync::Arc<kwg::Kwg>>,
+    klv: std::sync::Arc<klv::Klv>,
+    top_rating: f64,
+    options: &tournament::TournamentOptions,
+    dir: &std::path::Path,
+) -> error::Returns<()> {
+    const Z: f64 = 1.96; // 95% confidence interval
+    let no_klv = std::sync::Arc::new(klv::Klv::from_bytes_alloc(klv::EMPTY_KLV_BYTES)?);
+    let levels = bot_ladder::LEVELS
+        .iter()
+        .filter(|level| vocab_kwg.is_some() || !level.limited_vocab)
+        .collect::<Vec<_>>();
+    let bots = levels
+        .iter()
+        .map(|level| level.make_bot(&kwg, vocab_kwg.as_ref().unwrap_or(&kwg), &klv, &no_klv))
+        .collect::<Vec<_>>();
+    let pairings = (1..levels.len()).map(|i| [i - 1, i]).collect();
+    let results = run_tournament(
+        game_config,
+        std::sync::Arc::new(bots),
+        pairings,
+        options,
+        dir,
+    )?;
+    let mut ratings = vec![(top_rating, 0.0f64); levels.len()];
+    for (i, result) in results.iter().enumerate().rev() {
+        // avoid infinite differences when one side always wins.
+        let num_pairs = result.pair_wins.count();
+        let score_rate = result
+            .pair_wins
+            .mean()
+            .max(0.5 / num_pairs)
+            .min(1.0 - 0.5 / num_pairs);
+        let mut standard_error = (result.pair_wins.variance() / num_pairs).sqrt();
+        if standard_error == 0.0 {
+            standard_error = (score_rate * (1.0 - score_rate) / (2.0 * num_pairs)).sqrt();
+        }
+        let standard_error = standard_error * bot_ladder::elo_difference_slope(score_rate);
+        ratings[i] = (
+            ratings[i + 1].0 + bot_ladder::elo_difference(score_rate),
+            ratings[i + 1].1 + standard_error * standard_error,
+        );
+    }
+    let report = levels
+        .iter()
+        .zip(ratings.iter())
+        .map(|(level, &(rating, variance))| LadderLevelReport {
+            name: level.name,
+            limited_vocab: level.limited_vocab,
+            use_leaves: level.use_leaves,
+            top_n: level.top_n,
+            rating,
+            rating_ci: [rating - Z * variance.sqrt(), rating + Z * variance.sqrt()],
+        })
+        .collect::<Vec<_>>();
+    println!();
+    println!(
+        "{:<8} {:>5} {:>6} {:>5} {:>7} {:>20}",
+        "level", "vocab", "leaves", "top", "rating", "(95% ci)"
+    );
+    for level in report.iter() {
+        println!(
+            "{:<8} {:>5} {:>6} {:>5} {:>7.0} ({:>7.0} to {:>7.0})",
+            level.name,
+            if level.limited_vocab { "some" } else { "all" },
+            if level.use_leaves { "yes" } else { "no" },
+            level.top_n,
+            level.rating,
+            level.rating_ci[0],
+            level.rating_ci[1],
+        );
+    }
+    std::fs::write(
+        dir.join("ladder.json"),
+        serde_json::to_string_pretty(&report)?,
+    )?;
+    Ok(())
+}
+
+// tournaments name games files games-A-vs-B, others need A,B=path.
+fn parse_games_file_spec(spec: &str) -> error::Returns<(&str, &str, &str)> {
+    let mut parts = spec.splitn(2, '=');
+    let (names, path) = match (parts.next(), parts.next()) {
+        (Some(names), Some(path)) if names.contains(',') => (names, path),
+        _ => {
+            let file_name = std::path::Path::new(spec)
+                .file_name()
+                .and_then(|x| x.to_str())
+                .unwrap_or("");
+            match file_name.strip_prefix("games-") {
+                Some(names) => (names, spec),
+                None => {
+                    wolges::return_error!(format!("cannot tell who played in {}", spec));
+                }
+            }
+        }
+    };
+    let separator = if names.contains(',') { "," } else { "-vs-" };
+    let mut names_iter = names.splitn(2, separator);
+    match (names_iter.next(), names_iter.next()) {
+        (Some(p1), Some(p2)) if is_valid_bot_name(p1) && is_valid_bot_name(p2) => {
+            Ok((p1, p2, path))
+        }
+        _ => {
+            wolges::return_error!(format!("cannot tell who played in {}", spec));
+        }
+    }
+}
+
+fn print_ratings(
+    game_config: &game_config::GameConfig,
+    games_files: &[&str],
+    anchor: Option<&str>,
+    anchor_rating: f64,
+    prior_games: f64,
+) -> error::Returns<()> {
+    const Z: f64 = 1.96; // 95% confidence interval
+    let mut names = Vec::<String>::new();
+    let mut player_idx = |name: &str| match names.iter().position(|x| x == name) {
+        Some(idx) => idx,
+        None => {
+            names.push(name.into());
+            names.len() - 1
+        }
+    };
+    let mut games = Vec::new();
+    let mut row = autoplay_log::GameRow::default();
+    for spec in games_files {
+        let (p1, p2, path) = parse_games_file_spec(spec)?;
+        let players = [player_idx(p1), player_idx(p2)];
+        if players[0] == players[1] {
+            wolges::return_error!(format!("{} played itself in {}", p1, path));
+        }
+        let mut reader = autoplay_log::Reader::new(
+            game_config.alphabet(),
+            std::io::BufReader::new(std::fs::File::open(path)?),
+        )?;
+        if reader.kind() != autoplay_log::Kind::Games || reader.num_players() != 2 {
+            wolges::return_error!(format!("{} is not a two player games file", path));
+        }
+        while reader.read_game_row(&mut row)? {
+            let first = row.first as usize & 1;
+            games.push((
+                players[first],
+                players[first ^ 1],
+                row.scores[first],
+                row.scores[first ^ 1],
+            ));
+        }
+    }
+    let anchor_idx = match anchor {
+        Some(anchor) => match names.iter().position(|x| x == anchor) {
+            Some(idx) => Some(idx),
+            None => {
+                wolges::return_error!(format!("{} did not play", anchor));
+            }
+        },
+        None => None,
+    };
+    let mut results = rating::Results::new(names.len());
+    for &(first, second, first_score, second_score) in &games {
+        results.add_game(first, second, first_score, second_score);
+    }
+    let ratings = rating::Ratings::fit(&results, prior_games)?;
+    let totals = results.totals();
+    let mut leaderboard = (0..names.len())
+        .map(|i| {
+            let (rating, standard_error) = match anchor_idx {
+                Some(anchor_idx) => ratings.difference(i, anchor_idx),
+                None => ratings.relative_to_mean(i),
+            };
+            (i, anchor_rating + rating, standard_error)
+        })
+        .collect::<Vec<_>>();
+    leaderboard.sort_unstable_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    println!(
+        "{:<4} {:<20} {:>8} {:>7} {:>7} {:>20}",
+        "rank", "name", "games", "score%", "rating", "(95% ci)"
+    );
+    for (rank, &(i, rating, standard_error)) in leaderboard.iter().enumerate() {
+        println!(
+            "{:<4} {:<20} {:>8} {:>7.2} {:>7.0} ({:>7.0} to {:>7.0})",
+            rank + 1,
+            names[i],
+            totals[i].0,
+            totals[i].1 * 100.0 / totals[i].0,
+            rating,
+            rating - Z * standard_error,
+            rating + Z * standard_error,
+        );
+    }
+    let (first_advantage, standard_error) = ratings.first_advantage();
+    println!(
+        "{} games, going first is worth {:.0} ({:.0} to {:.0})",
+        games.len(),
+        first_advantage,
+        first_advantage - Z * standard_error,
+        first_advantage + Z * standard_error,
+    );
+    Ok(())
+}
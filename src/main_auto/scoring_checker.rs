@@ -189,7 +189,8 @@ impl ScoringChecker {
                     .for_each(|_| print!("{}", game_config.alphabet().from_rack(tile).unwrap()))
             });
         print!(" = ");
-        let leave_value = klv.leave_value_from_tally(&self.rack_tally);
+        let leave_value =
+            klv.leave_value_from_tally(&self.rack_tally, game_state.bag.0.len() as i16);
         println!("{}", leave_value);
 
         let mut recounted_equity = recounted_score as f32;
@@ -1,213 +1,127 @@
 // Copyright (C) 2020-2021 Andy Kurnia.
 
-use wolges::{alphabet, bites, build, error, fash, kwg, lexport, prob};
+use wolges::{alphabet, bites, build, error, game_config, klv, kwg, kwm, lexport, prob};
 
-fn read_machine_words(
-    alphabet_reader: &alphabet::AlphabetReader,
-    giant_string: &str,
-) -> error::Returns<Box<[bites::Bites]>> {
-    let mut machine_words = Vec::<bites::Bites>::new();
-    let mut v = Vec::new();
-    for s in giant_string.lines() {
-        if s.is_empty() {
-            continue;
-        }
-        let sb = s.as_bytes();
+// Reads a published word list leniently:
+// - BOM, CRLF, blank lines and #-comments are ignored.
+// - Only the first whitespace-separated field is used, the rest is definition.
+// - Unicode is normalized to NFC and either case is accepted.
+// The result is sorted and deduplicated in tile order,
+// each word comes with the first nonempty definition seen.
+fn read_word_list_entries<'a>(
+    alphabet: &alphabet::Alphabet,
+    giant_string: &'a str,
+) -> error::Returns<Vec<(bites::Bites, &'a str)>> {
+    use unicode_normalization::UnicodeNormalization;
+    // new_for_plays accepts both labels and blank labels.
+    let alphabet_reader = alphabet::AlphabetReader::new_for_plays(alphabet);
+    let parse = |s: &str, v: &mut Vec<u8>| -> bool {
         v.clear();
+        let sb = s.as_bytes();
         let mut ix = 0;
         while ix < sb.len() {
             if let Some((tile, end_ix)) = alphabet_reader.next_tile(sb, ix) {
-                v.push(tile);
+                v.push(tile & 0x7f);
                 ix = end_ix;
             } else {
-                wolges::return_error!(format!("invalid tile after {:?} in {:?}", v, s));
+                return false;
             }
         }
-        machine_words.push(v[..].into());
-    }
-    machine_words.sort_unstable();
-    machine_words.dedup();
-    Ok(machine_words.into_boxed_slice())
-}
-
-// This is rarely used, so it allocates a single-use AlphabetReader.
-fn read_polish_machine_words(giant_string: &str) -> error::Returns<Box<[bites::Bites]>> {
-    read_machine_words(
-        &alphabet::AlphabetReader::new_for_words(&alphabet::make_polish_alphabet()),
-        giant_string,
-    )
-}
-
-// This is a much faster replacement of
-// read_machine_words(
-//     &alphabet::AlphabetReader::new_for_racks(&alphabet::make_english_alphabet()),
-//     giant_string,
-// )
-// and requires a clean, pre-sorted input.
-fn read_english_machine_words_or_leaves(
-    blank: char,
-    giant_string: &str,
-) -> error::Returns<Box<[bites::Bites]>> {
-    let mut machine_words = Vec::<bites::Bites>::new();
+        true
+    };
+    let mut entries = Vec::<(bites::Bites, &str)>::new();
     let mut v = Vec::new();
-    for s in giant_string.lines() {
-        v.clear();
-        v.reserve(s.len());
-        // This is English-only, and will need adjustment for multibyte.
-        // The output must be 1-based because 0 has special meaning.
-        // It should also not be too high to fit in a u64 cross-set.
-        for c in s.chars() {
-            if ('A'..='Z').contains(&c) {
-                v.push((c as u8) & 0x3f);
-            } else if c == blank {
-                // Test this after the letters. Pass a letter to disable.
-                v.push(0);
-            } else {
-                wolges::return_error!(format!("invalid tile after {:?} in {:?}", v, s));
-            }
+    for (line_num, line) in giant_string
+        .strip_prefix('\u{feff}')
+        .unwrap_or(giant_string)
+        .lines()
+        .enumerate()
+    {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
         }
-        // Performance notes:
-        // - .last() is slow.
-        // - But the borrow checker does not like raw pointer.
-        match machine_words.last() {
-            Some(previous_v) => {
-                if v[..] <= previous_v[..] {
-                    wolges::return_error!(format!(
-                        "input is not sorted, {:?} cannot come after {:?}",
-                        v, previous_v
-                    ));
-                }
-            }
-            None => {
-                if v.is_empty() {
-                    wolges::return_error!("first line is blank".into());
-                }
-            }
+        let (word, definition) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim_start()),
+            None => (line, ""),
         };
-        machine_words.push(v[..].into());
+        let word = word.nfc().collect::<String>();
+        // uppercase first so that "Ch" is one tile, not C and blank-H.
+        if !parse(&word.to_uppercase(), &mut v) && !parse(&word, &mut v) {
+            wolges::return_error!(format!(
+                "line {}: invalid tile after {:?} in {:?}",
+                line_num + 1,
+                alphabet.fmt_rack(&v).to_string(),
+                word
+            ));
+        }
+        entries.push((v[..].into(), definition));
     }
-    Ok(machine_words.into_boxed_slice())
+    sort_and_dedup_entries(&mut entries);
+    Ok(entries)
 }
 
-/*
-#[inline(always)]
-fn read_english_leaves_machine_words(giant_string: &str) -> error::Returns<Box<[bites::Bites]>> {
-    read_english_machine_words_or_leaves('?', giant_string)
+// keeps the first nonempty definition of each word.
+fn sort_and_dedup_entries(entries: &mut Vec<(bites::Bites, &str)>) {
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries.dedup_by(|later, kept| {
+        if later.0 == kept.0 {
+            if kept.1.is_empty() {
+                kept.1 = later.1;
+            }
+            true
+        } else {
+            false
+        }
+    });
 }
-*/
 
-#[inline(always)]
-fn read_english_machine_words(giant_string: &str) -> error::Returns<Box<[bites::Bites]>> {
-    read_english_machine_words_or_leaves('A', giant_string)
+fn read_word_list(
+    alphabet: &alphabet::Alphabet,
+    giant_string: &str,
+) -> error::Returns<Box<[bites::Bites]>> {
+    let machine_words = read_word_list_entries(alphabet, giant_string)?
+        .into_iter()
+        .map(|(word, _)| word)
+        .collect::<Box<_>>();
+    print_word_counts_by_length(&machine_words);
+    Ok(machine_words)
 }
 
-use std::convert::TryInto;
-use std::str::FromStr;
-
-fn iter_dawg<'a, F: FnMut(&str)>(a: &alphabet::Alphabet<'a>, g: &kwg::Kwg, f: F) {
-    struct Env<'a, F: FnMut(&str)> {
-        a: &'a alphabet::Alphabet<'a>,
-        g: &'a kwg::Kwg,
-        s: &'a mut String,
-        f: F,
+fn print_word_counts_by_length(machine_words: &[bites::Bites]) {
+    let mut counts = Vec::new();
+    for word in machine_words {
+        if counts.len() <= word.len() {
+            counts.resize(word.len() + 1, 0usize);
+        }
+        counts[word.len()] += 1;
     }
-    fn iter<F: FnMut(&str)>(env: &mut Env<F>, mut p: i32) {
-        let l = env.s.len();
-        loop {
-            let t = env.g[p].tile();
-            env.s.push_str(env.a.from_rack(t).unwrap());
-            if env.g[p].accepts() {
-                (env.f)(env.s);
-            }
-            if env.g[p].arc_index() != 0 {
-                iter(env, env.g[p].arc_index());
-            }
-            env.s.truncate(l);
-            if env.g[p].is_end() {
-                break;
-            }
-            p += 1;
+    println!("{} words", machine_words.len());
+    for (len, &count) in counts.iter().enumerate() {
+        if count != 0 {
+            println!("{:>3} letters: {}", len, count);
         }
     }
-    iter(
-        &mut Env {
-            a: &a,
-            g: &g,
-            s: &mut String::new(),
-            f,
-        },
-        g[0].arc_index(),
-    );
 }
 
 fn build_leaves<Readable: std::io::Read>(
     f: Readable,
-    alph: alphabet::Alphabet,
+    alph: &alphabet::Alphabet,
 ) -> error::Returns<Vec<u8>> {
-    let mut leaves_map = fash::MyHashMap::default();
-    let mut leave_words = String::new();
-    let mut csv_reader = csv::ReaderBuilder::new().has_headers(false).from_reader(f);
-    for result in csv_reader.records() {
-        let record = result?;
-        let rounded_leave = (f32::from_str(&record[1])? * 256.0).round();
-        let int_leave = rounded_leave as i16;
-        assert!(
-            (int_leave as f32 - rounded_leave).abs() == 0.0,
-            "for {}: {} (f32) {} (*256) {} (round) {} (int) {} (float) {} (-) {} (abs) {}",
-            &record[0],
-            &record[1],
-            f32::from_str(&record[1])?,
-            f32::from_str(&record[1])? * 256.0,
-            rounded_leave,
-            int_leave,
-            int_leave as f32,
-            int_leave as f32 - rounded_leave,
-            (int_leave as f32 - rounded_leave).abs(),
-        );
-        leave_words.push_str(&record[0]);
-        leave_words.push('\n');
-        leaves_map.insert(String::from(&record[0]), int_leave);
-    }
-    let leaves_kwg = build::build(
-        build::BuildFormat::DawgOnly,
-        &read_machine_words(
-            &alphabet::AlphabetReader::new_for_racks(&alph),
-            &leave_words,
-        )?,
-    )?;
-    drop(leave_words);
-    let mut leave_values = Vec::with_capacity(leaves_map.len());
-    iter_dawg(&alph, &kwg::Kwg::from_bytes_alloc(&leaves_kwg), |s| {
-        leave_values.push(leaves_map[s])
-    });
-    drop(leaves_map);
-    let mut bin = vec![0; 2 * 4 + leaves_kwg.len() + leave_values.len() * 2];
-    let mut w = 0;
-    bin[w..w + 4].copy_from_slice(&((leaves_kwg.len() / 4) as u32).to_le_bytes());
-    w += 4;
-    bin[w..w + leaves_kwg.len()].copy_from_slice(&leaves_kwg);
-    w += leaves_kwg.len();
-    bin[w..w + 4].copy_from_slice(&(leave_values.len() as u32).to_le_bytes());
-    w += 4;
-    for v in leave_values {
-        bin[w..w + 2].copy_from_slice(&v.to_le_bytes());
-        w += 2;
-    }
-    assert_eq!(w, bin.len());
-    Ok(bin)
+    klv::build_klv(klv::read_leaves_csv(alph, f)?)
 }
 
-fn do_lang<'a, AlphabetMaker: Fn() -> alphabet::Alphabet<'a>>(
+fn do_lang(
     args: &[String],
     language_name: &str,
-    make_alphabet: AlphabetMaker,
+    alphabet: &alphabet::Alphabet,
 ) -> error::Returns<bool> {
     match args[1].strip_prefix(language_name) {
         Some(args1_suffix) => match args1_suffix {
             "-klv" => {
                 std::fs::write(
                     &args[3],
-                    build_leaves(std::fs::File::open(&args[2])?, make_alphabet())?,
+                    build_leaves(std::fs::File::open(&args[2])?, alphabet)?,
                 )?;
                 Ok(true)
             }
@@ -216,10 +130,7 @@ fn do_lang<'a, AlphabetMaker: Fn() -> alphabet::Alphabet<'a>>(
                     &args[3],
                     build::build(
                         build::BuildFormat::Gaddawg,
-                        &read_machine_words(
-                            &alphabet::AlphabetReader::new_for_words(&make_alphabet()),
-                            &std::fs::read_to_string(&args[2])?,
-                        )?,
+                        &read_word_list(alphabet, &std::fs::read_to_string(&args[2])?)?,
                     )?,
                 )?;
                 Ok(true)
@@ -229,10 +140,7 @@ fn do_lang<'a, AlphabetMaker: Fn() -> alphabet::Alphabet<'a>>(
                     &args[3],
                     build::build(
                         build::BuildFormat::DawgOnly,
-                        &read_machine_words(
-                            &alphabet::AlphabetReader::new_for_words(&make_alphabet()),
-                            &std::fs::read_to_string(&args[2])?,
-                        )?,
+                        &read_word_list(alphabet, &std::fs::read_to_string(&args[2])?)?,
                     )?,
                 )?;
                 Ok(true)
@@ -242,24 +150,97 @@ fn do_lang<'a, AlphabetMaker: Fn() -> alphabet::Alphabet<'a>>(
                     &args[3],
                     build::build(
                         build::BuildFormat::DawgOnly,
-                        &build::make_alphagrams(&read_machine_words(
-                            &alphabet::AlphabetReader::new_for_words(&make_alphabet()),
+                        &build::make_alphagrams(&read_word_list(
+                            alphabet,
                             &std::fs::read_to_string(&args[2])?,
                         )?),
                     )?,
                 )?;
                 Ok(true)
             }
+            "-kwg-edit" | "-kwg-dawg-edit" | "-kwg-alpha-edit" => {
+                let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read(&args[2])?);
+                let added_machine_words =
+                    read_word_list(alphabet, &std::fs::read_to_string(&args[3])?)?;
+                let removed_machine_words =
+                    read_word_list(alphabet, &std::fs::read_to_string(&args[4])?)?;
+                let build_format = if args1_suffix == "-kwg-edit" {
+                    build::BuildFormat::Gaddawg
+                } else {
+                    build::BuildFormat::DawgOnly
+                };
+                // a kad cannot tell whether an alphagram has other words left,
+                // so -kwg-alpha-edit reads the words from a kwg or dwg instead.
+                let old_machine_words = build::read_machine_words_from_kwg(&kwg);
+                let mut new_machine_words = build::edit_machine_words(
+                    &old_machine_words,
+                    &added_machine_words,
+                    &removed_machine_words,
+                );
+                println!(
+                    "{} words before, {} words after",
+                    old_machine_words.len(),
+                    new_machine_words.len()
+                );
+                if args1_suffix == "-kwg-alpha-edit" {
+                    new_machine_words = build::make_alphagrams(&new_machine_words);
+                }
+                std::fs::write(&args[5], build::build(build_format, &new_machine_words)?)?;
+                Ok(true)
+            }
             "-macondo" => {
-                let alphabet = make_alphabet();
                 let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read(&args[2])?);
                 std::fs::write(
                     &args[4],
-                    lexport::to_macondo(&kwg, &alphabet, &args[3], lexport::MacondoFormat::Dawg),
+                    lexport::to_macondo(&kwg, alphabet, &args[3], lexport::MacondoFormat::Dawg),
                 )?;
                 std::fs::write(
                     &args[5],
-                    lexport::to_macondo(&kwg, &alphabet, &args[3], lexport::MacondoFormat::Gaddag),
+                    lexport::to_macondo(&kwg, alphabet, &args[3], lexport::MacondoFormat::Gaddag),
+                )?;
+                Ok(true)
+            }
+            "-quackle" => {
+                let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read(&args[2])?);
+                std::fs::write(
+                    &args[3],
+                    lexport::to_quackle(&kwg, alphabet, lexport::QuackleFormat::Dawg),
+                )?;
+                std::fs::write(
+                    &args[4],
+                    lexport::to_quackle(&kwg, alphabet, lexport::QuackleFormat::Gaddag),
+                )?;
+                Ok(true)
+            }
+            "-words" | "-words-lower" | "-words-bracketed" => {
+                let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read(&args[2])?);
+                std::fs::write(
+                    &args[3],
+                    lexport::to_word_list(
+                        &kwg,
+                        alphabet,
+                        match args1_suffix {
+                            "-words-lower" => lexport::WordListLabels::BlankLabels,
+                            "-words-bracketed" => lexport::WordListLabels::Bracketed,
+                            _ => lexport::WordListLabels::Labels,
+                        },
+                    ),
+                )?;
+                Ok(true)
+            }
+            "-json" => {
+                let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read(&args[2])?);
+                std::fs::write(&args[4], lexport::to_json_trie(&kwg, alphabet, &args[3])?)?;
+                Ok(true)
+            }
+            "-from-macondo" => {
+                std::fs::write(
+                    &args[3],
+                    lexport::from_macondo(
+                        alphabet,
+                        &std::fs::read(&args[2])?,
+                        build::BuildFormat::Gaddawg,
+                    )?,
                 )?;
                 Ok(true)
             }
@@ -269,445 +250,430 @@ fn do_lang<'a, AlphabetMaker: Fn() -> alphabet::Alphabet<'a>>(
     }
 }
 
-pub fn main() -> error::Returns<()> {
-    let args = std::env::args().collect::<Vec<_>>();
-    if args.len() <= 1 {
-        println!(
-            "args:
-  auto
-    just to test
-  english-klv leaves.csv leaves.klv
-    generate klv file
-  english-kwg CSW19.txt CSW19.kwg
-    generate kwg file containing gaddawg
-  english-macondo CSW19.kwg CSW19 CSW19.dawg CSW19.gaddag
-    read kwg file, with lexicon name save macondo dawg/gaddag
-  english-kwg-alpha CSW19.txt CSW19.kad
-    generate kad file containing alpha dawg
-  english-kwg-dawg CSW19.txt outfile.dwg
-    generate dawg-only file
-  (english can also be french, german, norwegian, polish, spanish)"
-        );
-        Ok(())
-    } else if args[1] == "auto" {
-        old_main()?;
-        Ok(())
-    } else {
-        let t0 = std::time::Instant::now();
-        if do_lang(&args, "english", alphabet::make_english_alphabet)?
-            || do_lang(&args, "french", alphabet::make_french_alphabet)?
-            || do_lang(&args, "german", alphabet::make_german_alphabet)?
-            || do_lang(&args, "norwegian", alphabet::make_norwegian_alphabet)?
-            || do_lang(&args, "polish", alphabet::make_polish_alphabet)?
-            || do_lang(&args, "spanish", alphabet::make_spanish_alphabet)?
-        {
-        } else {
-            return Err("invalid argument".into());
+enum OutputFormat {
+    Kwg,
+    Kad,
+    Dawg,
+    Klv,
+    Macondo,
+    Quackle,
+    Words,
+    Json,
+    Kwm,
+    Kwi,
+    Kwp,
+}
+
+struct Output {
+    format: OutputFormat,
+    path: String,
+}
+
+impl std::str::FromStr for Output {
+    type Err = error::BoxAnyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format_name, path) = match s.find(':') {
+            Some(colon) => (&s[..colon], &s[colon + 1..]),
+            None => wolges::return_error!(format!("output {:?} should be format:path", s)),
+        };
+        let format = match format_name {
+            "kwg" => OutputFormat::Kwg,
+            "kad" => OutputFormat::Kad,
+            "dawg" => OutputFormat::Dawg,
+            "klv" => OutputFormat::Klv,
+            "macondo" => OutputFormat::Macondo,
+            "quackle" => OutputFormat::Quackle,
+            "words" => OutputFormat::Words,
+            "json" => OutputFormat::Json,
+            "kwm" => OutputFormat::Kwm,
+            "kwi" => OutputFormat::Kwi,
+            "kwp" => OutputFormat::Kwp,
+            _ => wolges::return_error!(format!("invalid output format {:?}", format_name)),
+        };
+        Ok(Self {
+            format,
+            path: path.to_string(),
+        })
+    }
+}
+
+// The alphabet can be given as its name or as a file, or come from a game config.
+enum AlphabetSource<'a> {
+    Alphabet(alphabet::Alphabet<'a>),
+    GameConfig(game_config::GameConfig<'a>),
+}
+
+impl<'a> AlphabetSource<'a> {
+    fn alphabet(&self) -> &alphabet::Alphabet<'a> {
+        match self {
+            AlphabetSource::Alphabet(x) => x,
+            AlphabetSource::GameConfig(x) => x.alphabet(),
         }
-        println!("time taken: {:?}", t0.elapsed());
-        Ok(())
     }
 }
 
-fn old_main() -> error::Returns<()> {
-    std::fs::write(
-        "lexbin/leaves.klv",
-        build_leaves(
-            Box::new(std::fs::File::open("lexsrc/leaves.csv")?),
-            alphabet::make_english_alphabet(),
-        )?,
-    )?;
-    {
-        let t0 = std::time::Instant::now();
-        std::fs::write(
-            "lexbin/CSW19.kwg",
-            build::build(
-                build::BuildFormat::Gaddawg,
-                &read_english_machine_words(&std::fs::read_to_string("lexsrc/CSW19.txt")?)?,
-            )?,
-        )?;
-        println!("{:?} for reading+building+writing CSW19 kwg", t0.elapsed());
+fn make_alphabet_source(name_or_path: &str) -> error::Returns<AlphabetSource<'static>> {
+    if let Some(alphabet) = alphabet::make_alphabet_by_name(name_or_path) {
+        Ok(AlphabetSource::Alphabet(alphabet))
+    } else if let Some(game_config) = game_config::make_game_config_by_name(name_or_path) {
+        Ok(AlphabetSource::GameConfig(game_config))
+    } else {
+        Ok(AlphabetSource::Alphabet(alphabet::make_alphabet_from_text(
+            &std::fs::read_to_string(name_or_path)?,
+        )?))
     }
-    {
-        let t0 = std::time::Instant::now();
-        std::fs::write(
-            "lexbin/CSW19.kad",
-            build::build(
-                build::BuildFormat::DawgOnly,
-                &build::make_alphagrams(&read_english_machine_words(&std::fs::read_to_string(
-                    "lexsrc/CSW19.txt",
-                )?)?),
-            )?,
-        )?;
-        println!(
-            "{:?} for reading+building+writing CSW19 alpha dawg",
-            t0.elapsed()
-        );
+}
+
+// Reads the input once and writes every requested output.
+fn do_build(args: &[String]) -> error::Returns<()> {
+    let mut alphabet_source = None;
+    let mut input_paths = Vec::<&str>::new();
+    let mut lexicon_name = None;
+    let mut outputs = Vec::<Output>::new();
+    let mut tag_files = Vec::<(&str, &str)>::new();
+    let mut args_iter = args.iter();
+    while let Some(arg) = args_iter.next() {
+        let mut value = || match args_iter.next() {
+            Some(value) => Ok(value),
+            None => Err(error::new(format!("{} needs a value", arg))),
+        };
+        match arg.as_str() {
+            "-a" | "--alphabet" => alphabet_source = Some(make_alphabet_source(value()?)?),
+            "-i" | "--input" => input_paths.push(value()?),
+            "-n" | "--name" => lexicon_name = Some(value()?.as_str()),
+            "-o" | "--output" => outputs.push(value()?.parse()?),
+            "-t" | "--tag" => {
+                let tag_file = value()?;
+                match tag_file.find(':') {
+                    Some(colon) => tag_files.push((&tag_file[..colon], &tag_file[colon + 1..])),
+                    None => {
+                        wolges::return_error!(format!("tag {:?} should be name:path", tag_file))
+                    }
+                }
+            }
+            _ => wolges::return_error!(format!("invalid argument {:?}", arg)),
+        }
     }
-    std::fs::write(
-        "lexbin/ECWL.kwg",
-        build::build(
-            build::BuildFormat::Gaddawg,
-            &read_english_machine_words(&std::fs::read_to_string("lexsrc/ECWL.txt")?)?,
-        )?,
-    )?;
-    std::fs::write(
-        "lexbin/NWL18.kwg",
-        build::build(
-            build::BuildFormat::Gaddawg,
-            &read_english_machine_words(&std::fs::read_to_string("lexsrc/NWL18.txt")?)?,
-        )?,
-    )?;
-    std::fs::write(
-        "lexbin/NWL20.kwg",
-        build::build(
-            build::BuildFormat::Gaddawg,
-            &read_english_machine_words(&std::fs::read_to_string("lexsrc/NWL20.txt")?)?,
-        )?,
-    )?;
-    if true {
-        let t0 = std::time::Instant::now();
-        std::fs::write(
-            "lexbin/OSPS42-dawg.kwg",
-            build::build(
-                build::BuildFormat::DawgOnly,
-                &read_polish_machine_words(&std::fs::read_to_string("lexsrc/OSPS42.txt")?)?,
-            )?,
-        )?;
-        println!(
-            "{:?} for reading+building+writing polish dawgonly",
-            t0.elapsed()
-        );
+    let alphabet_source = match alphabet_source {
+        Some(x) => x,
+        None => wolges::return_error!("need --alphabet".into()),
+    };
+    let alphabet = alphabet_source.alphabet();
+    if input_paths.is_empty() {
+        wolges::return_error!("need --input".into());
     }
-    if true {
-        let t0 = std::time::Instant::now();
-        std::fs::write(
-            "lexbin/OSPS42.kwg",
-            build::build(
-                build::BuildFormat::Gaddawg,
-                &read_polish_machine_words(&std::fs::read_to_string("lexsrc/OSPS42.txt")?)?,
-            )?,
-        )?;
-        println!(
-            "{:?} for reading+building+writing polish gaddawg",
-            t0.elapsed()
-        );
+    let input_names = input_paths
+        .iter()
+        .map(|input_path| {
+            std::path::Path::new(input_path)
+                .file_stem()
+                .and_then(|x| x.to_str())
+                .unwrap_or("")
+        })
+        .collect::<Vec<_>>();
+    if outputs.is_empty() {
+        wolges::return_error!("need at least one --output".into());
     }
-    std::fs::write(
-        "lexbin/TWL14.kwg",
-        build::build(
-            build::BuildFormat::Gaddawg,
-            &read_english_machine_words(&std::fs::read_to_string("lexsrc/TWL14.txt")?)?,
-        )?,
-    )?;
+    let lexicon_name = lexicon_name.unwrap_or(input_names[0]);
 
-    if true {
-        let english_alphabet = alphabet::make_english_alphabet();
-        let polish_alphabet = alphabet::make_polish_alphabet();
-        let t0 = std::time::Instant::now();
-        {
-            let t0 = std::time::Instant::now();
-            let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/CSW19.kwg")?);
-            println!("{:?} for rereading CSW19.kwg", t0.elapsed());
-            let t0 = std::time::Instant::now();
-            std::fs::write(
-                "lexbin/CSW19.dawg",
-                lexport::to_macondo(
-                    &kwg,
-                    &english_alphabet,
-                    "CSW19",
-                    lexport::MacondoFormat::Dawg,
-                ),
-            )?;
-            println!("{:?} for exporting CSW19 dawg", t0.elapsed());
-            let t0 = std::time::Instant::now();
-            std::fs::write(
-                "lexbin/CSW19.gaddag",
-                lexport::to_macondo(
-                    &kwg,
-                    &english_alphabet,
-                    "CSW19",
-                    lexport::MacondoFormat::Gaddag,
-                ),
-            )?;
-            println!("{:?} for exporting CSW19 gaddag", t0.elapsed());
-        }
-        {
-            let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/NWL18.kwg")?);
-            std::fs::write(
-                "lexbin/NWL18.dawg",
-                lexport::to_macondo(
-                    &kwg,
-                    &english_alphabet,
-                    "NWL18",
-                    lexport::MacondoFormat::Dawg,
-                ),
-            )?;
-            std::fs::write(
-                "lexbin/NWL18.gaddag",
-                lexport::to_macondo(
-                    &kwg,
-                    &english_alphabet,
-                    "NWL18",
-                    lexport::MacondoFormat::Gaddag,
-                ),
-            )?;
-        }
-        {
-            let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/NWL20.kwg")?);
-            std::fs::write(
-                "lexbin/NWL20.dawg",
-                lexport::to_macondo(
-                    &kwg,
-                    &english_alphabet,
-                    "NWL20",
-                    lexport::MacondoFormat::Dawg,
-                ),
-            )?;
-            std::fs::write(
-                "lexbin/NWL20.gaddag",
-                lexport::to_macondo(
-                    &kwg,
-                    &english_alphabet,
-                    "NWL20",
-                    lexport::MacondoFormat::Gaddag,
-                ),
-            )?;
-        }
-        {
-            let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/ECWL.kwg")?);
-            std::fs::write(
-                "lexbin/ECWL.dawg",
-                lexport::to_macondo(
-                    &kwg,
-                    &english_alphabet,
-                    "ECWL",
-                    lexport::MacondoFormat::Dawg,
-                ),
-            )?;
-            std::fs::write(
-                "lexbin/ECWL.gaddag",
-                lexport::to_macondo(
-                    &kwg,
-                    &english_alphabet,
-                    "ECWL",
-                    lexport::MacondoFormat::Gaddag,
-                ),
-            )?;
+    let num_klv_outputs = outputs
+        .iter()
+        .filter(|output| matches!(output.format, OutputFormat::Klv))
+        .count();
+    if num_klv_outputs > 0 {
+        // leaves come from csv, not a word list
+        if num_klv_outputs != outputs.len() || input_paths.len() != 1 {
+            wolges::return_error!(
+                "klv cannot be built together with other formats or inputs".into()
+            );
         }
-        println!("{:?} for exporting many files", t0.elapsed());
-        if true {
-            let t0 = std::time::Instant::now();
-            let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/OSPS42.kwg")?);
-            println!("{:?} for rereading OSPS42.kwg", t0.elapsed());
-            let t0 = std::time::Instant::now();
-            std::fs::write(
-                "lexbin/OSPS42.dawg",
-                lexport::to_macondo(
-                    &kwg,
-                    &polish_alphabet,
-                    "OSPS42",
-                    lexport::MacondoFormat::Dawg,
-                ),
-            )?;
-            println!("{:?} for exporting OSPS42 dawg", t0.elapsed());
-            let t0 = std::time::Instant::now();
-            std::fs::write(
-                "lexbin/OSPS42.gaddag",
-                lexport::to_macondo(
-                    &kwg,
-                    &polish_alphabet,
-                    "OSPS42",
-                    lexport::MacondoFormat::Gaddag,
-                ),
-            )?;
-            println!("{:?} for exporting OSPS42 gaddag", t0.elapsed());
+        let klv_bytes = build_leaves(std::fs::File::open(input_paths[0])?, alphabet)?;
+        for output in &outputs {
+            std::fs::write(&output.path, &klv_bytes)?;
         }
+        return Ok(());
     }
 
-    if true {
-        // this reads the files again, but this code is temporary
-        let v_csw19 = read_english_machine_words(&std::fs::read_to_string("lexsrc/CSW19.txt")?)?;
-        let v_ecwl = read_english_machine_words(&std::fs::read_to_string("lexsrc/ECWL.txt")?)?;
-        let v_nwl18 = read_english_machine_words(&std::fs::read_to_string("lexsrc/NWL18.txt")?)?;
-        let v_nwl20 = read_english_machine_words(&std::fs::read_to_string("lexsrc/NWL20.txt")?)?;
-        let v_twl14 = read_english_machine_words(&std::fs::read_to_string("lexsrc/TWL14.txt")?)?;
-        let mut v = Vec::<bites::Bites>::new();
-        v.extend_from_slice(&v_csw19);
-        v.extend_from_slice(&v_ecwl);
-        v.extend_from_slice(&v_nwl18);
-        v.extend_from_slice(&v_nwl20);
-        v.extend_from_slice(&v_twl14);
-        v.sort_unstable();
-        v.dedup();
-        let v = v.into_boxed_slice();
-        let v_bits_bytes = (v.len() + 7) / 8;
-        let mut v_csw19_bits = vec![0u8; v_bits_bytes];
-        let mut v_ecwl_bits = vec![0u8; v_bits_bytes];
-        let mut v_nwl18_bits = vec![0u8; v_bits_bytes];
-        let mut v_nwl20_bits = vec![0u8; v_bits_bytes];
-        let mut v_twl14_bits = vec![0u8; v_bits_bytes];
-        let mut p_csw19 = v_csw19.len();
-        let mut p_ecwl = v_ecwl.len();
-        let mut p_nwl18 = v_nwl18.len();
-        let mut p_nwl20 = v_nwl20.len();
-        let mut p_twl14 = v_twl14.len();
-        for i in (0..v.len()).rev() {
-            if p_csw19 > 0 && v[i] == v_csw19[p_csw19 - 1] {
-                v_csw19_bits[i / 8] |= 1 << (i % 8);
-                p_csw19 -= 1;
-            }
-            if p_ecwl > 0 && v[i] == v_ecwl[p_ecwl - 1] {
-                v_ecwl_bits[i / 8] |= 1 << (i % 8);
-                p_ecwl -= 1;
-            }
-            if p_nwl18 > 0 && v[i] == v_nwl18[p_nwl18 - 1] {
-                v_nwl18_bits[i / 8] |= 1 << (i % 8);
-                p_nwl18 -= 1;
+    // with several inputs, the lexicon is all of them.
+    let input_strings = input_paths
+        .iter()
+        .map(std::fs::read_to_string)
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut entries = Vec::new();
+    let mut input_machine_words = Vec::with_capacity(input_strings.len());
+    for input_string in &input_strings {
+        let input_entries = read_word_list_entries(alphabet, input_string)?;
+        input_machine_words.push(
+            input_entries
+                .iter()
+                .map(|(word, _)| word.clone())
+                .collect::<Box<_>>(),
+        );
+        entries.extend(input_entries);
+    }
+    if input_strings.len() > 1 {
+        sort_and_dedup_entries(&mut entries);
+    }
+    let machine_words = entries
+        .iter()
+        .map(|(word, _)| word.clone())
+        .collect::<Box<_>>();
+    print_word_counts_by_length(&machine_words);
+    let mut gaddawg_bytes = None;
+    let mut dawg_bytes = None;
+    let mut kad_bytes = None;
+    for output in &outputs {
+        match output.format {
+            OutputFormat::Kwg | OutputFormat::Macondo | OutputFormat::Quackle => {
+                if gaddawg_bytes.is_none() {
+                    gaddawg_bytes =
+                        Some(build::build(build::BuildFormat::Gaddawg, &machine_words)?);
+                }
             }
-            if p_nwl20 > 0 && v[i] == v_nwl20[p_nwl20 - 1] {
-                v_nwl20_bits[i / 8] |= 1 << (i % 8);
-                p_nwl20 -= 1;
+            OutputFormat::Dawg | OutputFormat::Words | OutputFormat::Json => {
+                if gaddawg_bytes.is_none() && dawg_bytes.is_none() {
+                    dawg_bytes = Some(build::build(build::BuildFormat::DawgOnly, &machine_words)?);
+                }
             }
-            if p_twl14 > 0 && v[i] == v_twl14[p_twl14 - 1] {
-                v_twl14_bits[i / 8] |= 1 << (i % 8);
-                p_twl14 -= 1;
+            OutputFormat::Kad => {
+                if kad_bytes.is_none() {
+                    kad_bytes = Some(build::build(
+                        build::BuildFormat::DawgOnly,
+                        &build::make_alphagrams(&machine_words),
+                    )?);
+                }
             }
+            OutputFormat::Klv | OutputFormat::Kwm | OutputFormat::Kwi | OutputFormat::Kwp => {}
         }
-        std::fs::write(
-            "lexbin/allgdw.kwg",
-            build::build(build::BuildFormat::Gaddawg, &v)?,
-        )?;
-        std::fs::write("lexbin/all-CSW19.kwi", v_csw19_bits)?;
-        std::fs::write("lexbin/all-ECWL.kwi", v_ecwl_bits)?;
-        std::fs::write("lexbin/all-NWL18.kwi", v_nwl18_bits)?;
-        std::fs::write("lexbin/all-NWL20.kwi", v_nwl20_bits)?;
-        std::fs::write("lexbin/all-TWL14.kwi", v_twl14_bits)?;
-
-        let english_alphabet = alphabet::make_english_alphabet();
-        let mut word_prob = prob::WordProbability::new(&english_alphabet);
-        let mut max_len = 0;
-        let mut tmp_vec = Vec::new();
-        let for_sorting = v
-            .iter()
-            .map(|word| {
-                tmp_vec.clear();
-                tmp_vec.extend_from_slice(word);
-                tmp_vec.sort_unstable();
-                let alphagram: bites::Bites = tmp_vec[..].into();
-                max_len = std::cmp::max(max_len, v.len());
-                (alphagram, word_prob.count_ways(word))
-            })
-            .collect::<Box<_>>();
-        let mut iter_indexes = (0u32..v.len() as u32).collect::<Box<_>>();
-        // sort by probability descending, then by alphagram ascending,
-        // then by raw index (v is already sorted)
-        iter_indexes.sort_unstable_by(|&a_idx, &b_idx| {
-            for_sorting[b_idx as usize]
-                .1
-                .cmp(&for_sorting[a_idx as usize].1)
-                .then_with(|| {
-                    for_sorting[a_idx as usize]
-                        .0
-                        .cmp(&for_sorting[b_idx as usize].0)
-                        .then_with(|| a_idx.cmp(&b_idx))
+    }
+    // kwi[j] has bit i (byte i / 8, bit i % 8) if word i is in input j.
+    let mut kwi_bytes = None;
+    if outputs
+        .iter()
+        .any(|output| matches!(output.format, OutputFormat::Kwi))
+    {
+        let num_words = machine_words.len();
+        if (1..input_names.len()).any(|j| input_names[..j].contains(&input_names[j])) {
+            wolges::return_error!("kwi needs inputs with different file names".into());
+        }
+        kwi_bytes = Some(
+            input_machine_words
+                .iter()
+                .map(|input_words| {
+                    let mut bits = vec![0u8; num_words / 8 + (num_words % 8 != 0) as usize];
+                    let mut i = 0;
+                    for word in input_words.iter() {
+                        while machine_words[i] < *word {
+                            i += 1;
+                        }
+                        bits[i / 8] |= 1 << (i % 8);
+                    }
+                    bits
                 })
-        });
-        // assign probability indexes by length
-        // 32-bit may be overkill, no length has more than 64k words yet
-        let mut assigned_indexes = vec![0u32; max_len + 1];
-        let mut output_probability_indexes = vec![0u32; v.len()];
-        for &idx in iter_indexes.iter() {
-            let len = v[idx as usize].len();
-            assigned_indexes[len] += 1;
-            output_probability_indexes[idx as usize] = assigned_indexes[len];
-            //println!(
-            //    "[{}] {:?} (len={} alpha={:?} wp={}) index={}",
-            //    idx,
-            //    v[idx as usize],
-            //    len,
-            //    for_sorting[idx as usize].0,
-            //    for_sorting[idx as usize].1,
-            //    output_probability_indexes[idx as usize]
-            //);
+                .collect::<Vec<_>>(),
+        );
+    }
+    drop(input_machine_words);
+    let kwp_bytes = if outputs
+        .iter()
+        .any(|output| matches!(output.format, OutputFormat::Kwp))
+    {
+        Some(
+            prob::probability_indexes_by_length(alphabet, &machine_words)
+                .iter()
+                .flat_map(|probability_index| probability_index.to_le_bytes())
+                .collect::<Vec<_>>(),
+        )
+    } else {
+        None
+    };
+    let mut kwm_bytes = None;
+    if outputs
+        .iter()
+        .any(|output| matches!(output.format, OutputFormat::Kwm))
+    {
+        if tag_files.len() > 64 {
+            wolges::return_error!(format!("kwm cannot have {} tags", tag_files.len()));
         }
-        let mut v_probability_indexes = vec![0u8; output_probability_indexes.len() * 4];
-        let mut w = 0;
-        for val in output_probability_indexes {
-            v_probability_indexes[w..w + 4].copy_from_slice(&val.to_le_bytes());
-            w += 4;
+        // tags[i] has bit j if word i is in tag file j.
+        let mut tags = vec![0u64; machine_words.len()];
+        for (tag_bit, &(tag_name, tag_path)) in tag_files.iter().enumerate() {
+            let tag_string = std::fs::read_to_string(tag_path)?;
+            let tag_entries = read_word_list_entries(alphabet, &tag_string)?;
+            let mut i = 0;
+            let mut num_not_found = 0;
+            for (word, _) in &tag_entries {
+                while i < machine_words.len() && machine_words[i] < *word {
+                    i += 1;
+                }
+                if i < machine_words.len() && machine_words[i] == *word {
+                    tags[i] |= 1 << tag_bit;
+                } else {
+                    num_not_found += 1;
+                }
+            }
+            println!(
+                "tag {}: {} words, {} not in lexicon",
+                tag_name,
+                tag_entries.len(),
+                num_not_found
+            );
         }
-        std::fs::write("lexbin/all-probidx.kwp", v_probability_indexes)?;
+        kwm_bytes = Some(kwm::build(
+            alphabet,
+            &machine_words,
+            &entries
+                .iter()
+                .map(|&(_, definition)| definition)
+                .collect::<Box<_>>(),
+            &tag_files
+                .iter()
+                .map(|&(tag_name, _)| tag_name)
+                .collect::<Box<_>>(),
+            &tags,
+        )?);
     }
-
-    if true {
-        // proof-of-concept
-        let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/allgdw.kwg")?);
-        let word_counts = kwg.count_dawg_words_alloc();
-        // because dawg do not need gaddag nodes
-        println!("only counting {} nodes", word_counts.len());
-        let v_csw19_bits = std::fs::read("lexbin/all-CSW19.kwi")?;
-        let v_ecwl_bits = std::fs::read("lexbin/all-ECWL.kwi")?;
-        let v_nwl18_bits = std::fs::read("lexbin/all-NWL18.kwi")?;
-        let v_nwl20_bits = std::fs::read("lexbin/all-NWL20.kwi")?;
-        let v_twl14_bits = std::fs::read("lexbin/all-TWL14.kwi")?;
-        let v_probability_indexes = std::fs::read("lexbin/all-probidx.kwp")?;
-        let mut out_vec = Vec::new();
-        let dawg_root = kwg[0].arc_index();
-        let english_alphabet = alphabet::make_english_alphabet();
-        let mut word_prob = prob::WordProbability::new(&english_alphabet);
-        for i in 0..word_counts[dawg_root as usize] {
-            out_vec.clear();
-            kwg.get_word_by_index(&word_counts, dawg_root, i, |v| {
-                out_vec.push(v);
-            });
-            let j = kwg.get_word_index(&word_counts, dawg_root, &out_vec);
-            print!("{} {} {:?}", i, j, out_vec);
-            let byte_index = j as usize / 8;
-            let bit = 1 << (j as usize % 8);
-            if v_csw19_bits[byte_index] & bit != 0 {
-                print!(" CSW19");
+    drop(entries);
+    drop(machine_words);
+    // words and json only need the dawg part, which is the same in both.
+    let kwg = gaddawg_bytes
+        .as_ref()
+        .or(dawg_bytes.as_ref())
+        .map(|bytes| kwg::Kwg::from_bytes_alloc(bytes));
+    for output in &outputs {
+        match output.format {
+            OutputFormat::Kwg => std::fs::write(&output.path, gaddawg_bytes.as_ref().unwrap())?,
+            OutputFormat::Dawg => match &dawg_bytes {
+                Some(bytes) => std::fs::write(&output.path, bytes)?,
+                None => std::fs::write(
+                    &output.path,
+                    build::build(
+                        build::BuildFormat::DawgOnly,
+                        &build::read_machine_words_from_kwg(kwg.as_ref().unwrap()),
+                    )?,
+                )?,
+            },
+            OutputFormat::Kad => std::fs::write(&output.path, kad_bytes.as_ref().unwrap())?,
+            OutputFormat::Macondo => {
+                let kwg = kwg.as_ref().unwrap();
+                std::fs::write(
+                    format!("{}.dawg", output.path),
+                    lexport::to_macondo(kwg, alphabet, lexicon_name, lexport::MacondoFormat::Dawg),
+                )?;
+                std::fs::write(
+                    format!("{}.gaddag", output.path),
+                    lexport::to_macondo(
+                        kwg,
+                        alphabet,
+                        lexicon_name,
+                        lexport::MacondoFormat::Gaddag,
+                    ),
+                )?;
             }
-            if v_ecwl_bits[byte_index] & bit != 0 {
-                print!(" ECWL");
+            OutputFormat::Quackle => {
+                let kwg = kwg.as_ref().unwrap();
+                std::fs::write(
+                    format!("{}.dawg", output.path),
+                    lexport::to_quackle(kwg, alphabet, lexport::QuackleFormat::Dawg),
+                )?;
+                std::fs::write(
+                    format!("{}.gaddag", output.path),
+                    lexport::to_quackle(kwg, alphabet, lexport::QuackleFormat::Gaddag),
+                )?;
             }
-            if v_nwl18_bits[byte_index] & bit != 0 {
-                print!(" NWL18");
+            OutputFormat::Words => std::fs::write(
+                &output.path,
+                lexport::to_word_list(
+                    kwg.as_ref().unwrap(),
+                    alphabet,
+                    lexport::WordListLabels::Labels,
+                ),
+            )?,
+            OutputFormat::Json => std::fs::write(
+                &output.path,
+                lexport::to_json_trie(kwg.as_ref().unwrap(), alphabet, lexicon_name)?,
+            )?,
+            OutputFormat::Kwm => std::fs::write(&output.path, kwm_bytes.as_ref().unwrap())?,
+            OutputFormat::Kwi => {
+                for (input_name, bits) in input_names.iter().zip(kwi_bytes.as_ref().unwrap()) {
+                    std::fs::write(format!("{}-{}.kwi", output.path, input_name), bits)?;
+                }
             }
-            if v_nwl20_bits[byte_index] & bit != 0 {
-                print!(" NWL20");
+            OutputFormat::Kwp => std::fs::write(&output.path, kwp_bytes.as_ref().unwrap())?,
+            OutputFormat::Klv => unreachable!(),
+        }
+    }
+    Ok(())
+}
+
+pub fn main() -> error::Returns<()> {
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.len() <= 1 {
+        println!(
+            "args:
+  build -a english -i CSW19.txt -o kwg:CSW19.kwg -o kad:CSW19.kad -o macondo:CSW19
+    read the word list once, generate every output
+    -a/--alphabet: alphabet or game config name, or alphabet file
+      (\"A a 9 1 1\" per line: label, blank label, freq, score, is vowel)
+    -i/--input: word list, or leaves csv for klv
+      (may be repeated, the lexicon is then all the words in any of them)
+    -n/--name: lexicon name for macondo/json, defaults to input file name
+    -t/--tag: name:path, tag the words listed in the file (for kwm)
+    -o/--output: format:path, format is one of
+      kwg, kad, dawg, klv, macondo, quackle, words, json,
+      kwm (definitions after each word in input, tags, probability order),
+      kwi (path-name.kwi per input name, bit per word if in that input),
+      kwp (u32 probability order within word length per word)
+      (macondo and quackle write path.dawg and path.gaddag)
+  english-klv leaves.csv leaves.klv
+    generate klv file
+  english-kwg CSW19.txt CSW19.kwg
+    generate kwg file containing gaddawg
+  english-macondo CSW19.kwg CSW19 CSW19.dawg CSW19.gaddag
+    read kwg file, with lexicon name save macondo dawg/gaddag
+  english-quackle CSW19.kwg CSW19.dawg CSW19.gaddag
+    read kwg file, save quackle-style dawg/gaddag
+  english-words CSW19.kwg CSW19.txt
+    read kwg file, save sorted word list
+    (also -words-lower, -words-bracketed for other tile labels)
+  english-json CSW19.kwg CSW19 CSW19.json
+    read kwg file, with lexicon name save compact json trie
+  english-from-macondo CSW19.gaddag CSW19.kwg
+    read macondo dawg or gaddag file, generate kwg file
+  english-kwg-alpha CSW19.txt CSW19.kad
+    generate kad file containing alpha dawg
+  english-kwg-dawg CSW19.txt outfile.dwg
+    generate dawg-only file
+  english-kwg-edit CSW19.kwg add.txt remove.txt outfile.kwg
+    generate kwg file from kwg file with words added/removed
+    (also -kwg-dawg-edit for .dwg, and -kwg-alpha-edit which reads the words
+    from a .kwg or .dwg and generates a .kad)
+  (english can be any alphabet name, such as catalan, danish, dutch, finnish,
+   french, german, italian, norwegian, polish, portuguese, slovene, spanish, swedish)"
+        );
+        Ok(())
+    } else {
+        let t0 = std::time::Instant::now();
+        if args[1] == "build" {
+            do_build(&args[2..])?;
+        } else {
+            // english-kwg-edit may be language "english" or "english-kwg".
+            let mut found = false;
+            for (i, _) in args[1].match_indices('-') {
+                let language_name = &args[1][..i];
+                if let Some(alphabet) = alphabet::make_alphabet_by_name(language_name) {
+                    if do_lang(&args, language_name, &alphabet)? {
+                        found = true;
+                        break;
+                    }
+                }
             }
-            if v_twl14_bits[byte_index] & bit != 0 {
-                print!(" TWL14");
+            if !found {
+                return Err("invalid argument".into());
             }
-            print!(" wp={}", word_prob.count_ways(&out_vec));
-            print!(
-                " pi={}",
-                u32::from_le_bytes(
-                    v_probability_indexes[i as usize * 4..i as usize * 4 + 4]
-                        .try_into()
-                        .unwrap()
-                )
-            );
-            println!();
-            assert_eq!(i, j);
         }
+        println!("time taken: {:?}", t0.elapsed());
+        Ok(())
     }
-
-    std::fs::write(
-        "lexbin/volost.kwg",
-        build::build(
-            build::BuildFormat::Gaddawg,
-            &read_english_machine_words("VOLOST\nVOLOSTS")?,
-        )?,
-    )?;
-    std::fs::write(
-        "lexbin/empty.kwg",
-        build::build(
-            build::BuildFormat::Gaddawg,
-            &read_english_machine_words("")?,
-        )?,
-    )?;
-
-    Ok(())
 }
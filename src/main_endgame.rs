@@ -9,8 +9,8 @@ use wolges::{endgame, error, game_config, kwg};
 // board: 2D array of numbers. 0 for empty, 1 for A, -1 for blank-as-A.
 // lexicon: this implies board size and other rules too.
 // count: maximum number of moves returned.
-// (note: equal moves are not stably sorted;
-//  different counts may tie-break the last move differently.)
+// (moves with equal equity are ordered by score, then by position and word,
+//  so a smaller count returns a prefix of a larger count.)
 #[derive(serde::Deserialize)]
 struct Question {
     lexicon: String,
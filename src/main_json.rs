@@ -2,7 +2,8 @@
 
 use rand::prelude::*;
 use wolges::{
-    display, error, game_config, game_state, kibitzer, klv, kwg, move_filter, move_picker, movegen,
+    alphabet, display, error, game_config, game_state, kibitzer, klv, kwg, kwm, move_filter,
+    move_picker, movegen,
 };
 
 // tile numbering follows alphabet order (not necessarily unicode order).
@@ -10,8 +11,8 @@ use wolges::{
 // board: 2D array of numbers. 0 for empty, 1 for A, -1 for blank-as-A.
 // lexicon: this implies board size and other rules too.
 // count: maximum number of moves returned.
-// (note: equal moves are not stably sorted;
-//  different counts may tie-break the last move differently.)
+// (moves with equal equity are ordered by score, then by position and word,
+//  so a smaller count returns a prefix of a larger count.)
 #[derive(serde::Deserialize)]
 struct Question {
     lexicon: String,
@@ -20,6 +21,9 @@ struct Question {
     board_tiles: Vec<Vec<i8>>,
     #[serde(rename = "count")]
     max_gen: usize,
+    // words to look up instead, this needs lexbin/<lexicon>.kwm.
+    #[serde(default)]
+    define: Vec<String>,
 }
 
 pub fn main() -> error::Returns<()> {
@@ -75,27 +79,27 @@ pub fn main() -> error::Returns<()> {
     match question.lexicon.as_str() {
         "CSW19" => {
             kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/CSW19.kwg")?);
-            klv = klv::Klv::from_bytes_alloc(&std::fs::read("lexbin/leaves.klv")?);
+            klv = klv::Klv::from_bytes_alloc(&std::fs::read("lexbin/leaves.klv")?)?;
             game_config = game_config::make_common_english_game_config();
         }
         "NWL18" => {
             kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/NWL18.kwg")?);
-            klv = klv::Klv::from_bytes_alloc(&std::fs::read("lexbin/leaves.klv")?);
+            klv = klv::Klv::from_bytes_alloc(&std::fs::read("lexbin/leaves.klv")?)?;
             game_config = game_config::make_common_english_game_config();
         }
         "NWL20" => {
             kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/NWL20.kwg")?);
-            klv = klv::Klv::from_bytes_alloc(&std::fs::read("lexbin/leaves.klv")?);
+            klv = klv::Klv::from_bytes_alloc(&std::fs::read("lexbin/leaves.klv")?)?;
             game_config = game_config::make_common_english_game_config();
         }
         "ECWL" => {
             kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/ECWL.kwg")?);
-            klv = klv::Klv::from_bytes_alloc(&std::fs::read("lexbin/leaves.klv")?);
+            klv = klv::Klv::from_bytes_alloc(&std::fs::read("lexbin/leaves.klv")?)?;
             game_config = game_config::make_common_english_game_config();
         }
         "OSPS42" => {
             kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/OSPS42.kwg")?);
-            klv = klv::Klv::from_bytes_alloc(klv::EMPTY_KLV_BYTES);
+            klv = klv::Klv::from_bytes_alloc(klv::EMPTY_KLV_BYTES)?;
             game_config = game_config::make_polish_game_config();
         }
         _ => {
@@ -103,6 +107,24 @@ pub fn main() -> error::Returns<()> {
         }
     };
 
+    if !question.define.is_empty() {
+        let kwm = kwm::Kwm::from_bytes_alloc(&std::fs::read(format!(
+            "lexbin/{}.kwm",
+            question.lexicon
+        ))?)?;
+        let word_counts = kwg.count_dawg_words_alloc();
+        let alphabet = game_config.alphabet();
+        let alphabet_reader = alphabet::AlphabetReader::new_for_plays(alphabet);
+        let mut result = Vec::with_capacity(question.define.len());
+        let mut v = Vec::new();
+        for word in &question.define {
+            kwm::parse_word(&alphabet_reader, word, &mut v)?;
+            result.push(kwm::word_info(&kwg, &word_counts, &kwm, alphabet, &v));
+        }
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+
     let mut rng = rand_chacha::ChaCha20Rng::from_entropy();
     let mut game_state = game_state::GameState::new(&game_config);
     // temp hardcode
@@ -156,6 +178,7 @@ pub fn main() -> error::Returns<()> {
             &board_snapshot,
             &game_state,
             &game_state.current_player().rack,
+            &mut rng,
         );
         let plays = &move_generator.plays;
         println!("found {} moves", plays.len());
@@ -169,6 +192,8 @@ pub fn main() -> error::Returns<()> {
         rack: &question.rack,
         max_gen: question.max_gen,
         always_include_pass: false,
+        num_tiles_in_bag: None,
+        constraints: None,
     });
     let plays = &move_generator.plays;
 
@@ -1,11 +1,11 @@
 // Copyright (C) 2020-2021 Andy Kurnia.
 
 use rand::prelude::*;
-use std::fmt::Write;
-use std::io::Write as _;
+use std::io::{Read as _, Seek as _, Write as _};
 use std::str::FromStr;
 use wolges::{
-    alphabet, bites, display, error, fash, game_config, game_state, klv, kwg, move_picker, movegen,
+    alphabet, autoplay_log, bites, error, fash, game_config, game_state, klv, kwg, leave_model,
+    move_picker, movegen, stats, tournament,
 };
 
 thread_local! {
@@ -38,22 +38,179 @@ fn do_lang<GameConfigMaker: Fn() -> game_config::GameConfig<'static>>(
     match args[1].strip_prefix(language_name) {
         Some(args1_suffix) => match args1_suffix {
             "-autoplay" => {
-                let args3 = if args.len() > 3 { &args[3] } else { "-" };
-                let args4 = if args.len() > 4 { &args[4] } else { "-" };
-                let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read(&args[2])?);
+                // leaves are positional, options follow them.
+                let num_positional_args = args
+                    .iter()
+                    .skip(2)
+                    .position(|arg| arg.len() > 1 && arg.starts_with('-'))
+                    .map_or(args.len(), |x| x + 2);
+                let args3 = if num_positional_args > 3 {
+                    &args[3]
+                } else {
+                    "-"
+                };
+                let args4 = if num_positional_args > 4 {
+                    &args[4]
+                } else {
+                    "-"
+                };
+                let mut options = AutoplayOptions::default();
+                let mut dir = std::path::PathBuf::from(".");
+                let mut prefix = "";
+                let mut resume = None;
+                let mut format = autoplay_log::Format::Csv;
+                let mut option_args = args[num_positional_args..].iter();
+                while let Some(option) = option_args.next() {
+                    let value = match option_args.next() {
+                        Some(value) => value,
+                        None => {
+                            wolges::return_error!(format!("missing value for {}", option));
+                        }
+                    };
+                    match option.as_str() {
+                        "-games" => options.num_games = value.parse()?,
+                        "-threads" => options.num_threads = value.parse()?,
+                        "-flush" => options.flush_every = value.parse()?,
+                        "-dir" => dir = value.into(),
+                        "-prefix" => prefix = value,
+                        "-resume" => resume = Some(value),
+                        "-format" => format = value.parse()?,
+                        "-pairing" => options.pairing = value.parse()?,
+                        "-seed" => options.seed = value.parse()?,
+                        _ => {
+                            wolges::return_error!(format!("invalid option {}", option));
+                        }
+                    }
+                }
+                if options.num_threads == 0 || options.flush_every == 0 {
+                    wolges::return_error!("threads and flush must be positive".to_string());
+                }
+                if options.pairing != Pairing::Unpaired {
+                    if options.num_games % 2 != 0 {
+                        wolges::return_error!("paired games must be even".to_string());
+                    }
+                    if resume.is_some() {
+                        wolges::return_error!("paired runs cannot be resumed".to_string());
+                    }
+                }
+                let kwg =
+                    std::sync::Arc::new(kwg::Kwg::from_bytes_alloc(&std::fs::read(&args[2])?));
                 let arc_klv0 = if args3 == "-" {
-                    std::sync::Arc::new(klv::Klv::from_bytes_alloc(klv::EMPTY_KLV_BYTES))
+                    std::sync::Arc::new(klv::Klv::from_bytes_alloc(klv::EMPTY_KLV_BYTES)?)
                 } else {
-                    std::sync::Arc::new(klv::Klv::from_bytes_alloc(&std::fs::read(&args3)?))
+                    std::sync::Arc::new(klv::Klv::from_bytes_alloc(&std::fs::read(&args3)?)?)
                 };
                 let arc_klv1 = if args3 == args4 {
                     std::sync::Arc::clone(&arc_klv0)
                 } else if args4 == "-" {
-                    std::sync::Arc::new(klv::Klv::from_bytes_alloc(klv::EMPTY_KLV_BYTES))
+                    std::sync::Arc::new(klv::Klv::from_bytes_alloc(klv::EMPTY_KLV_BYTES)?)
                 } else {
-                    std::sync::Arc::new(klv::Klv::from_bytes_alloc(&std::fs::read(&args4)?))
+                    std::sync::Arc::new(klv::Klv::from_bytes_alloc(&std::fs::read(&args4)?)?)
+                };
+                let logs = match resume {
+                    Some(log_path) => AutoplayLogs::resume(
+                        make_game_config().alphabet(),
+                        std::path::Path::new(log_path),
+                    )?,
+                    None => {
+                        std::fs::create_dir_all(&dir)?;
+                        AutoplayLogs::create_unique(&dir, prefix, format)?
+                    }
                 };
-                generate_autoplay_logs(make_game_config(), kwg, arc_klv0, arc_klv1)?;
+                let results = generate_autoplay_logs(
+                    make_game_config(),
+                    kwg,
+                    arc_klv0,
+                    arc_klv1,
+                    &options,
+                    logs,
+                )?;
+                results.print("p1");
+                Ok(true)
+            }
+            "-convert" => {
+                convert_log(
+                    make_game_config(),
+                    std::fs::File::open(&args[2])?,
+                    std::fs::File::create(&args[3])?,
+                    args[4].parse()?,
+                )?;
+                Ok(true)
+            }
+            "-generate-bucketed" => {
+                let bucket_max_bags = args[3]
+                    .split(',')
+                    .map(|x| x.parse::<i16>())
+                    .collect::<Result<Vec<_>, _>>()?;
+                std::fs::write(
+                    &args[4],
+                    generate_bucketed_klv(
+                        make_game_config,
+                        std::fs::File::open(&args[2])?,
+                        &bucket_max_bags,
+                    )?,
+                )?;
+                Ok(true)
+            }
+            "-generate-synergy" => {
+                let ridge = if args.len() > 4 {
+                    args[4].parse::<f64>()?
+                } else {
+                    1.0
+                };
+                std::fs::write(
+                    &args[3],
+                    generate_synergy_klv(
+                        make_game_config(),
+                        csv::ReaderBuilder::new()
+                            .has_headers(false)
+                            .from_path(&args[2])?,
+                        ridge,
+                    )?,
+                )?;
+                Ok(true)
+            }
+            "-klv-to-csv" => {
+                dump_klv(
+                    make_game_config(),
+                    &klv::Klv::from_bytes_alloc(&std::fs::read(&args[2])?)?,
+                    csv::Writer::from_path(&args[3])?,
+                )?;
+                Ok(true)
+            }
+            "-klv-lookup" => {
+                lookup_klv(
+                    make_game_config(),
+                    &klv::Klv::from_bytes_alloc(&std::fs::read(&args[2])?)?,
+                    &args[3..],
+                )?;
+                Ok(true)
+            }
+            "-klv-top" => {
+                let n = if args.len() > 3 {
+                    args[3].parse::<usize>()?
+                } else {
+                    10
+                };
+                print_klv_extremes(
+                    make_game_config(),
+                    &klv::Klv::from_bytes_alloc(&std::fs::read(&args[2])?)?,
+                    n,
+                );
+                Ok(true)
+            }
+            "-klv-diff" => {
+                let n = if args.len() > 4 {
+                    args[4].parse::<usize>()?
+                } else {
+                    20
+                };
+                diff_klv(
+                    make_game_config(),
+                    &klv::Klv::from_bytes_alloc(&std::fs::read(&args[2])?)?,
+                    &klv::Klv::from_bytes_alloc(&std::fs::read(&args[3])?)?,
+                    n,
+                );
                 Ok(true)
             }
             "-summarize" => {
@@ -84,28 +241,177 @@ fn do_lang<GameConfigMaker: Fn() -> game_config::GameConfig<'static>>(
                 )?;
                 Ok(true)
             }
+            "-train" => {
+                let kwg =
+                    std::sync::Arc::new(kwg::Kwg::from_bytes_alloc(&std::fs::read(&args[2])?));
+                let num_generations = args[4].parse::<u32>()?;
+                let num_games = args[5].parse::<u64>()?;
+                let klv_bytes = if args.len() > 6 && args[6] != "-" {
+                    std::fs::read(&args[6])?
+                } else {
+                    klv::EMPTY_KLV_BYTES.to_vec()
+                };
+                train_leaves(
+                    make_game_config,
+                    kwg,
+                    std::path::Path::new(&args[3]),
+                    num_generations,
+                    num_games,
+                    klv_bytes,
+                )?;
+                Ok(true)
+            }
             _ => Ok(false),
         },
         None => Ok(false),
     }
 }
 
+// each generation autoplays with the previous leaves, derives new leaves from
+// the log, and plays them against the previous leaves in paired games. stops
+// early once the new leaves are no longer significantly better per pair.
+fn train_leaves<GameConfigMaker: Fn() -> game_config::GameConfig<'static>>(
+    make_game_config: GameConfigMaker,
+    kwg: std::sync::Arc<kwg::Kwg>,
+    run_dir: &std::path::Path,
+    num_generations: u32,
+    num_games: u64,
+    klv_bytes: Vec<u8>,
+) -> error::Returns<()> {
+    std::fs::create_dir_all(run_dir)?;
+    let run_path = |name: String| run_dir.join(name).to_string_lossy().into_owned();
+    std::fs::write(run_path("gen0.klv".into()), &klv_bytes)?;
+    let mut best_generation = 0;
+    let mut arc_klv = std::sync::Arc::new(klv::Klv::from_bytes_alloc(&klv_bytes)?);
+    let options = AutoplayOptions {
+        num_games,
+        ..AutoplayOptions::default()
+    };
+    // paired bags cancel out much of the luck of the draw.
+    let eval_options = AutoplayOptions {
+        num_games: num_games + num_games % 2,
+        pairing: Pairing::Paired,
+        ..AutoplayOptions::default()
+    };
+    for generation in 1..=num_generations {
+        println!("generation {}", generation);
+        generate_autoplay_logs(
+            make_game_config(),
+            std::sync::Arc::clone(&kwg),
+            std::sync::Arc::clone(&arc_klv),
+            std::sync::Arc::clone(&arc_klv),
+            &options,
+            AutoplayLogs::create(
+                run_path(format!("gen{}-log.csv", generation)),
+                run_path(format!("gen{}-games.csv", generation)),
+                autoplay_log::Format::Csv,
+            )?,
+        )?;
+        generate_summary(
+            make_game_config(),
+            std::fs::File::open(run_path(format!("gen{}-log.csv", generation)))?,
+            csv::Writer::from_path(run_path(format!("gen{}-summary.csv", generation)))?,
+        )?;
+        generate_leaves::<_, _, true>(
+            make_game_config(),
+            csv::ReaderBuilder::new()
+                .has_headers(false)
+                .from_path(run_path(format!("gen{}-summary.csv", generation)))?,
+            csv::Writer::from_path(run_path(format!("gen{}-leaves.csv", generation)))?,
+        )?;
+        let klv_bytes = klv::build_klv(klv::read_leaves_csv(
+            make_game_config().alphabet(),
+            std::fs::File::open(run_path(format!("gen{}-leaves.csv", generation)))?,
+        )?)?;
+        std::fs::write(run_path(format!("gen{}.klv", generation)), &klv_bytes)?;
+        let new_arc_klv = std::sync::Arc::new(klv::Klv::from_bytes_alloc(&klv_bytes)?);
+        let results = generate_autoplay_logs(
+            make_game_config(),
+            std::sync::Arc::clone(&kwg),
+            std::sync::Arc::clone(&new_arc_klv),
+            arc_klv,
+            &eval_options,
+            AutoplayLogs::create(
+                run_path(format!("gen{}-eval-log.csv", generation)),
+                run_path(format!("gen{}-eval-games.csv", generation)),
+                autoplay_log::Format::Csv,
+            )?,
+        )?;
+        results.print(&format!("gen{} vs gen{}", generation, generation - 1));
+        if results.pair_spreads.ci_min(1.96) <= 0.0 {
+            println!(
+                "gen{} is not significantly better than gen{}, stopping",
+                generation,
+                generation - 1
+            );
+            break;
+        }
+        best_generation = generation;
+        arc_klv = new_arc_klv;
+    }
+    std::fs::copy(
+        run_path(format!("gen{}.klv", best_generation)),
+        run_path("best.klv".into()),
+    )?;
+    println!(
+        "best is gen{}, copied to {}",
+        best_generation,
+        run_path("best.klv".into())
+    );
+    Ok(())
+}
+
 pub fn main() -> error::Returns<()> {
     let args = std::env::args().collect::<Vec<_>>();
     if args.len() <= 1 {
         println!(
             "args:
-  english-autoplay NWL18.kwg leave0.klv leave1.klv
+  english-autoplay NWL18.kwg leave0.klv leave1.klv [options]
     autoplay many games, logs to a pair of csv.
-    (changing number of games or output filenames needs recompile.)
     if leave is \"-\" or omitted, uses no leave.
+    options:
+      -games 1000000 (total, including those already logged when resuming)
+      -threads N (defaults to number of cpus)
+      -dir . -prefix \"\" (logs go to dir/prefixlog-id, dir/games-prefixlog-id)
+      -flush 100 (each thread writes after this many games)
+      -format csv (or bin, a compact binary form of the same rows)
+      -resume dir/prefixlog-id (continues that log pair, ignores -dir -prefix -format)
+      -pairing none (or paired, each bag is played twice with p1 and p2 swapping
+        who goes first, or duplicate, which also draws the tiles in the same
+        order whatever is played, the mean spread of pairs is also reported)
+      -seed N (defaults to random, the same seed deals the same paired bags)
+  english-convert logfile outfile csv|bin
+    convert a log or games file to the other format
   english-summarize logfile summary.csv
-    summarize logfile into summary.csv
+    summarize logfile (either format) into summary.csv
   english-generate-no-smooth summary.csv leaves.csv
     generate leaves (no smoothing)
   english-generate summary.csv leaves.csv
     generate leaves (with smoothing)
-  (english can also be french, german, norwegian, polish, spanish)"
+  english-generate-bucketed logfile 7,20,50 leaves.klv
+    summarize and generate leaves (with smoothing) for all rows and for rows
+    in each bag size bucket (1-7, 8-20, 21-50 tiles remaining), then build a
+    klv that picks the table by bag size (above 50 uses the all rows table).
+  english-generate-synergy summary.csv leaves.klv [ridge]
+    fit tile values, pair synergies, duplicate penalties and vowel/consonant
+    terms to summary.csv (least squares, ridge defaults to 1), then build a
+    klv with every subrack valued by that fit. needs far fewer games.
+  english-klv-to-csv leaves.klv leaves.csv
+    dump leaves as rack,value (then one more value per bag size bucket, if any)
+  english-klv-lookup leaves.klv rack [rack...]
+    print the value of each leave (and in each bag size bucket, if any)
+  english-klv-top leaves.klv [10]
+    print the best and worst leaves of each length
+  english-klv-diff old.klv new.klv [20]
+    print how much leaves changed and the largest changes
+  english-train NWL18.kwg rundir generations games leave0.klv
+    repeatedly autoplay, summarize, generate and build leaves, starting from
+    leave0.klv (\"-\" or omitted for no leave), each generation playing the
+    given number of games. stops when a generation is not significantly better
+    than the previous one in paired games (rounded up to even). all files go to
+    rundir, the best leave as best.klv.
+  (english can also be catalan, danish, dutch, finnish, french, german, italian,
+   norwegian, polish, portuguese, slovene, spanish, swedish)"
         );
         Ok(())
     } else {
@@ -114,11 +420,23 @@ pub fn main() -> error::Returns<()> {
             &args,
             "english",
             game_config::make_common_english_game_config,
-        )? || do_lang(&args, "french", game_config::make_french_game_config)?
+        )? || do_lang(&args, "catalan", game_config::make_catalan_game_config)?
+            || do_lang(&args, "danish", game_config::make_danish_game_config)?
+            || do_lang(&args, "dutch", game_config::make_dutch_game_config)?
+            || do_lang(&args, "finnish", game_config::make_finnish_game_config)?
+            || do_lang(&args, "french", game_config::make_french_game_config)?
             || do_lang(&args, "german", game_config::make_german_game_config)?
+            || do_lang(&args, "italian", game_config::make_italian_game_config)?
             || do_lang(&args, "norwegian", game_config::make_norwegian_game_config)?
             || do_lang(&args, "polish", game_config::make_polish_game_config)?
+            || do_lang(
+                &args,
+                "portuguese",
+                game_config::make_portuguese_game_config,
+            )?
+            || do_lang(&args, "slovene", game_config::make_slovene_game_config)?
             || do_lang(&args, "spanish", game_config::make_spanish_game_config)?
+            || do_lang(&args, "swedish", game_config::make_swedish_game_config)?
         {
         } else {
             return Err("invalid argument".into());
@@ -128,82 +446,353 @@ pub fn main() -> error::Returns<()> {
     }
 }
 
+// Results of p1 (who uses leave0) against the best of the other players.
+#[derive(Default)]
+struct AutoplayResults {
+    spreads: stats::Stats,
+    wins: u64,
+    draws: u64,
+    // sum of both games of a pair.
+    pair_spreads: stats::Stats,
+}
+
+impl AutoplayResults {
+    fn record(&mut self, final_scores: &[i16]) {
+        let spread = final_scores[0] - final_scores[1..].iter().max().unwrap();
+        self.spreads.update(spread as f64);
+        self.wins += (spread > 0) as u64;
+        self.draws += (spread == 0) as u64;
+    }
+
+    fn record_pair(&mut self, pair_spread: i16) {
+        self.pair_spreads.update(pair_spread as f64);
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.spreads.update_bulk(&other.spreads);
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.pair_spreads.update_bulk(&other.pair_spreads);
+    }
+
+    fn num_games(&self) -> u64 {
+        self.spreads.count() as u64
+    }
+
+    fn print(&self, name: &str) {
+        let num_games = self.num_games();
+        println!(
+            "{} won {} drew {} lost {} of {} games ({:.2}%), mean spread {:.2} (95% ci {:.2} to {:.2})",
+            name,
+            self.wins,
+            self.draws,
+            num_games - self.wins - self.draws,
+            num_games,
+            (self.wins as f64 + self.draws as f64 * 0.5) * 100.0 / num_games.max(1) as f64,
+            self.spreads.mean(),
+            self.spreads.ci_min(1.96),
+            self.spreads.ci_max(1.96),
+        );
+        if self.pair_spreads.count() > 0.0 {
+            println!(
+                "{} mean spread per pair {:.2} (95% ci {:.2} to {:.2}) of {} pairs",
+                name,
+                self.pair_spreads.mean(),
+                self.pair_spreads.ci_min(1.96),
+                self.pair_spreads.ci_max(1.96),
+                self.pair_spreads.count(),
+            );
+        }
+    }
+}
+
+// how games are paired up to cancel out some of the luck of the draw.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pairing {
+    Unpaired,
+    // each bag is played twice, with p1 and p2 swapping who goes first (and
+    // so who gets the first rack).
+    Paired,
+    // also, exchanged tiles go to the bottom of the bag.
+    Duplicate,
+}
+
+impl FromStr for Pairing {
+    type Err = error::MyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::Unpaired),
+            "paired" => Ok(Self::Paired),
+            "duplicate" => Ok(Self::Duplicate),
+            _ => Err(error::new(format!("invalid pairing {:?}", s))),
+        }
+    }
+}
+
+struct AutoplayOptions {
+    num_games: u64,
+    num_threads: usize,
+    // each thread writes its games out after this many.
+    flush_every: u64,
+    pairing: Pairing,
+    // pair n plays bags from stream n of this seed.
+    seed: u64,
+}
+
+impl Default for AutoplayOptions {
+    fn default() -> Self {
+        Self {
+            num_games: 1_000_000,
+            num_threads: num_cpus::get(),
+            flush_every: 100,
+            pairing: Pairing::Unpaired,
+            seed: rand_chacha::ChaCha20Rng::from_entropy().next_u64(),
+        }
+    }
+}
+
+// a log and its games file, possibly already holding some games.
+struct AutoplayLogs {
+    run_identifier: String,
+    format: autoplay_log::Format,
+    log_file: std::fs::File,
+    games_file: std::fs::File,
+    num_logged_games: u64,
+    num_logged_moves: u64,
+}
+
+impl AutoplayLogs {
+    // overwrites existing files.
+    fn create(
+        log_path: String,
+        games_path: String,
+        format: autoplay_log::Format,
+    ) -> error::Returns<Self> {
+        Ok(Self {
+            log_file: std::fs::File::create(&log_path)?,
+            games_file: std::fs::File::create(&games_path)?,
+            run_identifier: log_path,
+            format,
+            num_logged_games: 0,
+            num_logged_moves: 0,
+        })
+    }
+
+    // the log file is created exclusively, so concurrent runs never share an
+    // id even if started in the same second.
+    fn create_unique(
+        dir: &std::path::Path,
+        prefix: &str,
+        format: autoplay_log::Format,
+    ) -> error::Returns<Self> {
+        let epoch_secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        for attempt in 0u32.. {
+            let file_name = if attempt == 0 {
+                format!("{}log-{:08x}", prefix, epoch_secs)
+            } else {
+                format!("{}log-{:08x}-{}", prefix, epoch_secs, attempt)
+            };
+            let log_path = dir.join(&file_name);
+            match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&log_path)
+            {
+                Ok(log_file) => {
+                    return Ok(Self {
+                        log_file,
+                        games_file: std::fs::File::create(
+                            dir.join(format!("games-{}", file_name)),
+                        )?,
+                        run_identifier: log_path.to_string_lossy().into_owned(),
+                        format,
+                        num_logged_games: 0,
+                        num_logged_moves: 0,
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        unreachable!();
+    }
+
+    // games are written whole, log rows before the games row, so after a
+    // crash only the tails can be incomplete. those are cut off here.
+    // the format is taken from the existing files.
+    fn resume(alphabet: &alphabet::Alphabet, log_path: &std::path::Path) -> error::Returns<Self> {
+        let file_name = match log_path.file_name() {
+            Some(file_name) => file_name.to_string_lossy(),
+            None => {
+                wolges::return_error!(format!("invalid log path {}", log_path.display()));
+            }
+        };
+        let games_path = log_path.with_file_name(format!("games-{}", file_name));
+        let mut game_ids = fash::MyHashSet::default();
+        let (games_len, num_logged_games, games_format) = complete_log_len(
+            alphabet,
+            &games_path,
+            autoplay_log::Kind::Games,
+            |game_id| {
+                game_ids.insert(game_id.to_string());
+                true
+            },
+        )?;
+        let (log_len, num_logged_moves, format) =
+            complete_log_len(alphabet, log_path, autoplay_log::Kind::Log, |game_id| {
+                game_ids.contains(game_id)
+            })?;
+        if format != games_format {
+            wolges::return_error!("log and games are in different formats".into());
+        }
+        let open_truncated = |path: &std::path::Path, len: u64| -> error::Returns<_> {
+            let file = std::fs::OpenOptions::new().append(true).open(path)?;
+            file.set_len(len)?;
+            Ok(file)
+        };
+        println!(
+            "resuming {} with {} games ({} moves)",
+            log_path.display(),
+            num_logged_games,
+            num_logged_moves
+        );
+        Ok(Self {
+            log_file: open_truncated(log_path, log_len)?,
+            games_file: open_truncated(&games_path, games_len)?,
+            run_identifier: log_path.to_string_lossy().into_owned(),
+            format,
+            num_logged_games,
+            num_logged_moves,
+        })
+    }
+}
+
+// returns the length up to the last row whose game id is accepted by keep,
+// the number of rows up to there, and the format. a torn last row is never
+// kept, in csv that is an unterminated last line. a row that fails to read is
+// only taken as torn if it runs into the end of the file.
+fn complete_log_len<P: AsRef<std::path::Path>, Keep: FnMut(&str) -> bool>(
+    alphabet: &alphabet::Alphabet,
+    path: P,
+    kind: autoplay_log::Kind,
+    mut keep: Keep,
+) -> error::Returns<(u64, u64, autoplay_log::Format)> {
+    let mut file = std::fs::File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let ends_with_newline = file_len > 0 && {
+        let mut last_byte = [0u8];
+        file.seek(std::io::SeekFrom::End(-1))?;
+        file.read_exact(&mut last_byte)?;
+        last_byte[0] == b'\n'
+    };
+    file.seek(std::io::SeekFrom::Start(0))?;
+    let mut reader = autoplay_log::Reader::new(alphabet, std::io::BufReader::new(file))?;
+    if reader.kind() != kind {
+        wolges::return_error!("unexpected kind of log".into());
+    }
+    let format = reader.format();
+    let is_complete =
+        |end: u64| format == autoplay_log::Format::Bin || end < file_len || ends_with_newline;
+    let mut log_row = autoplay_log::LogRow::default();
+    let mut game_row = autoplay_log::GameRow::default();
+    let mut kept_len = reader.position();
+    if !is_complete(kept_len) {
+        return Ok((0, 0, format));
+    }
+    let mut kept_rows = 0;
+    let mut num_rows = 0;
+    loop {
+        let read = match kind {
+            autoplay_log::Kind::Log => reader.read_log_row(&mut log_row),
+            autoplay_log::Kind::Games => reader.read_game_row(&mut game_row),
+        };
+        match read {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(_) if reader.position() == file_len => break,
+            Err(e) => return Err(e),
+        }
+        let game_id = match kind {
+            autoplay_log::Kind::Log => &log_row.game_id,
+            autoplay_log::Kind::Games => &game_row.game_id,
+        };
+        num_rows += 1;
+        let end = reader.position();
+        if is_complete(end) && keep(game_id) {
+            kept_len = end;
+            kept_rows = num_rows;
+        }
+    }
+    Ok((kept_len, kept_rows, format))
+}
+
 fn generate_autoplay_logs(
     game_config: game_config::GameConfig<'static>,
-    kwg: kwg::Kwg,
+    kwg: std::sync::Arc<kwg::Kwg>,
     arc_klv0: std::sync::Arc<klv::Klv>,
     arc_klv1: std::sync::Arc<klv::Klv>,
-) -> error::Returns<()> {
+    options: &AutoplayOptions,
+    logs: AutoplayLogs,
+) -> error::Returns<AutoplayResults> {
     let game_config = std::sync::Arc::new(game_config);
-    let kwg = std::sync::Arc::new(kwg);
-    let player_aliases = std::sync::Arc::new(
-        (1..=game_config.num_players())
-            .map(|x| format!("p{}", x))
-            .collect::<Box<_>>(),
-    );
-    let num_threads = num_cpus::get();
-    let num_games = 1_000_000;
-    let num_processed_games = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let num_games = options.num_games;
+    let flush_every = options.flush_every;
+    let pairing = options.pairing;
+    let seed = options.seed;
+    let games_per_claim = if pairing == Pairing::Unpaired { 1 } else { 2 };
+    if pairing != Pairing::Unpaired {
+        if game_config.num_players() != 2 {
+            wolges::return_error!("pairing needs two players".into());
+        }
+        println!("pairing games with seed {}", seed);
+    }
+    let format = logs.format;
+    let num_processed_games =
+        std::sync::Arc::new(std::sync::atomic::AtomicU64::new(logs.num_logged_games));
     let mut threads = vec![];
 
-    let epoch_secs = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let run_identifier = std::sync::Arc::new(format!("log-{:08x}", epoch_secs));
+    let run_identifier = std::sync::Arc::new(logs.run_identifier);
     println!("logging to {}", run_identifier);
-    let mut csv_log = csv::Writer::from_path(run_identifier.to_string())?;
-    csv_log.serialize((
-        "playerID",
-        "gameID",
-        "turn",
-        "rack",
-        "play",
-        "score",
-        "totalscore",
-        "tilesplayed",
-        "leave",
-        "equity",
-        "tilesremaining",
-        "oppscore",
-    ))?;
-    let csv_log_writer = csv_log.into_inner()?;
-    let mut csv_game = csv::Writer::from_path(format!("games-{}", run_identifier))?;
-    csv_game.serialize((
-        "gameID",
-        player_aliases
-            .iter()
-            .map(|x| format!("{}_score", x))
-            .collect::<Box<_>>(),
-        player_aliases
-            .iter()
-            .map(|x| format!("{}_bingos", x))
-            .collect::<Box<_>>(),
-        "first",
-    ))?;
-    let csv_game_writer = csv_game.into_inner()?;
-    let completed_games = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
-    let logged_games = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
-    let completed_moves = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let write_log_header = logs.log_file.metadata()?.len() == 0;
+    let write_games_header = logs.games_file.metadata()?.len() == 0;
+    let mut log_writer = autoplay_log::Writer::new(format, logs.log_file);
+    if write_log_header {
+        log_writer.write_log_header()?;
+    }
+    let log_file = log_writer.into_inner()?;
+    let mut games_writer = autoplay_log::Writer::new(format, logs.games_file);
+    if write_games_header {
+        games_writer.write_games_header(game_config.num_players())?;
+    }
+    let games_file = games_writer.into_inner()?;
+    let completed_games =
+        std::sync::Arc::new(std::sync::atomic::AtomicU64::new(logs.num_logged_games));
+    let logged_games =
+        std::sync::Arc::new(std::sync::atomic::AtomicU64::new(logs.num_logged_games));
+    let completed_moves =
+        std::sync::Arc::new(std::sync::atomic::AtomicU64::new(logs.num_logged_moves));
     let t0 = std::time::Instant::now();
     let tick_periods = move_picker::Periods(0);
     struct MutexedStuffs {
-        csv_game_writer: std::fs::File,
-        csv_log_writer: std::fs::File,
+        games_file: std::fs::File,
+        log_file: std::fs::File,
         tick_periods: move_picker::Periods,
     }
     let mutexed_stuffs = std::sync::Arc::new(std::sync::Mutex::new(MutexedStuffs {
-        csv_game_writer,
-        csv_log_writer,
+        games_file,
+        log_file,
         tick_periods,
     }));
 
-    for _ in 0..num_threads {
+    for _ in 0..options.num_threads {
         let game_config = std::sync::Arc::clone(&game_config);
         let kwg = std::sync::Arc::clone(&kwg);
         let arc_klv0 = std::sync::Arc::clone(&arc_klv0);
         let arc_klv1 = std::sync::Arc::clone(&arc_klv1);
-        let player_aliases = std::sync::Arc::clone(&player_aliases);
         let num_processed_games = std::sync::Arc::clone(&num_processed_games);
         let run_identifier = std::sync::Arc::clone(&run_identifier);
         let completed_games = std::sync::Arc::clone(&completed_games);
@@ -213,36 +802,70 @@ fn generate_autoplay_logs(
         threads.push(std::thread::spawn(move || {
             RNG.with(|rng| {
                 let mut rng = &mut *rng.borrow_mut();
-                let mut game_id = String::with_capacity(GAME_ID_LEN);
                 let mut move_generator = movegen::KurniaMoveGenerator::new(&game_config);
                 let mut game_state = game_state::GameState::new(&game_config);
-                let mut cur_rack_ser = String::new();
-                let mut aft_rack = Vec::with_capacity(game_config.rack_size() as usize);
-                let mut aft_rack_ser = String::new();
-                let mut play_fmt = String::new();
-                let mut equity_fmt = String::new();
-                let mut final_scores = vec![0; game_config.num_players() as usize];
-                let mut num_bingos = vec![0; game_config.num_players() as usize];
+                let mut log_row = autoplay_log::LogRow::default();
+                let mut game_row = autoplay_log::GameRow {
+                    game_id: String::with_capacity(GAME_ID_LEN),
+                    scores: vec![0; game_config.num_players() as usize],
+                    bingos: vec![0; game_config.num_players() as usize],
+                    first: 0,
+                };
                 let mut num_moves;
                 let mut num_batched_games_here = 0;
-                let mut batched_csv_log = csv::Writer::from_writer(Vec::new());
-                let mut batched_csv_game = csv::Writer::from_writer(Vec::new());
+                let mut batched_log = autoplay_log::Writer::new(format, Vec::new());
+                let mut batched_games = autoplay_log::Writer::new(format, Vec::new());
+                let mut results = AutoplayResults::default();
+                let mut pair_rng = tournament::pair_bag_rng(seed, 0);
+                let mut pair_idx = 0;
+                // which game of the pair is next, and the spread so far.
+                let mut pair_game = 0;
+                let mut pair_spread = 0;
                 loop {
-                    if num_processed_games.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
-                        >= num_games
-                    {
-                        num_processed_games.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
-                        break;
+                    if pair_game == 0 {
+                        let claimed = num_processed_games
+                            .fetch_add(games_per_claim, std::sync::atomic::Ordering::Relaxed);
+                        if claimed >= num_games {
+                            num_processed_games
+                                .fetch_sub(games_per_claim, std::sync::atomic::Ordering::Relaxed);
+                            break;
+                        }
+                        if pairing != Pairing::Unpaired {
+                            pair_idx = claimed / 2;
+                            pair_rng = tournament::pair_bag_rng(seed, pair_idx);
+                        }
                     }
 
                     num_moves = 0;
-                    num_bingos.iter_mut().for_each(|m| *m = 0);
-                    game_id.clear();
-                    for _ in 0..GAME_ID_LEN {
-                        game_id.push(*BASE57.choose(&mut rng).unwrap() as char);
+                    game_row.bingos.iter_mut().for_each(|m| *m = 0);
+                    game_row.game_id.clear();
+                    // both games of a pair start from the same rng state.
+                    let mut game_rng = pair_rng.clone();
+                    let went_first;
+                    if pairing == Pairing::Unpaired {
+                        for _ in 0..GAME_ID_LEN {
+                            game_row
+                                .game_id
+                                .push(*BASE57.choose(&mut rng).unwrap() as char);
+                        }
+                        went_first = rng.gen_range(0..game_config.num_players());
+                        game_state.reset_and_draw_tiles(&game_config, &mut rng);
+                    } else {
+                        tournament::push_pair_game_id(
+                            &mut game_row.game_id,
+                            seed,
+                            pair_idx,
+                            pair_game,
+                        );
+                        went_first = pair_game;
+                        game_state.reset_and_draw_tiles(&game_config, &mut game_rng);
+                        // whoever goes first gets the first rack.
+                        if went_first != 0 {
+                            game_state.players.swap(0, 1);
+                        }
                     }
-                    let went_first = rng.gen_range(0..game_config.num_players());
-                    game_state.reset_and_draw_tiles(&game_config, &mut rng);
+                    log_row.game_id.clone_from(&game_row.game_id);
+                    game_row.first = went_first;
                     game_state.turn = went_first;
                     loop {
                         num_moves += 1;
@@ -267,24 +890,23 @@ fn generate_autoplay_logs(
                             rack: &cur_rack,
                             max_gen: 1,
                             always_include_pass: false,
+                            num_tiles_in_bag: Some(game_state.bag.0.len() as i16),
+                            constraints: None,
                         });
 
                         let plays = &mut move_generator.plays;
                         let play = &plays[0];
-                        cur_rack_ser.clear();
-                        for &tile in cur_rack.iter() {
-                            cur_rack_ser.push_str(game_config.alphabet().from_rack(tile).unwrap());
-                        }
+                        log_row.rack.clone_from(cur_rack);
 
+                        let aft_rack = &mut log_row.leave;
                         aft_rack.clone_from(cur_rack);
                         match &play.play {
                             movegen::Play::Exchange { tiles } => {
-                                game_state::use_tiles(&mut aft_rack, tiles.iter().copied())
-                                    .unwrap();
+                                game_state::use_tiles(aft_rack, tiles.iter().copied()).unwrap();
                             }
                             movegen::Play::Place { word, .. } => {
                                 game_state::use_tiles(
-                                    &mut aft_rack,
+                                    aft_rack,
                                     word.iter().filter_map(|&tile| {
                                         if tile != 0 {
                                             Some(tile & !((tile as i8) >> 7) as u8)
@@ -297,51 +919,6 @@ fn generate_autoplay_logs(
                             }
                         }
                         aft_rack.sort_unstable();
-                        aft_rack_ser.clear();
-                        for &tile in aft_rack.iter() {
-                            aft_rack_ser.push_str(game_config.alphabet().from_rack(tile).unwrap());
-                        }
-
-                        play_fmt.clear();
-                        match &play.play {
-                            movegen::Play::Exchange { tiles } => {
-                                if tiles.is_empty() {
-                                    write!(play_fmt, "(Pass)").unwrap();
-                                } else {
-                                    let alphabet = game_config.alphabet();
-                                    write!(play_fmt, "(exch ").unwrap();
-                                    for &tile in tiles.iter() {
-                                        write!(play_fmt, "{}", alphabet.from_rack(tile).unwrap())
-                                            .unwrap();
-                                    }
-                                    write!(play_fmt, ")").unwrap();
-                                }
-                            }
-                            movegen::Play::Place {
-                                down,
-                                lane,
-                                idx,
-                                word,
-                                ..
-                            } => {
-                                let alphabet = game_config.alphabet();
-                                if *down {
-                                    write!(play_fmt, "{}{} ", display::column(*lane), idx + 1)
-                                        .unwrap();
-                                } else {
-                                    write!(play_fmt, "{}{} ", lane + 1, display::column(*idx))
-                                        .unwrap();
-                                }
-                                for &tile in word.iter() {
-                                    if tile == 0 {
-                                        write!(play_fmt, ".").unwrap();
-                                    } else {
-                                        write!(play_fmt, "{}", alphabet.from_board(tile).unwrap())
-                                            .unwrap();
-                                    }
-                                }
-                            }
-                        }
 
                         let play_score = match &play.play {
                             movegen::Play::Exchange { .. } => 0,
@@ -359,73 +936,75 @@ fn generate_autoplay_logs(
                             movegen::Play::Exchange { .. } => {}
                             movegen::Play::Place { .. } => {
                                 if tiles_played >= game_config.rack_size() as usize {
-                                    num_bingos[game_state.turn as usize] += 1;
+                                    game_row.bingos[game_state.turn as usize] += 1;
                                 }
                             }
                         };
 
-                        let old_bag_len = game_state.bag.0.len();
-                        game_state.play(&game_config, &mut rng, &play.play).unwrap();
+                        log_row.player = game_state.turn;
+                        log_row.turn = num_moves;
+                        log_row.play.clone_from(&play.play);
+                        log_row.score = play_score;
+                        log_row.tiles_played = tiles_played as u8;
+                        log_row.equity = autoplay_log::round_equity(play.equity);
+                        log_row.tiles_remaining = game_state.bag.0.len() as u16;
+
+                        match pairing {
+                            Pairing::Unpaired => {
+                                game_state.play(&game_config, &mut rng, &play.play)
+                            }
+                            Pairing::Paired => {
+                                game_state.play(&game_config, &mut game_rng, &play.play)
+                            }
+                            Pairing::Duplicate => {
+                                game_state.play_keeping_draw_order(&game_config, &play.play)
+                            }
+                        }
+                        .unwrap();
 
                         let old_turn = game_state.turn;
                         game_state.next_turn();
                         let new_turn = game_state.turn;
                         game_state.turn = old_turn;
 
-                        equity_fmt.clear();
-                        write!(equity_fmt, "{:.3}", play.equity).unwrap();
-
-                        match game_state.check_game_ended(&game_config, &mut final_scores) {
+                        match game_state.check_game_ended(&game_config, &mut game_row.scores) {
                             game_state::CheckGameEnded::PlayedOut
                             | game_state::CheckGameEnded::ZeroScores => {
+                                results.record(&game_row.scores);
+                                if pairing != Pairing::Unpaired {
+                                    pair_spread += game_row.scores[0] - game_row.scores[1];
+                                    pair_game ^= 1;
+                                    if pair_game == 0 {
+                                        results.record_pair(pair_spread);
+                                        pair_spread = 0;
+                                    }
+                                }
                                 let completed_moves = completed_moves
                                     .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
                                 completed_games.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                                batched_csv_log
-                                    .serialize((
-                                        &player_aliases[old_turn as usize],
-                                        &game_id,
-                                        num_moves,
-                                        &cur_rack_ser,
-                                        &play_fmt,
-                                        play_score,
-                                        final_scores[old_turn as usize],
-                                        tiles_played,
-                                        &aft_rack_ser,
-                                        &equity_fmt,
-                                        old_bag_len,
-                                        final_scores[new_turn as usize],
-                                    ))
-                                    .unwrap();
-                                batched_csv_game
-                                    .serialize((
-                                        &game_id,
-                                        &final_scores,
-                                        &num_bingos,
-                                        &player_aliases[went_first as usize],
-                                    ))
+                                log_row.total_score = game_row.scores[old_turn as usize];
+                                log_row.opp_score = game_row.scores[new_turn as usize];
+                                batched_log
+                                    .write_log_row(game_config.alphabet(), &log_row)
                                     .unwrap();
+                                batched_games.write_game_row(&game_row).unwrap();
                                 num_batched_games_here += 1;
-                                if num_batched_games_here >= 100 {
+                                // both games of a pair are written together.
+                                if num_batched_games_here >= flush_every && pair_game == 0 {
                                     let logged_games = logged_games.fetch_add(
                                         num_batched_games_here,
                                         std::sync::atomic::Ordering::Relaxed,
                                     ) + num_batched_games_here;
                                     num_batched_games_here = 0;
-                                    let mut batched_csv_log_buf =
-                                        batched_csv_log.into_inner().unwrap();
-                                    let mut batched_csv_game_buf =
-                                        batched_csv_game.into_inner().unwrap();
+                                    let mut batched_log_buf = batched_log.into_inner().unwrap();
+                                    let mut batched_games_buf = batched_games.into_inner().unwrap();
                                     let elapsed_time_secs = t0.elapsed().as_secs() as u64;
                                     let tick_changed = {
                                         let mut mutex_guard = mutexed_stuffs.lock().unwrap();
+                                        mutex_guard.log_file.write_all(&batched_log_buf).unwrap();
                                         mutex_guard
-                                            .csv_log_writer
-                                            .write_all(&batched_csv_log_buf)
-                                            .unwrap();
-                                        mutex_guard
-                                            .csv_game_writer
-                                            .write_all(&batched_csv_game_buf)
+                                            .games_file
+                                            .write_all(&batched_games_buf)
                                             .unwrap();
                                         mutex_guard.tick_periods.update(elapsed_time_secs)
                                     };
@@ -438,56 +1017,46 @@ fn generate_autoplay_logs(
                                         run_identifier
                                     );
                                     }
-                                    batched_csv_log_buf.clear();
-                                    batched_csv_log = csv::Writer::from_writer(batched_csv_log_buf);
-                                    batched_csv_game_buf.clear();
-                                    batched_csv_game =
-                                        csv::Writer::from_writer(batched_csv_game_buf);
+                                    batched_log_buf.clear();
+                                    batched_log =
+                                        autoplay_log::Writer::new(format, batched_log_buf);
+                                    batched_games_buf.clear();
+                                    batched_games =
+                                        autoplay_log::Writer::new(format, batched_games_buf);
                                 }
                                 break;
                             }
                             game_state::CheckGameEnded::NotEnded => {}
                         }
 
-                        batched_csv_log
-                            .serialize((
-                                &player_aliases[old_turn as usize],
-                                &game_id,
-                                num_moves,
-                                &cur_rack_ser,
-                                &play_fmt,
-                                play_score,
-                                game_state.players[old_turn as usize].score,
-                                tiles_played,
-                                &aft_rack_ser,
-                                &equity_fmt,
-                                old_bag_len,
-                                game_state.players[new_turn as usize].score,
-                            ))
+                        log_row.total_score = game_state.players[old_turn as usize].score;
+                        log_row.opp_score = game_state.players[new_turn as usize].score;
+                        batched_log
+                            .write_log_row(game_config.alphabet(), &log_row)
                             .unwrap();
                         completed_moves.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
                         game_state.turn = new_turn;
                     }
                 }
 
-                let batched_csv_log_buf = batched_csv_log.into_inner().unwrap();
-                let batched_csv_game_buf = batched_csv_game.into_inner().unwrap();
+                let batched_log_buf = batched_log.into_inner().unwrap();
+                let batched_games_buf = batched_games.into_inner().unwrap();
                 let mut mutex_guard = mutexed_stuffs.lock().unwrap();
+                mutex_guard.log_file.write_all(&batched_log_buf).unwrap();
                 mutex_guard
-                    .csv_log_writer
-                    .write_all(&batched_csv_log_buf)
-                    .unwrap();
-                mutex_guard
-                    .csv_game_writer
-                    .write_all(&batched_csv_game_buf)
+                    .games_file
+                    .write_all(&batched_games_buf)
                     .unwrap();
+                results
             })
         }));
     }
 
+    let mut results = AutoplayResults::default();
     for thread in threads {
-        if let Err(e) = thread.join() {
-            println!("{:?}", e);
+        match thread.join() {
+            Ok(thread_results) => results.merge(&thread_results),
+            Err(e) => println!("{:?}", e),
         }
     }
 
@@ -499,7 +1068,7 @@ fn generate_autoplay_logs(
         run_identifier
     );
 
-    Ok(())
+    Ok(results)
 }
 
 // handles the equivalent of '?', A-Z
@@ -525,79 +1094,195 @@ fn parse_rack(
     Ok(())
 }
 
+fn convert_log<Readable: std::io::Read, W: std::io::Write + 'static>(
+    game_config: game_config::GameConfig,
+    f: Readable,
+    w: W,
+    format: autoplay_log::Format,
+) -> error::Returns<()> {
+    let alphabet = game_config.alphabet();
+    let mut reader = autoplay_log::Reader::new(alphabet, std::io::BufReader::new(f))?;
+    let mut writer = autoplay_log::Writer::new(format, std::io::BufWriter::new(w));
+    let mut row_count = 0u64;
+    match reader.kind() {
+        autoplay_log::Kind::Log => {
+            writer.write_log_header()?;
+            let mut row = autoplay_log::LogRow::default();
+            while reader.read_log_row(&mut row)? {
+                writer.write_log_row(alphabet, &row)?;
+                row_count += 1;
+            }
+        }
+        autoplay_log::Kind::Games => {
+            writer.write_games_header(reader.num_players())?;
+            let mut row = autoplay_log::GameRow::default();
+            while reader.read_game_row(&mut row)? {
+                writer.write_game_row(&row)?;
+                row_count += 1;
+            }
+        }
+    }
+    writer.into_inner()?.flush()?;
+    println!("converted {} rows", row_count);
+    Ok(())
+}
+
 struct Cumulate {
     equity: f64,
     count: u64,
 }
 
-fn generate_summary<Readable: std::io::Read, W: std::io::Write>(
-    game_config: game_config::GameConfig,
+// generate_summary for several ranges of tiles remaining at once.
+fn generate_summaries<Readable: std::io::Read, W: std::io::Write>(
+    game_config: &game_config::GameConfig,
     f: Readable,
-    mut csv_out: csv::Writer<W>,
+    bag_ranges: &[std::ops::RangeInclusive<u16>],
+    csv_outs: &mut [csv::Writer<W>],
 ) -> error::Returns<()> {
-    let rack_reader = alphabet::AlphabetReader::new_for_racks(game_config.alphabet());
-    let mut csv_reader = csv::ReaderBuilder::new().has_headers(false).from_reader(f);
-    let mut rack_bytes = Vec::new();
-    let mut full_rack_map = fash::MyHashMap::<bites::Bites, Cumulate>::default();
-    // playerID,gameID,turn,rack,play,score,totalscore,tilesplayed,leave,equity,tilesremaining,oppscore
-    // 0       ,1     ,2   ,3   ,4   ,5    ,6         ,7          ,8    ,9     ,10            ,11
+    let mut log_reader =
+        autoplay_log::Reader::new(game_config.alphabet(), std::io::BufReader::new(f))?;
+    let mut log_row = autoplay_log::LogRow::default();
+    let mut full_rack_maps = bag_ranges
+        .iter()
+        .map(|_| fash::MyHashMap::<bites::Bites, Cumulate>::default())
+        .collect::<Box<_>>();
+    let mut row_counts = vec![0u64; bag_ranges.len()];
     let t0 = std::time::Instant::now();
     let mut tick_periods = move_picker::Periods(0);
-    let mut row_count = 0u64;
-    for (record_num, result) in csv_reader.records().enumerate() {
-        let record = result?;
-        if let Err(e) = (|| -> error::Returns<()> {
-            if i16::from_str(&record[10])? >= 1 {
-                let equity = f32::from_str(&record[9])? as f64;
-                //let score = i16::from_str(&record[5])? as i64;
-                parse_rack(&rack_reader, &record[3], &mut rack_bytes)?;
-                rack_bytes.sort_unstable();
-                row_count += 1;
-                if let Some(v) = full_rack_map.get_mut(&rack_bytes[..]) {
-                    *v = Cumulate {
-                        equity: v.equity + equity,
-                        count: v.count + 1,
-                    }
-                } else {
-                    full_rack_map.insert(rack_bytes[..].into(), Cumulate { equity, count: 1 });
+    for record_num in 1.. {
+        match log_reader.read_log_row(&mut log_row) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => {
+                // csv rows can be skipped, but binary rows cannot.
+                println!("parsing {}: {:?}", record_num, e);
+                if log_reader.format() == autoplay_log::Format::Csv {
+                    continue;
                 }
-                let elapsed_time_secs = t0.elapsed().as_secs() as u64;
-                if tick_periods.update(elapsed_time_secs) {
-                    println!(
-                        "After {} seconds, have read {} rows",
-                        elapsed_time_secs, row_count
-                    );
+                return Err(e);
+            }
+        }
+        let equity = log_row.equity as f64;
+        let rack_bytes = &mut log_row.rack;
+        rack_bytes.sort_unstable();
+        for (i, bag_range) in bag_ranges.iter().enumerate() {
+            if !bag_range.contains(&log_row.tiles_remaining) {
+                continue;
+            }
+            row_counts[i] += 1;
+            if let Some(v) = full_rack_maps[i].get_mut(&rack_bytes[..]) {
+                *v = Cumulate {
+                    equity: v.equity + equity,
+                    count: v.count + 1,
                 }
+            } else {
+                full_rack_maps[i].insert(rack_bytes[..].into(), Cumulate { equity, count: 1 });
             }
-            Ok(())
-        })() {
-            println!("parsing {}: {:?}: {:?}", record_num + 1, record, e);
+        }
+        let elapsed_time_secs = t0.elapsed().as_secs() as u64;
+        if tick_periods.update(elapsed_time_secs) {
+            println!(
+                "After {} seconds, have read {} rows",
+                elapsed_time_secs, record_num
+            );
         }
     }
-    drop(csv_reader);
-    let total_equity = full_rack_map.values().fold(0.0, |a, x| a + x.equity);
-    println!(
-        "{} records, {} unique racks",
-        row_count,
-        full_rack_map.len()
-    );
-
-    let mut kv = full_rack_map.into_iter().collect::<Vec<_>>();
-    kv.sort_unstable_by(|a, b| a.0.len().cmp(&b.0.len()).then_with(|| a.0.cmp(&b.0)));
+    drop(log_reader);
 
     let mut cur_rack_ser = String::new();
-    csv_out.serialize(("", total_equity, row_count))?;
-    for (k, fv) in kv.iter() {
-        cur_rack_ser.clear();
-        for &tile in k.iter() {
-            cur_rack_ser.push_str(game_config.alphabet().from_rack(tile).unwrap());
+    for (((full_rack_map, row_count), bag_range), csv_out) in full_rack_maps
+        .into_vec()
+        .into_iter()
+        .zip(row_counts)
+        .zip(bag_ranges)
+        .zip(csv_outs)
+    {
+        let total_equity = full_rack_map.values().fold(0.0, |a, x| a + x.equity);
+        println!(
+            "{} records, {} unique racks, with {} to {} tiles remaining",
+            row_count,
+            full_rack_map.len(),
+            bag_range.start(),
+            bag_range.end()
+        );
+
+        let mut kv = full_rack_map.into_iter().collect::<Vec<_>>();
+        kv.sort_unstable_by(|a, b| a.0.len().cmp(&b.0.len()).then_with(|| a.0.cmp(&b.0)));
+
+        csv_out.serialize(("", total_equity, row_count))?;
+        for (k, fv) in kv.iter() {
+            cur_rack_ser.clear();
+            for &tile in k.iter() {
+                cur_rack_ser.push_str(game_config.alphabet().from_rack(tile).unwrap());
+            }
+            csv_out.serialize((&cur_rack_ser, fv.equity, fv.count))?;
         }
-        csv_out.serialize((&cur_rack_ser, fv.equity, fv.count))?;
     }
 
     Ok(())
 }
 
+// summarizes rows with tiles remaining in the bag.
+fn generate_summary<Readable: std::io::Read, W: std::io::Write>(
+    game_config: game_config::GameConfig,
+    f: Readable,
+    csv_out: csv::Writer<W>,
+) -> error::Returns<()> {
+    generate_summaries(&game_config, f, &[1..=u16::MAX], &mut [csv_out])
+}
+
+// the base table uses all rows with tiles remaining in the bag, and each
+// bucket uses rows with more tiles remaining than the previous bucket's max.
+fn generate_bucketed_klv<
+    Readable: std::io::Read,
+    GameConfigMaker: Fn() -> game_config::GameConfig<'static>,
+>(
+    make_game_config: GameConfigMaker,
+    f: Readable,
+    bucket_max_bags: &[i16],
+) -> error::Returns<Vec<u8>> {
+    let mut bag_ranges = vec![1..=u16::MAX];
+    let mut min_bag = 1;
+    for &max_bag in bucket_max_bags {
+        if max_bag < min_bag {
+            wolges::return_error!(format!(
+                "bucket max bags must be positive and ascending, not {}",
+                max_bag
+            ));
+        }
+        bag_ranges.push(min_bag as u16..=max_bag as u16);
+        min_bag = max_bag + 1;
+    }
+    let mut summaries = bag_ranges
+        .iter()
+        .map(|_| csv::Writer::from_writer(Vec::new()))
+        .collect::<Vec<_>>();
+    generate_summaries(&make_game_config(), f, &bag_ranges, &mut summaries)?;
+    let mut tables = Vec::with_capacity(summaries.len());
+    for (bag_range, summary) in bag_ranges.iter().zip(summaries) {
+        println!(
+            "generating leaves with {} to {} tiles remaining",
+            bag_range.start(),
+            bag_range.end()
+        );
+        let mut leaves_csv = Vec::new();
+        generate_leaves::<_, _, true>(
+            make_game_config(),
+            csv::ReaderBuilder::new()
+                .has_headers(false)
+                .from_reader(&summary.into_inner()?[..]),
+            csv::Writer::from_writer(&mut leaves_csv),
+        )?;
+        tables.push(klv::read_leaves_csv(
+            make_game_config().alphabet(),
+            &leaves_csv[..],
+        )?);
+    }
+    let mut tables = tables.into_iter();
+    let base = tables.next().unwrap();
+    klv::build_bucketed_klv(base, bucket_max_bags.iter().copied().zip(tables).collect())
+}
+
 struct ExchangeEnv<'a, FoundExchangeMove: FnMut(&[u8])> {
     found_exchange_move: FoundExchangeMove,
     rack_tally: &'a mut [u8],
@@ -606,6 +1291,66 @@ struct ExchangeEnv<'a, FoundExchangeMove: FnMut(&[u8])> {
     exchange_buffer: &'a mut Vec<u8>,
 }
 
+// fits a leave_model::SynergyModel to the summarized subracks and evaluates it
+// on every possible subrack, so the result is a regular klv.
+fn generate_synergy_klv<Readable: std::io::Read>(
+    game_config: game_config::GameConfig,
+    csv_in: csv::Reader<Readable>,
+    ridge: f64,
+) -> error::Returns<Vec<u8>> {
+    let (subrack_map, mean_equity) = read_subrack_summary(&game_config, csv_in)?;
+    let t0 = std::time::Instant::now();
+    let model = leave_model::SynergyModel::fit(
+        game_config.alphabet(),
+        game_config.rack_size(),
+        subrack_map.iter().map(|(k, v)| {
+            (
+                &k[..],
+                v.equity / v.count as f64 - mean_equity,
+                v.count as f64,
+            )
+        }),
+        ridge,
+    )?;
+    let mut sum_sq_err = 0.0f64;
+    let mut sum_sq = 0.0f64;
+    let mut sum_weight = 0.0f64;
+    for (k, v) in subrack_map.iter() {
+        let weight = v.count as f64;
+        let y = v.equity / v.count as f64 - mean_equity;
+        let err = model.value(k) - y;
+        sum_sq_err += weight * err * err;
+        sum_sq += weight * y * y;
+        sum_weight += weight;
+    }
+    println!(
+        "After {} seconds, fitted with weighted rms error {:.4} (vs {:.4} for all zeros)",
+        t0.elapsed().as_secs(),
+        (sum_sq_err / sum_weight).sqrt(),
+        (sum_sq / sum_weight).sqrt(),
+    );
+
+    let mut leaves = Vec::new();
+    let mut alphabet_freqs = (0..game_config.alphabet().len())
+        .map(|tile| game_config.alphabet().freq(tile))
+        .collect::<Box<_>>();
+    let mut exchange_buffer = Vec::with_capacity(game_config.rack_size() as usize);
+    generate_exchanges(
+        &mut ExchangeEnv {
+            found_exchange_move: |rack_bytes: &[u8]| {
+                leaves.push((rack_bytes.into(), model.value(rack_bytes) as f32));
+            },
+            rack_tally: &mut alphabet_freqs,
+            min_len: 1,
+            max_len: game_config.rack_size() - 1,
+            exchange_buffer: &mut exchange_buffer,
+        },
+        0,
+    );
+    println!("{} subracks evaluated", leaves.len());
+    klv::build_klv(leaves)
+}
+
 fn generate_exchanges<FoundExchangeMove: FnMut(&[u8])>(
     env: &mut ExchangeEnv<FoundExchangeMove>,
     mut idx: u8,
@@ -667,11 +1412,12 @@ fn generate_neighbors<FoundNeighbor: FnMut(&[u8])>(
     }
 }
 
-fn generate_leaves<Readable: std::io::Read, W: std::io::Write, const DO_SMOOTHING: bool>(
-    game_config: game_config::GameConfig,
+// returns the cumulative equity of every subrack kept from the summarized
+// racks, and the mean equity of all rows.
+fn read_subrack_summary<Readable: std::io::Read>(
+    game_config: &game_config::GameConfig,
     mut csv_in: csv::Reader<Readable>,
-    mut csv_out: csv::Writer<W>,
-) -> error::Returns<()> {
+) -> error::Returns<(fash::MyHashMap<bites::Bites, Cumulate>, f64)> {
     let mut rack_tally = vec![0u8; game_config.alphabet().len() as usize];
     let mut exchange_buffer = Vec::with_capacity(game_config.rack_size() as usize);
     let mut rack_bytes = Vec::new();
@@ -741,6 +1487,19 @@ fn generate_leaves<Readable: std::io::Read, W: std::io::Write, const DO_SMOOTHIN
         }
     }
     println!("{} unique subracks", subrack_map.len());
+    Ok((subrack_map, total_equity / row_count as f64))
+}
+
+fn generate_leaves<Readable: std::io::Read, W: std::io::Write, const DO_SMOOTHING: bool>(
+    game_config: game_config::GameConfig,
+    csv_in: csv::Reader<Readable>,
+    mut csv_out: csv::Writer<W>,
+) -> error::Returns<()> {
+    let (subrack_map, mean_equity) = read_subrack_summary(&game_config, csv_in)?;
+    let mut rack_tally = vec![0u8; game_config.alphabet().len() as usize];
+    let mut exchange_buffer = Vec::with_capacity(game_config.rack_size() as usize);
+    let t0 = std::time::Instant::now();
+    let mut tick_periods = move_picker::Periods(0);
 
     let threshold_count = if DO_SMOOTHING {
         let total_count = subrack_map.values().fold(0, |a, x| a + x.count);
@@ -748,7 +1507,6 @@ fn generate_leaves<Readable: std::io::Read, W: std::io::Write, const DO_SMOOTHIN
     } else {
         0
     };
-    let mean_equity = total_equity / row_count as f64;
     let mut ev_map = fash::MyHashMap::<bites::Bites, _>::default();
     let mut alphabet_freqs = (0..game_config.alphabet().len())
         .map(|tile| game_config.alphabet().freq(tile))
@@ -917,3 +1675,160 @@ fn generate_leaves<Readable: std::io::Read, W: std::io::Write, const DO_SMOOTHIN
 
     Ok(())
 }
+
+// one row per leave, the base value then the value in each bucket.
+fn dump_klv<W: std::io::Write>(
+    game_config: game_config::GameConfig,
+    klv: &klv::Klv,
+    mut csv_out: csv::Writer<W>,
+) -> error::Returns<()> {
+    let num_tables = klv.bucket_max_bags.len() + 1;
+    let mut leave = Vec::new();
+    let mut row = Vec::with_capacity(num_tables + 1);
+    for leave_idx in 0..klv.num_leaves {
+        klv.get_leave(leave_idx as u32, &mut leave);
+        row.clear();
+        row.push(game_config.alphabet().fmt_rack(&leave).to_string());
+        for table_idx in 0..num_tables {
+            row.push(
+                klv.leave(table_idx * klv.num_leaves + leave_idx)
+                    .to_string(),
+            );
+        }
+        csv_out.write_record(&row)?;
+    }
+    Ok(())
+}
+
+fn lookup_klv(
+    game_config: game_config::GameConfig,
+    klv: &klv::Klv,
+    racks: &[String],
+) -> error::Returns<()> {
+    let rack_reader = alphabet::AlphabetReader::new_for_racks(game_config.alphabet());
+    let mut leave = Vec::new();
+    for rack in racks {
+        parse_rack(&rack_reader, rack, &mut leave)?;
+        leave.sort_unstable();
+        let leave_idx = klv.leave_index(&leave);
+        if leave_idx == !0 {
+            println!(
+                "{}: not found (valued 0)",
+                game_config.alphabet().fmt_rack(&leave)
+            );
+            continue;
+        }
+        print!(
+            "{}: {}",
+            game_config.alphabet().fmt_rack(&leave),
+            klv.leave(leave_idx as usize)
+        );
+        for (bucket, max_bag) in klv.bucket_max_bags.iter().enumerate() {
+            print!(
+                ", bag <= {}: {}",
+                max_bag,
+                klv.leave((bucket + 1) * klv.num_leaves + leave_idx as usize)
+            );
+        }
+        println!();
+    }
+    Ok(())
+}
+
+// top and bottom n of the base table, for each leave length.
+fn print_klv_extremes(game_config: game_config::GameConfig, klv: &klv::Klv, n: usize) {
+    let mut by_len = Vec::<Vec<(f32, u32)>>::new();
+    let mut leave = Vec::new();
+    for leave_idx in 0..klv.num_leaves as u32 {
+        klv.get_leave(leave_idx, &mut leave);
+        if by_len.len() <= leave.len() {
+            by_len.resize_with(leave.len() + 1, Vec::new);
+        }
+        by_len[leave.len()].push((klv.leave(leave_idx as usize), leave_idx));
+    }
+    for (len, values) in by_len.iter_mut().enumerate() {
+        if values.is_empty() {
+            continue;
+        }
+        values.sort_unstable_by(|a, b| b.0.partial_cmp(&a.0).unwrap().then(a.1.cmp(&b.1)));
+        let mut print_leave = |&(value, leave_idx): &(f32, u32)| {
+            klv.get_leave(leave_idx, &mut leave);
+            println!(
+                "  {:>9.3} {}",
+                value,
+                game_config.alphabet().fmt_rack(&leave)
+            );
+        };
+        println!("length {} ({} leaves), top:", len, values.len());
+        values.iter().take(n).for_each(&mut print_leave);
+        println!("length {} ({} leaves), bottom:", len, values.len());
+        values.iter().rev().take(n).for_each(&mut print_leave);
+    }
+}
+
+// compares base tables, showing the n largest changes.
+fn diff_klv(
+    game_config: game_config::GameConfig,
+    old_klv: &klv::Klv,
+    new_klv: &klv::Klv,
+    n: usize,
+) {
+    let mut changes = Vec::<(f32, f32, bites::Bites)>::new();
+    let mut num_only_old = 0u32;
+    let mut num_only_new = 0u32;
+    let mut leave = Vec::new();
+    for leave_idx in 0..new_klv.num_leaves {
+        new_klv.get_leave(leave_idx as u32, &mut leave);
+        let old_leave_idx = old_klv.leave_index(&leave);
+        let old_value = if old_leave_idx == !0 {
+            num_only_new += 1;
+            0.0
+        } else {
+            old_klv.leave(old_leave_idx as usize)
+        };
+        changes.push((old_value, new_klv.leave(leave_idx), leave[..].into()));
+    }
+    for leave_idx in 0..old_klv.num_leaves {
+        old_klv.get_leave(leave_idx as u32, &mut leave);
+        if new_klv.leave_index(&leave) == !0 {
+            num_only_old += 1;
+            changes.push((old_klv.leave(leave_idx), 0.0, leave[..].into()));
+        }
+    }
+    let mut sum_abs = 0.0f64;
+    let mut sum_sq = 0.0f64;
+    for &(old_value, new_value, _) in changes.iter() {
+        let change = (new_value - old_value) as f64;
+        sum_abs += change.abs();
+        sum_sq += change * change;
+    }
+    println!(
+        "{} leaves in both, {} only in old, {} only in new (missing leaves are valued 0)",
+        changes.len() as u32 - num_only_old - num_only_new,
+        num_only_old,
+        num_only_new,
+    );
+    if !changes.is_empty() {
+        println!(
+            "mean absolute change {:.4}, rms change {:.4}",
+            sum_abs / changes.len() as f64,
+            (sum_sq / changes.len() as f64).sqrt(),
+        );
+    }
+    changes.sort_unstable_by(|a, b| {
+        (b.1 - b.0)
+            .abs()
+            .partial_cmp(&(a.1 - a.0).abs())
+            .unwrap()
+            .then_with(|| a.2.cmp(&b.2))
+    });
+    for (old_value, new_value, leave) in changes.iter().take(n) {
+        println!(
+            "  {:>9.3} -> {:>9.3} ({:>+9.3}) {}",
+            old_value,
+            new_value,
+            new_value - old_value,
+            game_config.alphabet().fmt_rack(leave)
+        );
+    }
+}
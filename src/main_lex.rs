@@ -1,6 +1,6 @@
 // Copyright (C) 2020-2021 Andy Kurnia.
 
-use wolges::{alphabet, error, game_config, kwg};
+use wolges::{alphabet, error, game_config, kwg, kwm};
 
 fn print_dawg<'a>(a: &alphabet::Alphabet<'a>, g: &kwg::Kwg) {
     struct Env<'a> {
@@ -42,7 +42,71 @@ fn print_dawg<'a>(a: &alphabet::Alphabet<'a>, g: &kwg::Kwg) {
     );
 }
 
+// words are parsed case-insensitively.
+fn do_define(args: &[String]) -> error::Returns<()> {
+    if args.len() < 4 {
+        wolges::return_error!("need alphabet, kwg, kwm, words".into());
+    }
+    let alphabet = match alphabet::make_alphabet_by_name(&args[0]) {
+        Some(x) => x,
+        None => wolges::return_error!(format!("invalid alphabet {:?}", args[0])),
+    };
+    let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read(&args[1])?);
+    let kwm = kwm::Kwm::from_bytes_alloc(&std::fs::read(&args[2])?)?;
+    let word_counts = kwg.count_dawg_words_alloc();
+    let alphabet_reader = alphabet::AlphabetReader::new_for_plays(&alphabet);
+    let mut v = Vec::new();
+    for word in &args[3..] {
+        kwm::parse_word(&alphabet_reader, word, &mut v)?;
+        let info = kwm::word_info(&kwg, &word_counts, &kwm, &alphabet, &v);
+        if info.valid {
+            // hooks are shown as "-" when there are none.
+            print!(
+                "{} {} {} #{}",
+                if info.front_hooks.is_empty() {
+                    "-"
+                } else {
+                    &info.front_hooks
+                },
+                info.word,
+                if info.back_hooks.is_empty() {
+                    "-"
+                } else {
+                    &info.back_hooks
+                },
+                info.probability_index
+            );
+            if !info.definition.is_empty() {
+                print!(" {}", info.definition);
+            }
+            if !info.inflections.is_empty() {
+                print!(" [{}]", info.inflections);
+            }
+            for tag in info.tags {
+                print!(" ({})", tag);
+            }
+            println!();
+        } else {
+            println!("{}* is not valid", info.word);
+        }
+    }
+    Ok(())
+}
+
 pub fn main() -> error::Returns<()> {
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.len() > 1 {
+        if args[1] == "define" {
+            return do_define(&args[2..]);
+        }
+        println!(
+            "args:
+  define english CSW19.kwg CSW19.kwm word...
+    show hooks, probability order, definition and tags of each word
+  (no args runs the index self-test on lexbin/CSW19.kwg)"
+        );
+        return Ok(());
+    }
     if false {
         let kwg = kwg::Kwg::from_bytes_alloc(&std::fs::read("lexbin/OSPS42.kwg")?);
         print_dawg(&alphabet::make_polish_alphabet(), &kwg);
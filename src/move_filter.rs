@@ -175,9 +175,54 @@ impl<'a> Tilt<'a> {
     }
 }
 
+// Only forms words found in a smaller word list, such as common words.
+// The kwg must have the same alphabet, and be a kad for jumbled games.
+#[derive(Clone)]
+pub struct LimitedVocab<'a> {
+    kwg: &'a kwg::Kwg,
+    letters_tally: Vec<u8>,
+    limited_vocab_checker: LimitedVocabChecker,
+}
+
+impl<'a> LimitedVocab<'a> {
+    pub fn new(kwg: &'a kwg::Kwg) -> Self {
+        Self {
+            kwg,
+            letters_tally: Vec::new(),
+            limited_vocab_checker: LimitedVocabChecker::new(),
+        }
+    }
+
+    #[inline(always)]
+    fn word_is_ok(&mut self, game_config: &game_config::GameConfig, word: &[u8]) -> bool {
+        match game_config.game_rules() {
+            game_config::GameRules::Classic => {
+                let mut p = 0;
+                for &tile in word {
+                    p = self.kwg.seek(p, tile);
+                    if p <= 0 {
+                        return false;
+                    }
+                }
+                self.kwg[p].accepts()
+            }
+            game_config::GameRules::Jumbled => {
+                self.letters_tally.clear();
+                self.letters_tally
+                    .resize(game_config.alphabet().len() as usize, 0);
+                for &tile in word {
+                    self.letters_tally[tile as usize] += 1;
+                }
+                self.kwg.accepts_alpha(&self.letters_tally)
+            }
+        }
+    }
+}
+
 pub enum GenMoves<'a> {
     Unfiltered,
     Tilt { tilt: Tilt<'a>, bot_level: i8 },
+    LimitedVocab(LimitedVocab<'a>),
 }
 
 impl GenMoves<'_> {
@@ -188,6 +233,7 @@ impl GenMoves<'_> {
         board_snapshot: &movegen::BoardSnapshot<'_>,
         rack: &[u8],
         max_gen: usize,
+        num_tiles_in_bag: Option<i16>,
     ) {
         match self {
             Self::Unfiltered => {
@@ -196,6 +242,8 @@ impl GenMoves<'_> {
                     rack,
                     max_gen,
                     always_include_pass: false,
+                    num_tiles_in_bag,
+                    constraints: None,
                 });
             }
             Self::Tilt { tilt, bot_level: _ } => {
@@ -208,6 +256,8 @@ impl GenMoves<'_> {
                         rack,
                         max_gen,
                         always_include_pass: false,
+                        num_tiles_in_bag,
+                        constraints: None,
                     },
                     |down: bool,
                      lane: i8,
@@ -224,11 +274,48 @@ impl GenMoves<'_> {
                             |word: &[u8]| tilt.word_is_ok(word),
                         )
                     },
-                    |leave_value: f32| leave_scale * leave_value,
+                    &movegen::KlvEvaluator {
+                        leave_scale,
+                        ..Default::default()
+                    },
                     |_equity: f32, _play: &movegen::Play| true,
                 );
                 tilt.limited_vocab_checker = limited_vocab_checker;
             }
+            Self::LimitedVocab(limited_vocab) => {
+                let mut limited_vocab_checker =
+                    std::mem::take(&mut limited_vocab.limited_vocab_checker);
+                move_generator.gen_moves_filtered(
+                    &movegen::GenMovesParams {
+                        board_snapshot,
+                        rack,
+                        max_gen,
+                        always_include_pass: false,
+                        num_tiles_in_bag,
+                        constraints: None,
+                    },
+                    |down: bool,
+                     lane: i8,
+                     idx: i8,
+                     word: &[u8],
+                     _score: i16,
+                     _rack_tally: &[u8]| {
+                        limited_vocab_checker.words_placed_are_ok(
+                            board_snapshot,
+                            down,
+                            lane,
+                            idx,
+                            word,
+                            |word: &[u8]| {
+                                limited_vocab.word_is_ok(board_snapshot.game_config, word)
+                            },
+                        )
+                    },
+                    &movegen::KlvEvaluator::default(),
+                    |_equity: f32, _play: &movegen::Play| true,
+                );
+                limited_vocab.limited_vocab_checker = limited_vocab_checker;
+            }
         }
     }
 }
@@ -1,12 +1,35 @@
 // Copyright (C) 2020-2021 Andy Kurnia.
 
 use super::{game_config, game_state, klv, kwg, move_filter, movegen, simmer, stats};
+use rand::prelude::*;
 
 struct Candidate {
     play_index: usize,
     stats: stats::Stats,
 }
 
+#[derive(Clone, Copy)]
+pub struct SimmerSettings {
+    pub num_candidates: usize,
+    pub num_sim_plies: usize,
+    pub num_sim_iters: usize,
+    // candidates are pruned so that this is roughly when it ends.
+    pub max_time_for_move_ms: u64,
+    pub verbose: bool,
+}
+
+impl Default for SimmerSettings {
+    fn default() -> Self {
+        Self {
+            num_candidates: 100,
+            num_sim_plies: 2,
+            num_sim_iters: 1000,
+            max_time_for_move_ms: 8000,
+            verbose: true,
+        }
+    }
+}
+
 // Simmer can only be reused for the same game_config and kwg.
 // (Refer to note at simmer::Simmer.)
 // This is not enforced.
@@ -16,6 +39,7 @@ pub struct Simmer<'a> {
     klv: &'a klv::Klv,
     candidates: Vec<Candidate>,
     simmer: simmer::Simmer,
+    pub settings: SimmerSettings,
 }
 
 impl<'a> Simmer<'a> {
@@ -30,6 +54,7 @@ impl<'a> Simmer<'a> {
             klv,
             candidates: Vec::new(),
             simmer: simmer::Simmer::new(game_config),
+            settings: SimmerSettings::default(),
         }
     }
 
@@ -75,6 +100,8 @@ impl Periods {
 pub enum MovePicker<'a> {
     Hasty,
     Simmer(Simmer<'a>),
+    // a weaker bot, picks any of the top n moves with equal chance.
+    RandomTopN(usize),
 }
 
 unsafe impl Send for MovePicker<'_> {}
@@ -109,31 +136,63 @@ impl MovePicker<'_> {
         board_snapshot: &movegen::BoardSnapshot<'_>,
         game_state: &game_state::GameState,
         rack: &[u8],
+        rng: &mut dyn RngCore,
     ) {
         match self {
             MovePicker::Hasty => {
-                filtered_movegen.gen_moves(&mut move_generator, board_snapshot, &rack, 1);
+                filtered_movegen.gen_moves(
+                    &mut move_generator,
+                    board_snapshot,
+                    &rack,
+                    1,
+                    Some(game_state.bag.0.len() as i16),
+                );
+            }
+            MovePicker::RandomTopN(top_n) => {
+                filtered_movegen.gen_moves(
+                    move_generator,
+                    board_snapshot,
+                    rack,
+                    *top_n,
+                    Some(game_state.bag.0.len() as i16),
+                );
+                if move_generator.plays.len() > 1 {
+                    let picked_idx = rng.gen_range(0..move_generator.plays.len());
+                    move_generator.plays.swap(0, picked_idx);
+                    move_generator.plays.truncate(1);
+                }
             }
             MovePicker::Simmer(simmer) => {
                 let t0 = std::time::Instant::now();
-                tokio::spawn(async move {
-                    tokio::time::sleep(tokio::time::Duration::from_millis(3000)).await;
-                    println!("3 secs have passed");
-                });
-                filtered_movegen.gen_moves(&mut move_generator, board_snapshot, &rack, 100);
-                simmer.simmer.prepare(simmer.game_config, &game_state, 2);
+                let settings = simmer.settings;
+                if settings.verbose {
+                    tokio::spawn(async move {
+                        tokio::time::sleep(tokio::time::Duration::from_millis(3000)).await;
+                        println!("3 secs have passed");
+                    });
+                }
+                filtered_movegen.gen_moves(
+                    &mut move_generator,
+                    board_snapshot,
+                    &rack,
+                    settings.num_candidates,
+                    Some(game_state.bag.0.len() as i16),
+                );
+                simmer
+                    .simmer
+                    .prepare(simmer.game_config, &game_state, settings.num_sim_plies);
                 let mut candidates = simmer.take_candidates(move_generator.plays.len());
-                let num_sim_iters = 1000;
+                let num_sim_iters = settings.num_sim_iters;
                 let mut tick_periods = Periods(0);
                 let mut prune_periods = Periods(0);
-                let max_time_for_move_ms = 8000u64;
+                let max_time_for_move_ms = settings.max_time_for_move_ms;
                 let prune_interval_ms =
                     std::cmp::max(1, max_time_for_move_ms / candidates.len() as u64);
                 const Z: f64 = 1.96; // 95% confidence interval
                 for sim_iter in 1..=num_sim_iters {
                     tokio::task::yield_now().await;
                     let elapsed_time_ms = t0.elapsed().as_millis() as u64;
-                    if tick_periods.update(elapsed_time_ms / 1000) {
+                    if tick_periods.update(elapsed_time_ms / 1000) && settings.verbose {
                         println!(
                             "After {} seconds, doing iteration {} with {} candidates",
                             tick_periods.0,
@@ -182,15 +241,17 @@ impl MovePicker<'_> {
                     .max_by(|(_, a), (_, b)| a.stats.mean().partial_cmp(&b.stats.mean()).unwrap())
                     .unwrap()
                     .0;
-                println!(
-                    "top candidate mean = {} (sd={} count={} range {}..{}) took {:?}",
-                    candidates[top_idx].stats.mean(),
-                    candidates[top_idx].stats.standard_deviation(),
-                    candidates[top_idx].stats.count(),
-                    candidates[top_idx].stats.ci_max(-Z),
-                    candidates[top_idx].stats.ci_max(Z),
-                    t0.elapsed()
-                );
+                if settings.verbose {
+                    println!(
+                        "top candidate mean = {} (sd={} count={} range {}..{}) took {:?}",
+                        candidates[top_idx].stats.mean(),
+                        candidates[top_idx].stats.standard_deviation(),
+                        candidates[top_idx].stats.count(),
+                        candidates[top_idx].stats.ci_max(-Z),
+                        candidates[top_idx].stats.ci_max(Z),
+                        t0.elapsed()
+                    );
+                }
                 assert_eq!(
                     candidates[top_idx].play_index,
                     top_candidate_play_index_by_mean(&candidates)
@@ -213,6 +274,7 @@ impl MovePicker<'_> {
         board_snapshot: &movegen::BoardSnapshot<'_>,
         game_state: &game_state::GameState,
         rack: &[u8],
+        rng: &mut dyn RngCore,
     ) {
         self.pick_a_move_async(
             filtered_movegen,
@@ -220,6 +282,7 @@ impl MovePicker<'_> {
             board_snapshot,
             game_state,
             rack,
+            rng,
         )
         .await
     }
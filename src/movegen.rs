@@ -1,18 +1,18 @@
 // Copyright (C) 2020-2021 Andy Kurnia.
 
-use super::{alphabet, bites, display, fash, game_config, klv, kwg, matrix};
+use super::{alphabet, bites, bits, display, fash, game_config, klv, kwg, letter_sets, matrix};
 
-#[derive(Clone)]
-struct CrossSet {
-    bits: u64,
+#[derive(Clone, PartialEq)]
+struct CrossSet<B: bits::Bits> {
+    bits: B,
     score: i16,
 }
 
 #[derive(Clone, Copy)]
-struct CachedCrossSet {
+struct CachedCrossSet<B: bits::Bits> {
     p_left: i32,
     p_right: i32,
-    bits: u64,
+    bits: B,
 }
 
 #[derive(Clone)]
@@ -36,21 +36,21 @@ struct PossiblePlacement {
 // WorkingBuffer can only be reused for the same game_config and kwg.
 // (The kwg is partially cached in cached_cross_set.)
 // This is not enforced.
-struct WorkingBuffer {
-    rack_tally: Box<[u8]>,                                      // 27 for ?A-Z
-    word_buffer_for_across_plays: Box<[u8]>,                    // r*c
-    word_buffer_for_down_plays: Box<[u8]>,                      // c*r
-    cross_set_for_across_plays: Box<[CrossSet]>,                // r*c
-    cross_set_for_down_plays: Box<[CrossSet]>,                  // c*r
-    cached_cross_set_for_across_plays: Box<[CachedCrossSet]>,   // c*r
-    cached_cross_set_for_down_plays: Box<[CachedCrossSet]>,     // r*c
-    cross_set_buffer: Box<[CrossSetComputation]>,               // max(r, c)
-    remaining_word_multipliers_for_across_plays: Box<[i8]>,     // r*c (1 if tile placed)
-    remaining_word_multipliers_for_down_plays: Box<[i8]>,       // c*r
-    remaining_tile_multipliers_for_across_plays: Box<[i8]>,     // r*c (1 if tile placed)
-    remaining_tile_multipliers_for_down_plays: Box<[i8]>,       // c*r
-    face_value_scores_for_across_plays: Box<[i8]>,              // r*c
-    face_value_scores_for_down_plays: Box<[i8]>,                // c*r
+struct WorkingBuffer<B: bits::Bits> {
+    rack_tally: Box<[u8]>,                                       // 27 for ?A-Z
+    word_buffer_for_across_plays: Box<[u8]>,                     // r*c
+    word_buffer_for_down_plays: Box<[u8]>,                       // c*r
+    cross_set_for_across_plays: Box<[CrossSet<B>]>,              // r*c
+    cross_set_for_down_plays: Box<[CrossSet<B>]>,                // c*r
+    cached_cross_set_for_across_plays: Box<[CachedCrossSet<B>]>, // c*r
+    cached_cross_set_for_down_plays: Box<[CachedCrossSet<B>]>,   // r*c
+    cross_set_buffer: Box<[CrossSetComputation]>,                // max(r, c)
+    remaining_word_multipliers_for_across_plays: Box<[i8]>,      // r*c (1 if tile placed)
+    remaining_word_multipliers_for_down_plays: Box<[i8]>,        // c*r
+    remaining_tile_multipliers_for_across_plays: Box<[i8]>,      // r*c (1 if tile placed)
+    remaining_tile_multipliers_for_down_plays: Box<[i8]>,        // c*r
+    face_value_scores_for_across_plays: Box<[i8]>,               // r*c
+    face_value_scores_for_down_plays: Box<[i8]>,                 // c*r
     perpendicular_word_multipliers_for_across_plays: Box<[i8]>, // r*c (0 if no perpendicularly adjacent tile)
     perpendicular_word_multipliers_for_down_plays: Box<[i8]>,   // c*r
     perpendicular_scores_for_across_plays: Box<[i16]>, // r*c (multiplied by perpendicular_word_multipliers)
@@ -60,7 +60,7 @@ struct WorkingBuffer {
     num_tiles_in_bag: i16, // negative when players also have less than full racks
     play_out_bonus: i16,
     num_tiles_on_rack: u8,
-    rack_bits: u64, // bit 0 = blank conveniently matches bit 0 = have cross set
+    rack_bits: B, // bit 0 = blank conveniently matches bit 0 = have cross set
     descending_scores: Vec<i8>, // rack.len()
     exchange_buffer: Vec<u8>, // rack.len()
     square_multipliers_by_aggregated_word_multipliers_buffer: fash::MyHashMap<i16, usize>,
@@ -69,9 +69,16 @@ struct WorkingBuffer {
     best_leave_values: Vec<f32>, // rack.len() + 1
     found_placements: Vec<PossiblePlacement>,
     used_letters_tally: Vec<u8>, // 27 for ?A-Z, ? is always 0, jumbled mode only
+    unseen_tally: Box<[u8]>,     // 27 for ?A-Z, not on board and not on rack
+    incremental: bool,           // keep cross sets, the caller reports plays
+    verify_incremental: bool,    // compare incremental updates with a full recompute
+    cross_sets_valid: bool,      // for the board after the reported plays
+    dirty_cols: Box<[bool]>,     // c, cross sets for across plays need recomputing
+    dirty_rows: Box<[bool]>,     // r, cross sets for down plays need recomputing
+    letter_sets: Option<std::sync::Arc<letter_sets::LetterSets<B>>>, // classic only
 }
 
-impl Clone for WorkingBuffer {
+impl<B: bits::Bits> Clone for WorkingBuffer<B> {
     #[inline(always)]
     fn clone(&self) -> Self {
         Self {
@@ -125,6 +132,13 @@ impl Clone for WorkingBuffer {
             best_leave_values: self.best_leave_values.clone(),
             found_placements: self.found_placements.clone(),
             used_letters_tally: self.used_letters_tally.clone(),
+            unseen_tally: self.unseen_tally.clone(),
+            incremental: self.incremental,
+            verify_incremental: self.verify_incremental,
+            cross_sets_valid: self.cross_sets_valid,
+            dirty_cols: self.dirty_cols.clone(),
+            dirty_rows: self.dirty_rows.clone(),
+            letter_sets: self.letter_sets.clone(),
         }
     }
 
@@ -184,10 +198,18 @@ impl Clone for WorkingBuffer {
         self.found_placements.clone_from(&source.found_placements);
         self.used_letters_tally
             .clone_from(&source.used_letters_tally);
+        self.unseen_tally.clone_from(&source.unseen_tally);
+        self.incremental.clone_from(&source.incremental);
+        self.verify_incremental
+            .clone_from(&source.verify_incremental);
+        self.cross_sets_valid.clone_from(&source.cross_sets_valid);
+        self.dirty_cols.clone_from(&source.dirty_cols);
+        self.dirty_rows.clone_from(&source.dirty_rows);
+        self.letter_sets.clone_from(&source.letter_sets);
     }
 }
 
-impl WorkingBuffer {
+impl<B: bits::Bits> WorkingBuffer<B> {
     fn new(game_config: &game_config::GameConfig) -> Self {
         let dim = game_config.board_layout().dim();
         let rows_times_cols = (dim.rows as isize * dim.cols as isize) as usize;
@@ -195,15 +217,27 @@ impl WorkingBuffer {
             rack_tally: vec![0u8; game_config.alphabet().len() as usize].into_boxed_slice(),
             word_buffer_for_across_plays: vec![0u8; rows_times_cols].into_boxed_slice(),
             word_buffer_for_down_plays: vec![0u8; rows_times_cols].into_boxed_slice(),
-            cross_set_for_across_plays: vec![CrossSet { bits: 0, score: 0 }; rows_times_cols]
-                .into_boxed_slice(),
-            cross_set_for_down_plays: vec![CrossSet { bits: 0, score: 0 }; rows_times_cols]
-                .into_boxed_slice(),
+            cross_set_for_across_plays: vec![
+                CrossSet {
+                    bits: B::ZERO,
+                    score: 0,
+                };
+                rows_times_cols
+            ]
+            .into_boxed_slice(),
+            cross_set_for_down_plays: vec![
+                CrossSet {
+                    bits: B::ZERO,
+                    score: 0,
+                };
+                rows_times_cols
+            ]
+            .into_boxed_slice(),
             cached_cross_set_for_across_plays: vec![
                 CachedCrossSet {
                     p_left: 0,
                     p_right: 0,
-                    bits: 0
+                    bits: B::ZERO,
                 };
                 rows_times_cols
             ]
@@ -212,7 +246,7 @@ impl WorkingBuffer {
                 CachedCrossSet {
                     p_left: 0,
                     p_right: 0,
-                    bits: 0
+                    bits: B::ZERO,
                 };
                 rows_times_cols
             ]
@@ -248,7 +282,7 @@ impl WorkingBuffer {
             num_tiles_in_bag: 0,
             play_out_bonus: 0,
             num_tiles_on_rack: 0,
-            rack_bits: 0,
+            rack_bits: B::ZERO,
             descending_scores: Vec::new(),
             exchange_buffer: Vec::new(),
             square_multipliers_by_aggregated_word_multipliers_buffer: fash::MyHashMap::default(),
@@ -257,14 +291,22 @@ impl WorkingBuffer {
             best_leave_values: Vec::new(),
             found_placements: Vec::new(),
             used_letters_tally: Vec::new(),
+            unseen_tally: vec![0u8; game_config.alphabet().len() as usize].into_boxed_slice(),
+            incremental: false,
+            verify_incremental: false,
+            cross_sets_valid: false,
+            dirty_cols: vec![false; dim.cols as usize].into_boxed_slice(),
+            dirty_rows: vec![false; dim.rows as usize].into_boxed_slice(),
+            letter_sets: None,
         }
     }
 
-    fn init<AdjustLeaveValue: Fn(f32) -> f32>(
+    fn init<Evaluator: StaticEvaluator + ?Sized>(
         &mut self,
         board_snapshot: &BoardSnapshot<'_>,
         rack: &[u8],
-        adjust_leave_value: &AdjustLeaveValue,
+        num_tiles_in_bag: Option<i16>,
+        evaluator: &Evaluator,
     ) {
         self.exchange_buffer.clear();
         self.exchange_buffer.reserve(rack.len());
@@ -278,14 +320,7 @@ impl WorkingBuffer {
         self.word_buffer_for_down_plays
             .iter_mut()
             .for_each(|m| *m = 0);
-        self.cross_set_for_across_plays.iter_mut().for_each(|m| {
-            m.bits = 0;
-            m.score = 0;
-        });
-        self.cross_set_for_down_plays.iter_mut().for_each(|m| {
-            m.bits = 0;
-            m.score = 0;
-        });
+        // cross sets are fully overwritten by update_cross_sets.
 
         let alphabet = board_snapshot.game_config.alphabet();
         let board_layout = board_snapshot.game_config.board_layout();
@@ -338,32 +373,43 @@ impl WorkingBuffer {
             .iter()
             .filter(|&t| *t != 0)
             .count() as u16;
-        self.num_tiles_in_bag = alphabet.num_tiles() as i16
-            - (self.num_tiles_on_board as i16
-                + board_snapshot.game_config.num_players() as i16
-                    * board_snapshot.game_config.rack_size() as i16);
+        for (tile, (unseen, &num)) in
+            (0u8..).zip(self.unseen_tally.iter_mut().zip(self.rack_tally.iter()))
+        {
+            *unseen = alphabet.freq(tile).saturating_sub(num);
+        }
+        for &t in board_snapshot.board_tiles.iter() {
+            if t != 0 {
+                let tile = if t & 0x80 != 0 { 0 } else { t };
+                self.unseen_tally[tile as usize] =
+                    self.unseen_tally[tile as usize].saturating_sub(1);
+            }
+        }
+        self.num_tiles_in_bag = match num_tiles_in_bag {
+            Some(x) => x,
+            None => {
+                alphabet.num_tiles() as i16
+                    - (self.num_tiles_on_board as i16
+                        + board_snapshot.game_config.num_players() as i16
+                            * board_snapshot.game_config.rack_size() as i16)
+            }
+        };
+        // with an empty bag, the unseen tiles are exactly the opponent's rack.
         self.play_out_bonus = if self.num_tiles_in_bag <= 0 {
-            2 * ((0u8..)
-                .zip(self.rack_tally.iter())
-                .map(|(tile, &num)| {
-                    (alphabet.freq(tile) as i16 - num as i16) * alphabet.score(tile) as i16
-                })
+            2 * (0u8..)
+                .zip(self.unseen_tally.iter())
+                .map(|(tile, &num)| num as i16 * alphabet.score(tile) as i16)
                 .sum::<i16>()
-                - board_snapshot
-                    .board_tiles
-                    .iter()
-                    .map(|&t| if t != 0 { alphabet.score(t) as i16 } else { 0 })
-                    .sum::<i16>())
         } else {
             0
         };
 
         // eg if my rack is ZY??YVA it'd be [10,4,4,4,1,0,0].
         self.num_tiles_on_rack = 0;
-        self.rack_bits = 0u64;
+        self.rack_bits = B::ZERO;
         for (tile, &count) in (0u8..).zip(self.rack_tally.iter()) {
             self.num_tiles_on_rack += count;
-            self.rack_bits |= ((count != 0) as u64) << tile;
+            self.rack_bits |= B::from(count != 0) << tile;
         }
         self.descending_scores.clear();
         self.descending_scores
@@ -382,60 +428,57 @@ impl WorkingBuffer {
         self.best_leave_values.clear();
         self.best_leave_values
             .resize(self.num_tiles_on_rack as usize + 1, f32::NEG_INFINITY);
-        if self.num_tiles_in_bag <= 0 {
-            let mut unpaid = 0i16;
-            for i in (0..self.num_tiles_on_rack).rev() {
-                unpaid += self.descending_scores[i as usize] as i16;
-                self.best_leave_values[i as usize] = (-10 - 2 * unpaid) as f32;
+        struct Env<'a, Evaluator: ?Sized> {
+            eval_context: &'a StaticEvalContext<'a>,
+            evaluator: &'a Evaluator,
+            best_leave_values: &'a mut [f32],
+            rack_tally: &'a mut [u8],
+        }
+        #[inline(always)]
+        fn pretend_to_generate_exchanges<Evaluator: StaticEvaluator + ?Sized>(
+            mut env: &mut Env<'_, Evaluator>,
+            mut num_tiles_exchanged: u16,
+            mut idx: u8,
+        ) {
+            let rack_tally_len = env.rack_tally.len();
+            while (idx as usize) < rack_tally_len && env.rack_tally[idx as usize] == 0 {
+                idx += 1;
             }
-            self.best_leave_values[self.num_tiles_on_rack as usize] = self.play_out_bonus as f32;
-        } else {
-            struct Env<'a, AdjustLeaveValue> {
-                klv: &'a klv::Klv,
-                best_leave_values: &'a mut [f32],
-                rack_tally: &'a mut [u8],
-                adjust_leave_value: AdjustLeaveValue,
-            }
-            #[inline(always)]
-            fn pretend_to_generate_exchanges<AdjustLeaveValue: Fn(f32) -> f32>(
-                mut env: &mut Env<'_, AdjustLeaveValue>,
-                mut num_tiles_exchanged: u16,
-                mut idx: u8,
-            ) {
-                let rack_tally_len = env.rack_tally.len();
-                while (idx as usize) < rack_tally_len && env.rack_tally[idx as usize] == 0 {
-                    idx += 1;
+            if idx as usize >= rack_tally_len {
+                let this_leave_value = env.evaluator.leave_value(env.eval_context, env.rack_tally);
+                if this_leave_value > env.best_leave_values[num_tiles_exchanged as usize] {
+                    env.best_leave_values[num_tiles_exchanged as usize] = this_leave_value;
                 }
-                if idx as usize >= rack_tally_len {
-                    let this_leave_value =
-                        (env.adjust_leave_value)(env.klv.leave_value_from_tally(env.rack_tally));
-                    if this_leave_value > env.best_leave_values[num_tiles_exchanged as usize] {
-                        env.best_leave_values[num_tiles_exchanged as usize] = this_leave_value;
-                    }
-                    return;
-                }
-                let original_count = env.rack_tally[idx as usize];
-                loop {
-                    pretend_to_generate_exchanges(&mut env, num_tiles_exchanged, idx + 1);
-                    if env.rack_tally[idx as usize] == 0 {
-                        break;
-                    }
-                    env.rack_tally[idx as usize] -= 1;
-                    num_tiles_exchanged += 1;
+                return;
+            }
+            let original_count = env.rack_tally[idx as usize];
+            loop {
+                pretend_to_generate_exchanges(&mut env, num_tiles_exchanged, idx + 1);
+                if env.rack_tally[idx as usize] == 0 {
+                    break;
                 }
-                env.rack_tally[idx as usize] = original_count;
-            }
-            pretend_to_generate_exchanges(
-                &mut Env {
-                    klv: &board_snapshot.klv,
-                    best_leave_values: &mut self.best_leave_values,
-                    rack_tally: &mut self.rack_tally,
-                    adjust_leave_value,
+                env.rack_tally[idx as usize] -= 1;
+                num_tiles_exchanged += 1;
+            }
+            env.rack_tally[idx as usize] = original_count;
+        }
+        pretend_to_generate_exchanges(
+            &mut Env {
+                eval_context: &StaticEvalContext {
+                    board_snapshot,
+                    unseen_tally: &self.unseen_tally,
+                    num_tiles_on_board: self.num_tiles_on_board,
+                    num_tiles_on_rack: self.num_tiles_on_rack,
+                    num_tiles_in_bag: self.num_tiles_in_bag,
+                    play_out_bonus: self.play_out_bonus,
                 },
-                0,
-                0,
-            );
-        }
+                evaluator,
+                best_leave_values: &mut self.best_leave_values,
+                rack_tally: &mut self.rack_tally,
+            },
+            0,
+            0,
+        );
         for i in 0..=self.num_tiles_on_rack {
             self.best_leave_values[i as usize] +=
                 board_snapshot.game_config.num_played_bonus(i as i8) as f32;
@@ -449,16 +492,78 @@ impl WorkingBuffer {
         }
     }
 
-    fn init_after_cross_sets(&mut self, board_snapshot: &BoardSnapshot<'_>) {
+    // only_dirty recomputes just the lanes touched by reported plays.
+    fn gen_cross_sets(&mut self, board_snapshot: &BoardSnapshot<'_>, only_dirty: bool) {
         let board_layout = board_snapshot.game_config.board_layout();
         let dim = board_layout.dim();
+        // striped by row
+        for col in 0..dim.cols {
+            if only_dirty && !self.dirty_cols[col as usize] {
+                continue;
+            }
+            let strip_range_start = (col as isize * dim.rows as isize) as usize;
+            let strip_range_end = strip_range_start + dim.rows as usize;
+            gen_cross_set(
+                board_snapshot,
+                &self.transposed_board_tiles[strip_range_start..strip_range_end],
+                &board_layout.transposed_blocked()[strip_range_start..strip_range_end],
+                &mut self.cross_set_for_across_plays,
+                dim.down(col),
+                &mut self.cross_set_buffer,
+                &mut self.cached_cross_set_for_across_plays[strip_range_start..strip_range_end],
+                &mut self.used_letters_tally,
+                self.letter_sets.as_deref(),
+            );
+        }
+        let transposed_dim = matrix::Dim {
+            rows: dim.cols,
+            cols: dim.rows,
+        };
+        // striped by columns for better cache locality
+        for row in 0..dim.rows {
+            if only_dirty && !self.dirty_rows[row as usize] {
+                continue;
+            }
+            let strip_range_start = (row as isize * dim.cols as isize) as usize;
+            let strip_range_end = strip_range_start + dim.cols as usize;
+            gen_cross_set(
+                board_snapshot,
+                &board_snapshot.board_tiles[strip_range_start..strip_range_end],
+                &board_layout.blocked()[strip_range_start..strip_range_end],
+                &mut self.cross_set_for_down_plays,
+                transposed_dim.down(row),
+                &mut self.cross_set_buffer,
+                &mut self.cached_cross_set_for_down_plays[strip_range_start..strip_range_end],
+                &mut self.used_letters_tally,
+                self.letter_sets.as_deref(),
+            );
+        }
+    }
+
+    // returns whether only the dirty lanes were recomputed.
+    // the dirty lanes are kept for init_after_cross_sets.
+    fn update_cross_sets(&mut self, board_snapshot: &BoardSnapshot<'_>) -> bool {
+        let only_dirty = self.incremental && self.cross_sets_valid;
+        self.gen_cross_sets(board_snapshot, only_dirty);
+        self.cross_sets_valid = self.incremental;
+        only_dirty
+    }
+
+    fn gen_perpendicular_scores(&mut self, only_dirty: bool) {
+        let dim = matrix::Dim {
+            rows: self.dirty_rows.len() as i8,
+            cols: self.dirty_cols.len() as i8,
+        };
         for row in 0..dim.rows {
             let strip_range_start = (row as isize * dim.cols as isize) as usize;
             for col in 0..dim.cols {
+                if only_dirty && !self.dirty_cols[col as usize] {
+                    continue;
+                }
                 let idx = strip_range_start + col as usize;
                 let cross_set = &self.cross_set_for_across_plays[idx];
                 let effective_pwm = self.remaining_word_multipliers_for_across_plays[idx]
-                    & -(cross_set.bits as i8 & 1);
+                    & -((cross_set.bits & B::ONE != B::ZERO) as i8);
                 self.perpendicular_word_multipliers_for_across_plays[idx] = effective_pwm;
                 self.perpendicular_scores_for_across_plays[idx] =
                     cross_set.score * effective_pwm as i16;
@@ -467,16 +572,52 @@ impl WorkingBuffer {
         for col in 0..dim.cols {
             let strip_range_start = (col as isize * dim.rows as isize) as usize;
             for row in 0..dim.rows {
+                if only_dirty && !self.dirty_rows[row as usize] {
+                    continue;
+                }
                 let idx = strip_range_start + row as usize;
                 let cross_set = &self.cross_set_for_down_plays[idx];
                 let effective_pwm = self.remaining_word_multipliers_for_down_plays[idx]
-                    & -(cross_set.bits as i8 & 1);
+                    & -((cross_set.bits & B::ONE != B::ZERO) as i8);
                 self.perpendicular_word_multipliers_for_down_plays[idx] = effective_pwm;
                 self.perpendicular_scores_for_down_plays[idx] =
                     cross_set.score * effective_pwm as i16;
             }
         }
     }
+
+    // only_dirty should come from update_cross_sets, and be false if the cross
+    // sets have since been changed outside the dirty lanes.
+    fn init_after_cross_sets(&mut self, board_snapshot: &BoardSnapshot<'_>, only_dirty: bool) {
+        self.gen_perpendicular_scores(only_dirty);
+        if only_dirty && self.verify_incremental {
+            let incremental_for_across_plays = self.cross_set_for_across_plays.clone();
+            let incremental_for_down_plays = self.cross_set_for_down_plays.clone();
+            let incremental_pwm_for_across_plays =
+                self.perpendicular_word_multipliers_for_across_plays.clone();
+            let incremental_pwm_for_down_plays =
+                self.perpendicular_word_multipliers_for_down_plays.clone();
+            let incremental_ps_for_across_plays =
+                self.perpendicular_scores_for_across_plays.clone();
+            let incremental_ps_for_down_plays = self.perpendicular_scores_for_down_plays.clone();
+            self.gen_cross_sets(board_snapshot, false);
+            self.gen_perpendicular_scores(false);
+            assert!(
+                incremental_for_across_plays == self.cross_set_for_across_plays
+                    && incremental_for_down_plays == self.cross_set_for_down_plays
+                    && incremental_pwm_for_across_plays
+                        == self.perpendicular_word_multipliers_for_across_plays
+                    && incremental_pwm_for_down_plays
+                        == self.perpendicular_word_multipliers_for_down_plays
+                    && incremental_ps_for_across_plays
+                        == self.perpendicular_scores_for_across_plays
+                    && incremental_ps_for_down_plays == self.perpendicular_scores_for_down_plays,
+                "incremental cross sets differ from full recompute"
+            );
+        }
+        self.dirty_cols.iter_mut().for_each(|m| *m = false);
+        self.dirty_rows.iter_mut().for_each(|m| *m = false);
+    }
 }
 
 // kwg must be Gaddawg for Classic, AlphaDawg for Jumbled.
@@ -488,17 +629,22 @@ pub struct BoardSnapshot<'a> {
 }
 
 // cached_cross_sets is just one strip, so it is transposed from cross_sets
-fn gen_classic_cross_set<'a>(
+fn gen_classic_cross_set<'a, B: bits::Bits>(
     board_snapshot: &'a BoardSnapshot<'a>,
     board_strip: &'a [u8],
-    cross_sets: &'a mut [CrossSet],
+    cross_sets: &'a mut [CrossSet<B>],
     output_strider: matrix::Strider,
     cross_set_buffer: &'a mut [CrossSetComputation],
-    mut cached_cross_sets: &'a mut [CachedCrossSet],
+    mut cached_cross_sets: &'a mut [CachedCrossSet<B>],
+    letter_sets: Option<&letter_sets::LetterSets<B>>,
 ) {
     let len = output_strider.len();
     let step = output_strider.step() as usize;
     let kwg = &board_snapshot.kwg;
+    let seek = |p, tile| match letter_sets {
+        Some(letter_sets) => letter_sets.seek(kwg, p, tile),
+        None => kwg.seek(p, tile),
+    };
     let mut last_nonempty = len;
     {
         let alphabet = board_snapshot.game_config.alphabet();
@@ -509,7 +655,7 @@ fn gen_classic_cross_set<'a>(
             let b = board_strip[j as usize];
             if b != 0 {
                 let b_letter = b & 0x7f;
-                p = kwg.seek(p, b_letter);
+                p = seek(p, b_letter);
                 score += alphabet.score(b) as i16;
                 cross_set_buffer[j as usize] = CrossSetComputation {
                     score,
@@ -530,7 +676,7 @@ fn gen_classic_cross_set<'a>(
     }
 
     let reuse_cross_set =
-        |cached_cross_sets: &mut [CachedCrossSet], out_idx: i8, p_left, p_right| -> u64 {
+        |cached_cross_sets: &mut [CachedCrossSet<B>], out_idx: i8, p_left, p_right| -> B {
             if cached_cross_sets[out_idx as usize].p_left == p_left
                 && cached_cross_sets[out_idx as usize].p_right == p_right
             {
@@ -538,7 +684,7 @@ fn gen_classic_cross_set<'a>(
             } else {
                 cached_cross_sets[out_idx as usize].p_left = p_left;
                 cached_cross_sets[out_idx as usize].p_right = p_right;
-                0 // means unset, because bit 0 should always be set
+                B::ZERO // means unset, because bit 0 should always be set
             }
         };
     let mut wi = 0;
@@ -550,14 +696,16 @@ fn gen_classic_cross_set<'a>(
             // [j-1] has right, no left.
             let mut p = cross_set_buffer[j as usize].p;
             let mut bits = reuse_cross_set(cached_cross_sets, j - 1, -2, p);
-            if bits == 0 {
-                bits = 1u64;
+            if bits == B::ZERO {
+                bits = B::ONE;
                 if p > 0 {
                     p = kwg[p].arc_index();
-                    if p > 0 {
+                    if let Some(letter_sets) = letter_sets {
+                        bits |= letter_sets.accepts(p);
+                    } else if p > 0 {
                         loop {
                             let node = kwg[p];
-                            bits |= (node.accepts() as u64) << node.tile();
+                            bits |= B::from(node.accepts()) << node.tile();
                             if node.is_end() {
                                 break;
                             }
@@ -568,7 +716,10 @@ fn gen_classic_cross_set<'a>(
                 cached_cross_sets[j as usize - 1].bits = bits;
             }
             for _ in wi..j - 1 {
-                cross_sets[wp] = CrossSet { bits: 0, score: 0 };
+                cross_sets[wp] = CrossSet {
+                    bits: B::ZERO,
+                    score: 0,
+                };
                 wp += step;
             }
             cross_sets[wp] = CrossSet {
@@ -588,116 +739,164 @@ fn gen_classic_cross_set<'a>(
             // [j-1] has left and right.
             let j_end = cross_set_buffer[j as usize].end_range;
             let mut p_right = cross_set_buffer[j as usize].p;
-            let mut p_left = kwg.seek(cross_set_buffer[prev_j as usize].p, 0);
+            let mut p_left = seek(cross_set_buffer[prev_j as usize].p, 0);
             let mut bits = reuse_cross_set(&mut cached_cross_sets, j - 1, p_left, p_right);
-            if bits == 0 {
-                bits = 1u64;
+            if bits == B::ZERO {
+                bits = B::ONE;
                 if p_right > 0 && p_left > 0 {
                     p_right = kwg[p_right].arc_index();
                     if p_right > 0 {
                         p_left = kwg[p_left].arc_index();
                         if p_left > 0 {
-                            let mut node_left = kwg[p_left];
-                            let mut node_right = kwg[p_right];
-                            let mut node_left_tile = node_left.tile();
-                            if j_end - j > j - 1 - prev_j {
-                                // Right is longer than left.
-                                loop {
-                                    match node_left_tile.cmp(&node_right.tile()) {
-                                        std::cmp::Ordering::Less => {
-                                            // left < right: advance left
-                                            if node_left.is_end() {
-                                                break;
-                                            }
-                                            p_left += 1;
-                                            node_left = kwg[p_left];
-                                            node_left_tile = node_left.tile();
-                                        }
-                                        std::cmp::Ordering::Greater => {
-                                            // left > right: advance right
-                                            if node_right.is_end() {
-                                                break;
-                                            }
-                                            p_right += 1;
-                                            node_right = kwg[p_right];
-                                        }
-                                        std::cmp::Ordering::Equal => {
-                                            // left == right (right is longer than left):
-                                            // complete right half with the shorter left half
-                                            let mut q = p_right;
-                                            for qi in (prev_j..j - 1).rev() {
-                                                q = kwg.seek(
-                                                    q,
-                                                    cross_set_buffer[qi as usize].b_letter,
-                                                );
-                                                if q <= 0 {
-                                                    break;
+                            if let Some(letter_sets) = letter_sets {
+                                // only tiles on both arc lists can fit, complete
+                                // each from the longer half with the shorter half.
+                                let candidates =
+                                    letter_sets.children(p_left) & letter_sets.children(p_right);
+                                if candidates != B::ZERO {
+                                    let right_is_longer = j_end - j > j - 1 - prev_j;
+                                    let mut p = if right_is_longer { p_right } else { p_left };
+                                    loop {
+                                        let node = kwg[p];
+                                        let tile = node.tile();
+                                        if candidates & (B::ONE << tile) != B::ZERO {
+                                            let mut q = p;
+                                            if right_is_longer {
+                                                for qi in (prev_j..j - 1).rev() {
+                                                    q = seek(
+                                                        q,
+                                                        cross_set_buffer[qi as usize].b_letter,
+                                                    );
+                                                    if q <= 0 {
+                                                        break;
+                                                    }
+                                                }
+                                            } else {
+                                                for qi in j..j_end {
+                                                    q = seek(
+                                                        q,
+                                                        cross_set_buffer[qi as usize].b_letter,
+                                                    );
+                                                    if q <= 0 {
+                                                        break;
+                                                    }
                                                 }
                                             }
                                             if q > 0 {
-                                                bits |= (kwg[q].accepts() as u64) << node_left_tile;
-                                            }
-                                            if node_left.is_end() {
-                                                break;
+                                                bits |= B::from(kwg[q].accepts()) << tile;
                                             }
-                                            p_left += 1;
-                                            node_left = kwg[p_left];
-                                            node_left_tile = node_left.tile();
-                                            if node_right.is_end() {
-                                                break;
-                                            }
-                                            p_right += 1;
-                                            node_right = kwg[p_right];
                                         }
+                                        if node.is_end() {
+                                            break;
+                                        }
+                                        p += 1;
                                     }
                                 }
                             } else {
-                                loop {
-                                    match node_left_tile.cmp(&node_right.tile()) {
-                                        std::cmp::Ordering::Less => {
-                                            // left < right: advance left
-                                            if node_left.is_end() {
-                                                break;
+                                let mut node_left = kwg[p_left];
+                                let mut node_right = kwg[p_right];
+                                let mut node_left_tile = node_left.tile();
+                                if j_end - j > j - 1 - prev_j {
+                                    // Right is longer than left.
+                                    loop {
+                                        match node_left_tile.cmp(&node_right.tile()) {
+                                            std::cmp::Ordering::Less => {
+                                                // left < right: advance left
+                                                if node_left.is_end() {
+                                                    break;
+                                                }
+                                                p_left += 1;
+                                                node_left = kwg[p_left];
+                                                node_left_tile = node_left.tile();
                                             }
-                                            p_left += 1;
-                                            node_left = kwg[p_left];
-                                            node_left_tile = node_left.tile();
-                                        }
-                                        std::cmp::Ordering::Greater => {
-                                            // left > right: advance right
-                                            if node_right.is_end() {
-                                                break;
+                                            std::cmp::Ordering::Greater => {
+                                                // left > right: advance right
+                                                if node_right.is_end() {
+                                                    break;
+                                                }
+                                                p_right += 1;
+                                                node_right = kwg[p_right];
                                             }
-                                            p_right += 1;
-                                            node_right = kwg[p_right];
-                                        }
-                                        std::cmp::Ordering::Equal => {
-                                            // left == right (right is not longer than left):
-                                            // complete left half with right half
-                                            let mut q = p_left;
-                                            for qi in j..j_end {
-                                                q = kwg.seek(
-                                                    q,
-                                                    cross_set_buffer[qi as usize].b_letter,
-                                                );
-                                                if q <= 0 {
+                                            std::cmp::Ordering::Equal => {
+                                                // left == right (right is longer than left):
+                                                // complete right half with the shorter left half
+                                                let mut q = p_right;
+                                                for qi in (prev_j..j - 1).rev() {
+                                                    q = seek(
+                                                        q,
+                                                        cross_set_buffer[qi as usize].b_letter,
+                                                    );
+                                                    if q <= 0 {
+                                                        break;
+                                                    }
+                                                }
+                                                if q > 0 {
+                                                    bits |=
+                                                        B::from(kwg[q].accepts()) << node_left_tile;
+                                                }
+                                                if node_left.is_end() {
+                                                    break;
+                                                }
+                                                p_left += 1;
+                                                node_left = kwg[p_left];
+                                                node_left_tile = node_left.tile();
+                                                if node_right.is_end() {
                                                     break;
                                                 }
+                                                p_right += 1;
+                                                node_right = kwg[p_right];
                                             }
-                                            if q > 0 {
-                                                bits |= (kwg[q].accepts() as u64) << node_left_tile;
+                                        }
+                                    }
+                                } else {
+                                    loop {
+                                        match node_left_tile.cmp(&node_right.tile()) {
+                                            std::cmp::Ordering::Less => {
+                                                // left < right: advance left
+                                                if node_left.is_end() {
+                                                    break;
+                                                }
+                                                p_left += 1;
+                                                node_left = kwg[p_left];
+                                                node_left_tile = node_left.tile();
                                             }
-                                            if node_right.is_end() {
-                                                break;
+                                            std::cmp::Ordering::Greater => {
+                                                // left > right: advance right
+                                                if node_right.is_end() {
+                                                    break;
+                                                }
+                                                p_right += 1;
+                                                node_right = kwg[p_right];
                                             }
-                                            p_right += 1;
-                                            node_right = kwg[p_right];
-                                            if node_left.is_end() {
-                                                break;
+                                            std::cmp::Ordering::Equal => {
+                                                // left == right (right is not longer than left):
+                                                // complete left half with right half
+                                                let mut q = p_left;
+                                                for qi in j..j_end {
+                                                    q = seek(
+                                                        q,
+                                                        cross_set_buffer[qi as usize].b_letter,
+                                                    );
+                                                    if q <= 0 {
+                                                        break;
+                                                    }
+                                                }
+                                                if q > 0 {
+                                                    bits |=
+                                                        B::from(kwg[q].accepts()) << node_left_tile;
+                                                }
+                                                if node_right.is_end() {
+                                                    break;
+                                                }
+                                                p_right += 1;
+                                                node_right = kwg[p_right];
+                                                if node_left.is_end() {
+                                                    break;
+                                                }
+                                                p_left += 1;
+                                                node_left = kwg[p_left];
+                                                node_left_tile = node_left.tile();
                                             }
-                                            p_left += 1;
-                                            node_left = kwg[p_left];
-                                            node_left_tile = node_left.tile();
                                         }
                                     }
                                 }
@@ -708,7 +907,10 @@ fn gen_classic_cross_set<'a>(
                 cached_cross_sets[j as usize - 1].bits = bits;
             }
             for _ in wi..j - 1 {
-                cross_sets[wp] = CrossSet { bits: 0, score: 0 };
+                cross_sets[wp] = CrossSet {
+                    bits: B::ZERO,
+                    score: 0,
+                };
                 wp += step;
             }
             cross_sets[wp] = CrossSet {
@@ -724,16 +926,18 @@ fn gen_classic_cross_set<'a>(
             break;
         }
         // [j] has left, no right.
-        let mut p = kwg.seek(cross_set_buffer[prev_j as usize].p, 0);
+        let mut p = seek(cross_set_buffer[prev_j as usize].p, 0);
         let mut bits = reuse_cross_set(&mut cached_cross_sets, j, p, -2);
-        if bits == 0 {
-            bits = 1u64;
+        if bits == B::ZERO {
+            bits = B::ONE;
             if p > 0 {
                 p = kwg[p].arc_index();
-                if p > 0 {
+                if let Some(letter_sets) = letter_sets {
+                    bits |= letter_sets.accepts(p);
+                } else if p > 0 {
                     loop {
                         let node = kwg[p];
-                        bits |= (node.accepts() as u64) << node.tile();
+                        bits |= B::from(node.accepts()) << node.tile();
                         if node.is_end() {
                             break;
                         }
@@ -744,7 +948,10 @@ fn gen_classic_cross_set<'a>(
             cached_cross_sets[j as usize].bits = bits;
         }
         for _ in wi..j {
-            cross_sets[wp] = CrossSet { bits: 0, score: 0 };
+            cross_sets[wp] = CrossSet {
+                bits: B::ZERO,
+                score: 0,
+            };
             wp += step;
         }
         cross_sets[wp] = CrossSet {
@@ -756,16 +963,19 @@ fn gen_classic_cross_set<'a>(
         j = cross_set_buffer[j as usize].end_range;
     }
     for _ in wi..len {
-        cross_sets[wp] = CrossSet { bits: 0, score: 0 };
+        cross_sets[wp] = CrossSet {
+            bits: B::ZERO,
+            score: 0,
+        };
         wp += step;
     }
 }
 
 // this is suboptimal, it computes lone islands twice.
-fn gen_jumbled_cross_set<'a>(
+fn gen_jumbled_cross_set<'a, B: bits::Bits>(
     board_snapshot: &'a BoardSnapshot<'a>,
     board_strip: &'a [u8],
-    cross_sets: &'a mut [CrossSet],
+    cross_sets: &'a mut [CrossSet<B>],
     output_strider: matrix::Strider,
     used_letters_tally: &'a mut [u8],
 ) {
@@ -777,7 +987,10 @@ fn gen_jumbled_cross_set<'a>(
     for i in 0..len {
         let b = board_strip[i as usize];
         if b != 0 {
-            cross_sets[wp] = CrossSet { bits: 0, score: 0 };
+            cross_sets[wp] = CrossSet {
+                bits: B::ZERO,
+                score: 0,
+            };
         } else {
             let mut score = 0i16;
             let mut j = i;
@@ -801,7 +1014,10 @@ fn gen_jumbled_cross_set<'a>(
                 used_letters_tally[(b & 0x7f) as usize] += 1;
             }
             if k == j + 1 {
-                cross_sets[wp] = CrossSet { bits: 0, score: 0 };
+                cross_sets[wp] = CrossSet {
+                    bits: B::ZERO,
+                    score: 0,
+                };
             } else {
                 cross_sets[wp] = CrossSet {
                     bits: kwg.compute_alpha_cross_set(used_letters_tally),
@@ -814,15 +1030,19 @@ fn gen_jumbled_cross_set<'a>(
     }
 }
 
+// blocked squares get only bit 0, so nothing can be placed there.
+#[allow(clippy::too_many_arguments)]
 #[inline(always)]
-fn gen_cross_set<'a>(
+fn gen_cross_set<'a, B: bits::Bits>(
     board_snapshot: &'a BoardSnapshot<'a>,
     board_strip: &'a [u8],
-    cross_sets: &'a mut [CrossSet],
+    blocked_strip: &'a [bool],
+    cross_sets: &'a mut [CrossSet<B>],
     output_strider: matrix::Strider,
     cross_set_buffer: &'a mut [CrossSetComputation],
-    cached_cross_sets: &'a mut [CachedCrossSet],
+    cached_cross_sets: &'a mut [CachedCrossSet<B>],
     used_letters_tally: &'a mut [u8],
+    letter_sets: Option<&letter_sets::LetterSets<B>>,
 ) {
     match board_snapshot.game_config.game_rules() {
         game_config::GameRules::Classic => gen_classic_cross_set(
@@ -832,6 +1052,7 @@ fn gen_cross_set<'a>(
             output_strider,
             cross_set_buffer,
             cached_cross_sets,
+            letter_sets,
         ),
         game_config::GameRules::Jumbled => gen_jumbled_cross_set(
             board_snapshot,
@@ -841,26 +1062,140 @@ fn gen_cross_set<'a>(
             used_letters_tally,
         ),
     }
+    let step = output_strider.step() as usize;
+    let mut wp = output_strider.base() as usize;
+    for &is_blocked in blocked_strip {
+        if is_blocked {
+            cross_sets[wp] = CrossSet {
+                bits: B::ONE,
+                score: 0,
+            };
+        }
+        wp += step;
+    }
+}
+
+// Constraints resolved for one strip.
+#[derive(Clone, Copy)]
+struct StripConstraints {
+    num_played: i8, // 0 if any
+    cover_idx: i8,  // -1 if any
+    hit_tws: bool,
+    min_word_len: i8,
+}
+
+impl StripConstraints {
+    #[inline(always)]
+    fn accepts(
+        &self,
+        idx_left: i8,
+        idx_right: i8,
+        num_played: i8,
+        remaining_word_multipliers_strip: &[i8],
+    ) -> bool {
+        (self.num_played == 0 || num_played == self.num_played)
+            && (self.cover_idx < 0 || (idx_left <= self.cover_idx && self.cover_idx < idx_right))
+            && idx_right - idx_left >= self.min_word_len
+            && (!self.hit_tws
+                || remaining_word_multipliers_strip[idx_left as usize..idx_right as usize]
+                    .iter()
+                    .any(|&wm| wm >= 3))
+    }
+
+    // whether a word within [leftmost, rightmost) could be accepted.
+    #[inline(always)]
+    fn may_fit(
+        &self,
+        leftmost: i8,
+        rightmost: i8,
+        remaining_word_multipliers_strip: &[i8],
+    ) -> bool {
+        self.accepts(
+            leftmost,
+            rightmost,
+            self.num_played,
+            remaining_word_multipliers_strip,
+        )
+    }
+
+    // words extending rightwards from idx_left can no longer cover it.
+    #[inline(always)]
+    fn passed_cover(&self, idx_left: i8) -> bool {
+        self.cover_idx >= 0 && self.cover_idx < idx_left
+    }
+
+    // whether a word spanning [idx_left, idx_right) so far can still be accepted
+    // after placing at most num_tiles_left more tiles within [leftmost, rightmost).
+    // ignores hit_tws, which accepts checks when recording.
+    #[allow(clippy::too_many_arguments)]
+    fn may_extend(
+        &self,
+        board_strip: &[u8],
+        leftmost: i8,
+        idx_left: i8,
+        idx_right: i8,
+        rightmost: i8,
+        num_played: i8,
+        num_tiles_left: i8,
+    ) -> bool {
+        let num_empty = |range: std::ops::Range<i8>| {
+            board_strip[range.start as usize..range.end as usize]
+                .iter()
+                .filter(|&&b| b == 0)
+                .count() as i8
+        };
+        if self.cover_idx >= 0 {
+            if self.cover_idx < idx_left {
+                if self.cover_idx < leftmost || num_empty(self.cover_idx..idx_left) > num_tiles_left
+                {
+                    return false;
+                }
+            } else if self.cover_idx >= idx_right
+                && (self.cover_idx >= rightmost
+                    || num_empty(idx_right..self.cover_idx + 1) > num_tiles_left)
+            {
+                return false;
+            }
+        }
+        if self.num_played == 0 && self.min_word_len <= idx_right - idx_left {
+            return true;
+        }
+        let num_empty_left = num_empty(leftmost..idx_left);
+        let num_empty_right = num_empty(idx_right..rightmost);
+        let num_fillable = std::cmp::min(num_tiles_left, num_empty_left + num_empty_right);
+        if self.num_played != 0 && num_played + num_fillable < self.num_played {
+            return false;
+        }
+        // every occupied square in range might join the word for free.
+        let num_occupied =
+            (idx_left - leftmost - num_empty_left) + (rightmost - idx_right - num_empty_right);
+        idx_right - idx_left + num_fillable + num_occupied >= self.min_word_len
+    }
 }
 
-struct GenPlacePlacementsParams<'a> {
+struct GenPlacePlacementsParams<'a, B: bits::Bits> {
     board_strip: &'a [u8],
-    cross_set_strip: &'a [CrossSet],
+    cross_set_strip: &'a [CrossSet<B>],
     remaining_word_multipliers_strip: &'a [i8],
     face_value_scores_strip: &'a [i8],
     perpendicular_scores_strip: &'a [i16],
-    rack_bits: u64,
+    rack_bits: B,
     descending_scores: &'a [i8],
     square_multipliers_by_aggregated_word_multipliers_buffer: &'a mut fash::MyHashMap<i16, usize>,
     precomputed_square_multiplier_buffer: &'a mut Vec<i16>,
     indexes_to_descending_square_multiplier_buffer: &'a mut Vec<i8>,
     best_leave_values: &'a [f32],
     num_max_played: u8,
+    constraints: Option<StripConstraints>,
 }
 
-fn gen_place_placements<'a, PossibleStripPlacementCallbackType: FnMut(i8, i8, i8, f32)>(
+fn gen_place_placements<
+    'a,
+    B: bits::Bits,
+    PossibleStripPlacementCallbackType: FnMut(i8, i8, i8, f32),
+>(
     want_raw: bool,
-    params: &'a mut GenPlacePlacementsParams<'a>,
+    params: &'a mut GenPlacePlacementsParams<'a, B>,
     remaining_tile_multipliers_strip: &'a [i8],
     perpendicular_word_multipliers_strip: &'a [i8],
     num_tiles_on_rack: u8,
@@ -925,8 +1260,8 @@ fn gen_place_placements<'a, PossibleStripPlacementCallbackType: FnMut(i8, i8, i8
         }
     }
 
-    struct Env<'a> {
-        params: &'a GenPlacePlacementsParams<'a>,
+    struct Env<'a, B: bits::Bits> {
+        params: &'a GenPlacePlacementsParams<'a, B>,
         strider_len: usize,
         anchor: i8,
         leftmost: i8,
@@ -947,14 +1282,24 @@ fn gen_place_placements<'a, PossibleStripPlacementCallbackType: FnMut(i8, i8, i8
         best_possible_equity: f32::NEG_INFINITY,
     };
 
-    fn shadow_record(
-        env: &mut Env,
+    fn shadow_record<B: bits::Bits>(
+        env: &mut Env<B>,
         idx_left: i8,
         idx_right: i8,
         main_played_through_score: i16,
         perpendicular_additional_score: i16,
         word_multiplier: i16,
     ) {
+        if let Some(constraints) = &env.params.constraints {
+            if !constraints.accepts(
+                idx_left,
+                idx_right,
+                env.num_played,
+                env.params.remaining_word_multipliers_strip,
+            ) {
+                return;
+            }
+        }
         let low_end = env
             .params
             .square_multipliers_by_aggregated_word_multipliers_buffer[&word_multiplier];
@@ -983,14 +1328,19 @@ fn gen_place_placements<'a, PossibleStripPlacementCallbackType: FnMut(i8, i8, i8
         }
     }
 
-    fn shadow_play_right(
-        env: &mut Env,
+    fn shadow_play_right<B: bits::Bits>(
+        env: &mut Env<B>,
         mut idx: i8,
         mut main_played_through_score: i16,
         perpendicular_additional_score: i16,
         word_multiplier: i16,
         is_unique: bool,
     ) {
+        if let Some(constraints) = &env.params.constraints {
+            if constraints.passed_cover(env.idx_left) {
+                return;
+            }
+        }
         // tail-recurse placing current sequence of tiles
         while idx < env.rightmost {
             let b = env.params.board_strip[idx as usize];
@@ -1023,7 +1373,7 @@ fn gen_place_placements<'a, PossibleStripPlacementCallbackType: FnMut(i8, i8, i8
         // place a tile at [idx] if it is still in bounds
         if idx < env.rightmost {
             let this_cross_bits = env.params.cross_set_strip[idx as usize].bits;
-            if this_cross_bits & 1 == 0 {
+            if this_cross_bits & B::ONE == B::ZERO {
                 // nothing hooks here
                 env.num_played += 1;
                 shadow_play_right(
@@ -1036,7 +1386,7 @@ fn gen_place_placements<'a, PossibleStripPlacementCallbackType: FnMut(i8, i8, i8
                     true,
                 );
                 env.num_played -= 1;
-            } else if this_cross_bits & env.params.rack_bits != 0 {
+            } else if this_cross_bits & env.params.rack_bits != B::ZERO {
                 // something hooks here
                 // rack_bits remains unchanged because assignment is tentative.
                 env.num_played += 1;
@@ -1055,8 +1405,8 @@ fn gen_place_placements<'a, PossibleStripPlacementCallbackType: FnMut(i8, i8, i8
         }
     }
 
-    fn shadow_play_left(
-        env: &mut Env,
+    fn shadow_play_left<B: bits::Bits>(
+        env: &mut Env<B>,
         mut idx: i8,
         mut main_played_through_score: i16,
         perpendicular_additional_score: i16,
@@ -1105,7 +1455,7 @@ fn gen_place_placements<'a, PossibleStripPlacementCallbackType: FnMut(i8, i8, i8
         // place a tile at [idx] if it is still in bounds
         if idx >= env.leftmost {
             let this_cross_bits = env.params.cross_set_strip[idx as usize].bits;
-            if this_cross_bits & 1 == 0 {
+            if this_cross_bits & B::ONE == B::ZERO {
                 // nothing hooks here
                 env.num_played += 1;
                 shadow_play_left(
@@ -1118,7 +1468,7 @@ fn gen_place_placements<'a, PossibleStripPlacementCallbackType: FnMut(i8, i8, i8
                     true,
                 );
                 env.num_played -= 1;
-            } else if this_cross_bits & env.params.rack_bits != 0 {
+            } else if this_cross_bits & env.params.rack_bits != B::ZERO {
                 // something hooks here
                 // rack_bits remains unchanged because assignment is tentative.
                 env.num_played += 1;
@@ -1138,12 +1488,39 @@ fn gen_place_placements<'a, PossibleStripPlacementCallbackType: FnMut(i8, i8, i8
     }
 
     #[inline(always)]
-    fn gen_moves_from<PossibleStripPlacementCallbackType: FnMut(i8, i8, i8, f32)>(
+    fn gen_moves_from<B: bits::Bits, PossibleStripPlacementCallbackType: FnMut(i8, i8, i8, f32)>(
         want_raw: bool,
-        env: &mut Env,
+        env: &mut Env<B>,
         single_tile_plays: bool,
         mut possible_strip_placement_callback: PossibleStripPlacementCallbackType,
     ) {
+        if let Some(constraints) = &env.params.constraints {
+            if !constraints.may_fit(
+                env.leftmost,
+                env.rightmost,
+                env.params.remaining_word_multipliers_strip,
+            ) {
+                return;
+            }
+        }
+        // an empty square where nothing can be placed (such as a blocked
+        // square) bounds the word like the board edge.
+        for idx in (env.leftmost..env.anchor).rev() {
+            if env.params.board_strip[idx as usize] == 0
+                && env.params.cross_set_strip[idx as usize].bits == B::ONE
+            {
+                env.leftmost = idx + 1;
+                break;
+            }
+        }
+        for idx in env.anchor + 1..env.rightmost {
+            if env.params.board_strip[idx as usize] == 0
+                && env.params.cross_set_strip[idx as usize].bits == B::ONE
+            {
+                env.rightmost = idx;
+                break;
+            }
+        }
         if want_raw {
             possible_strip_placement_callback(env.anchor, env.leftmost, env.rightmost, 0.0);
         } else {
@@ -1183,12 +1560,12 @@ fn gen_place_placements<'a, PossibleStripPlacementCallbackType: FnMut(i8, i8, i8
             let leftmost = leftmost + (leftmost > 0) as i8; // shadowing
             for anchor in (leftmost..rightmost).rev() {
                 let cross_set_bits = params.cross_set_strip[anchor as usize].bits;
-                if cross_set_bits != 0 {
+                if cross_set_bits != B::ZERO {
                     if rightmost - leftmost < 2 {
                         // not enough room for 2-tile words
                         break;
                     }
-                    if cross_set_bits != 1 {
+                    if cross_set_bits != B::ONE {
                         env.anchor = anchor;
                         env.leftmost = leftmost;
                         env.rightmost = rightmost;
@@ -1213,10 +1590,10 @@ fn gen_place_placements<'a, PossibleStripPlacementCallbackType: FnMut(i8, i8, i8
     }
 }
 
-struct GenPlaceMovesParams<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])> {
+struct GenPlaceMovesParams<'a, B: bits::Bits, CallbackType: FnMut(i8, &[u8], i16, &[u8])> {
     board_snapshot: &'a BoardSnapshot<'a>,
     board_strip: &'a [u8],
-    cross_set_strip: &'a [CrossSet],
+    cross_set_strip: &'a [CrossSet<B>],
     remaining_word_multipliers_strip: &'a [i8],
     remaining_tile_multipliers_strip: &'a [i8],
     face_value_scores_strip: &'a [i8],
@@ -1230,27 +1607,38 @@ struct GenPlaceMovesParams<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])> {
     rightmost: i8,
     callback: CallbackType,
     used_letters_tally: &'a mut [u8], // jumbled mode only
+    constraints: Option<StripConstraints>,
 }
 
-fn gen_classic_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
-    params: &'a mut GenPlaceMovesParams<'a, CallbackType>,
+fn gen_classic_place_moves<'a, B: bits::Bits, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
+    params: &'a mut GenPlaceMovesParams<'a, B, CallbackType>,
     single_tile_plays: bool,
 ) {
-    struct Env<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])> {
-        params: &'a mut GenPlaceMovesParams<'a, CallbackType>,
+    struct Env<'a, B: bits::Bits, CallbackType: FnMut(i8, &[u8], i16, &[u8])> {
+        params: &'a mut GenPlaceMovesParams<'a, B, CallbackType>,
         alphabet: &'a alphabet::Alphabet<'a>,
         num_played: i8,
         idx_left: i8,
     }
 
-    fn record<CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
-        env: &mut Env<CallbackType>,
+    fn record<B: bits::Bits, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
+        env: &mut Env<B, CallbackType>,
         idx_left: i8,
         idx_right: i8,
         main_score: i16,
         perpendicular_cumulative_score: i16,
         word_multiplier: i16,
     ) {
+        if let Some(constraints) = &env.params.constraints {
+            if !constraints.accepts(
+                idx_left,
+                idx_right,
+                env.num_played,
+                env.params.remaining_word_multipliers_strip,
+            ) {
+                return;
+            }
+        }
         let score = main_score * word_multiplier
             + perpendicular_cumulative_score
             + env
@@ -1266,8 +1654,8 @@ fn gen_classic_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
         );
     }
 
-    fn play_right<CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
-        env: &mut Env<CallbackType>,
+    fn play_right<B: bits::Bits, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
+        env: &mut Env<B, CallbackType>,
         mut idx: i8,
         mut p: i32,
         mut main_score: i16,
@@ -1275,6 +1663,11 @@ fn gen_classic_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
         word_multiplier: i16,
         mut is_unique: bool,
     ) {
+        if let Some(constraints) = &env.params.constraints {
+            if constraints.passed_cover(env.idx_left) {
+                return;
+            }
+        }
         // tail-recurse placing current sequence of tiles
         while idx < env.params.rightmost {
             let b = env.params.board_strip[idx as usize];
@@ -1313,14 +1706,14 @@ fn gen_classic_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
                 return;
             }
             let mut this_cross_bits = env.params.cross_set_strip[idx as usize].bits;
-            if this_cross_bits == 1 {
+            if this_cross_bits == B::ONE {
                 // already handled '@'
                 return;
-            } else if this_cross_bits != 0 {
+            } else if this_cross_bits != B::ZERO {
                 // turn off bit 0 so it cannot match later
-                this_cross_bits &= !1;
+                this_cross_bits &= !B::ONE;
             } else {
-                this_cross_bits = !1;
+                this_cross_bits = !B::ONE;
                 is_unique = true;
             };
             let new_word_multiplier =
@@ -1332,7 +1725,7 @@ fn gen_classic_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
             loop {
                 let node = env.params.board_snapshot.kwg[p];
                 let tile = node.tile();
-                if this_cross_bits & (1 << tile) != 0 {
+                if this_cross_bits & (B::ONE << tile) != B::ZERO {
                     if env.params.rack_tally[tile as usize] > 0 {
                         env.params.rack_tally[tile as usize] -= 1;
                         env.num_played += 1;
@@ -1381,8 +1774,8 @@ fn gen_classic_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
         }
     }
 
-    fn play_left<CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
-        env: &mut Env<CallbackType>,
+    fn play_left<B: bits::Bits, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
+        env: &mut Env<B, CallbackType>,
         mut idx: i8,
         mut p: i32,
         mut main_score: i16,
@@ -1418,6 +1811,19 @@ fn gen_classic_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
         if env.num_played as u8 >= env.params.num_max_played {
             return;
         }
+        if let Some(constraints) = &env.params.constraints {
+            if !constraints.may_extend(
+                env.params.board_strip,
+                env.params.leftmost,
+                idx + 1,
+                env.params.anchor + 1,
+                env.params.rightmost,
+                env.num_played,
+                env.params.num_max_played as i8 - env.num_played,
+            ) {
+                return;
+            }
+        }
 
         p = node.arc_index();
         if p <= 0 {
@@ -1445,14 +1851,14 @@ fn gen_classic_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
 
         if idx >= env.params.leftmost {
             let mut this_cross_bits = env.params.cross_set_strip[idx as usize].bits;
-            if this_cross_bits == 1 {
+            if this_cross_bits == B::ONE {
                 // already handled '@'
                 return;
-            } else if this_cross_bits != 0 {
+            } else if this_cross_bits != B::ZERO {
                 // turn off bit 0 so it cannot match later
-                this_cross_bits &= !1;
+                this_cross_bits &= !B::ONE;
             } else {
-                this_cross_bits = !1;
+                this_cross_bits = !B::ONE;
                 is_unique = true;
             }
             let new_word_multiplier =
@@ -1464,7 +1870,7 @@ fn gen_classic_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
             loop {
                 let node = env.params.board_snapshot.kwg[p];
                 let tile = node.tile();
-                if this_cross_bits & (1 << tile) != 0 {
+                if this_cross_bits & (B::ONE << tile) != B::ZERO {
                     if env.params.rack_tally[tile as usize] > 0 {
                         env.params.rack_tally[tile as usize] -= 1;
                         env.num_played += 1;
@@ -1531,25 +1937,35 @@ fn gen_classic_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
     );
 }
 
-fn gen_jumbled_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
-    params: &'a mut GenPlaceMovesParams<'a, CallbackType>,
+fn gen_jumbled_place_moves<'a, B: bits::Bits, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
+    params: &'a mut GenPlaceMovesParams<'a, B, CallbackType>,
     single_tile_plays: bool,
 ) {
-    struct Env<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])> {
-        params: &'a mut GenPlaceMovesParams<'a, CallbackType>,
+    struct Env<'a, B: bits::Bits, CallbackType: FnMut(i8, &[u8], i16, &[u8])> {
+        params: &'a mut GenPlaceMovesParams<'a, B, CallbackType>,
         alphabet: &'a alphabet::Alphabet<'a>,
         num_played: i8,
         idx_left: i8,
     }
 
-    fn record_if_valid<CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
-        env: &mut Env<CallbackType>,
+    fn record_if_valid<B: bits::Bits, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
+        env: &mut Env<B, CallbackType>,
         idx_left: i8,
         idx_right: i8,
         main_score: i16,
         perpendicular_cumulative_score: i16,
         word_multiplier: i16,
     ) {
+        if let Some(constraints) = &env.params.constraints {
+            if !constraints.accepts(
+                idx_left,
+                idx_right,
+                env.num_played,
+                env.params.remaining_word_multipliers_strip,
+            ) {
+                return;
+            }
+        }
         if env
             .params
             .board_snapshot
@@ -1572,14 +1988,19 @@ fn gen_jumbled_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
         }
     }
 
-    fn play_right<CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
-        env: &mut Env<CallbackType>,
+    fn play_right<B: bits::Bits, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
+        env: &mut Env<B, CallbackType>,
         mut idx: i8,
         mut main_score: i16,
         perpendicular_cumulative_score: i16,
         word_multiplier: i16,
         mut is_unique: bool,
     ) {
+        if let Some(constraints) = &env.params.constraints {
+            if constraints.passed_cover(env.idx_left) {
+                return;
+            }
+        }
         let orig_idx = idx;
         // tail-recurse placing current sequence of tiles
         while idx < env.params.rightmost {
@@ -1606,14 +2027,14 @@ fn gen_jumbled_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
         }
         if (env.num_played as u8) < env.params.num_max_played && idx < env.params.rightmost {
             let mut this_cross_bits = env.params.cross_set_strip[idx as usize].bits;
-            if this_cross_bits == 1 {
+            if this_cross_bits == B::ONE {
                 // already handled '@'
             } else {
-                if this_cross_bits != 0 {
+                if this_cross_bits != B::ZERO {
                     // turn off bit 0 so it cannot match later
-                    this_cross_bits &= !1;
+                    this_cross_bits &= !B::ONE;
                 } else {
-                    this_cross_bits = !1;
+                    this_cross_bits = !B::ONE;
                     is_unique = true;
                 };
                 let new_word_multiplier = word_multiplier
@@ -1623,7 +2044,7 @@ fn gen_jumbled_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
                     env.params.perpendicular_word_multipliers_strip[idx as usize];
                 let perpendicular_score = env.params.perpendicular_scores_strip[idx as usize];
                 for tile in 1..env.alphabet.len() {
-                    if this_cross_bits & (1 << tile) != 0 {
+                    if this_cross_bits & (B::ONE << tile) != B::ZERO {
                         if env.params.rack_tally[tile as usize] > 0 {
                             env.params.rack_tally[tile as usize] -= 1;
                             env.num_played += 1;
@@ -1677,8 +2098,8 @@ fn gen_jumbled_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
         }
     }
 
-    fn play_left<CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
-        env: &mut Env<CallbackType>,
+    fn play_left<B: bits::Bits, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
+        env: &mut Env<B, CallbackType>,
         mut idx: i8,
         mut main_score: i16,
         perpendicular_cumulative_score: i16,
@@ -1706,7 +2127,20 @@ fn gen_jumbled_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
                 word_multiplier,
             );
         }
-        if (env.num_played as u8) < env.params.num_max_played {
+        // no early return, used_letters_tally is restored below.
+        let may_extend = match &env.params.constraints {
+            Some(constraints) => constraints.may_extend(
+                env.params.board_strip,
+                env.params.leftmost,
+                idx + 1,
+                env.params.anchor + 1,
+                env.params.rightmost,
+                env.num_played,
+                env.params.num_max_played as i8 - env.num_played,
+            ),
+            None => true,
+        };
+        if (env.num_played as u8) < env.params.num_max_played && may_extend {
             if idx < env.params.anchor {
                 env.idx_left = idx + 1;
                 play_right(
@@ -1721,14 +2155,14 @@ fn gen_jumbled_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
 
             if idx >= env.params.leftmost {
                 let mut this_cross_bits = env.params.cross_set_strip[idx as usize].bits;
-                if this_cross_bits == 1 {
+                if this_cross_bits == B::ONE {
                     // already handled '@'
                 } else {
-                    if this_cross_bits != 0 {
+                    if this_cross_bits != B::ZERO {
                         // turn off bit 0 so it cannot match later
-                        this_cross_bits &= !1;
+                        this_cross_bits &= !B::ONE;
                     } else {
-                        this_cross_bits = !1;
+                        this_cross_bits = !B::ONE;
                         is_unique = true;
                     }
                     let new_word_multiplier = word_multiplier
@@ -1738,7 +2172,7 @@ fn gen_jumbled_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
                         env.params.perpendicular_word_multipliers_strip[idx as usize];
                     let perpendicular_score = env.params.perpendicular_scores_strip[idx as usize];
                     for tile in 1..env.alphabet.len() {
-                        if this_cross_bits & (1 << tile) != 0 {
+                        if this_cross_bits & (B::ONE << tile) != B::ZERO {
                             if env.params.rack_tally[tile as usize] > 0 {
                                 env.params.rack_tally[tile as usize] -= 1;
                                 env.num_played += 1;
@@ -1812,8 +2246,8 @@ fn gen_jumbled_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
 }
 
 #[inline(always)]
-fn gen_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
-    params: &'a mut GenPlaceMovesParams<'a, CallbackType>,
+fn gen_place_moves<'a, B: bits::Bits, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
+    params: &'a mut GenPlaceMovesParams<'a, B, CallbackType>,
     single_tile_plays: bool,
 ) {
     match params.board_snapshot.game_config.game_rules() {
@@ -1822,11 +2256,12 @@ fn gen_place_moves<'a, CallbackType: FnMut(i8, &[u8], i16, &[u8])>(
     }
 }
 
-fn gen_place_moves_at<'a, FoundPlaceMove: FnMut(bool, i8, i8, &[u8], i16, &[u8])>(
+fn gen_place_moves_at<'a, B: bits::Bits, FoundPlaceMove: FnMut(bool, i8, i8, &[u8], i16, &[u8])>(
     board_snapshot: &'a BoardSnapshot<'a>,
-    working_buffer: &mut WorkingBuffer,
+    working_buffer: &mut WorkingBuffer<B>,
     placement: &PossiblePlacement,
     max_rack_size: u8,
+    constraints: Option<&Constraints>,
     mut found_place_move: FoundPlaceMove,
 ) {
     let dim = board_snapshot.game_config.board_layout().dim();
@@ -1839,6 +2274,21 @@ fn gen_place_moves_at<'a, FoundPlaceMove: FnMut(bool, i8, i8, &[u8], i16, &[u8])
         strip_range_start = (placement.lane as isize * dim.cols as isize) as usize;
         strip_range_end = strip_range_start + dim.cols as usize;
     }
+    let remaining_word_multipliers_strip = if placement.down {
+        &working_buffer.remaining_word_multipliers_for_down_plays
+            [strip_range_start..strip_range_end]
+    } else {
+        &working_buffer.remaining_word_multipliers_for_across_plays
+            [strip_range_start..strip_range_end]
+    };
+    let constraints = constraints.and_then(|constraints| {
+        constraints.for_strip(
+            placement.down,
+            placement.lane,
+            remaining_word_multipliers_strip,
+            working_buffer.num_tiles_on_rack,
+        )
+    });
     gen_place_moves(
         &mut GenPlaceMovesParams {
             board_snapshot: &board_snapshot,
@@ -1852,13 +2302,7 @@ fn gen_place_moves_at<'a, FoundPlaceMove: FnMut(bool, i8, i8, &[u8], i16, &[u8])
             } else {
                 &working_buffer.cross_set_for_across_plays[strip_range_start..strip_range_end]
             },
-            remaining_word_multipliers_strip: if placement.down {
-                &working_buffer.remaining_word_multipliers_for_down_plays
-                    [strip_range_start..strip_range_end]
-            } else {
-                &working_buffer.remaining_word_multipliers_for_across_plays
-                    [strip_range_start..strip_range_end]
-            },
+            remaining_word_multipliers_strip,
             remaining_tile_multipliers_strip: if placement.down {
                 &working_buffer.remaining_tile_multipliers_for_down_plays
                     [strip_range_start..strip_range_end]
@@ -1900,6 +2344,7 @@ fn gen_place_moves_at<'a, FoundPlaceMove: FnMut(bool, i8, i8, &[u8], i16, &[u8])
                 found_place_move(placement.down, placement.lane, idx, word, score, rack_tally)
             },
             used_letters_tally: &mut working_buffer.used_letters_tally,
+            constraints,
         },
         !placement.down,
     );
@@ -1983,6 +2428,11 @@ impl Clone for Play {
     }
 }
 
+// ValuedMove is totally ordered, best first: higher equity, higher score,
+// then by Play::cmp_canonical. Generated moves are sorted this way, and the
+// moves kept are the first max_gen of that order, so they do not depend on
+// max_gen or on generation order. Equity compares with f32::total_cmp, so
+// -0.0 is below 0.0, and NaN is beyond the infinity of the same sign.
 pub struct ValuedMove {
     pub equity: f32,
     pub play: Play,
@@ -2007,7 +2457,7 @@ impl Clone for ValuedMove {
 impl PartialEq for ValuedMove {
     #[inline(always)]
     fn eq(&self, other: &Self) -> bool {
-        (other.equity - self.equity) == 0.0
+        self.cmp(other) == std::cmp::Ordering::Equal
     }
 }
 
@@ -2016,17 +2466,18 @@ impl Eq for ValuedMove {}
 impl PartialOrd for ValuedMove {
     #[inline(always)]
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        other.equity.partial_cmp(&self.equity)
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for ValuedMove {
     #[inline(always)]
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match self.partial_cmp(other) {
-            Some(x) => x,
-            None => std::cmp::Ordering::Equal,
-        }
+        other
+            .equity
+            .total_cmp(&self.equity)
+            .then_with(|| other.play.score().cmp(&self.play.score()))
+            .then_with(|| self.play.cmp_canonical(&other.play))
     }
 }
 
@@ -2103,47 +2554,432 @@ impl Play {
             play: self,
         }
     }
+
+    // exchanges and passes score 0.
+    #[inline(always)]
+    pub fn score(&self) -> i16 {
+        match self {
+            Play::Exchange { .. } => 0,
+            Play::Place { score, .. } => *score,
+        }
+    }
+
+    // Placements first, across before down, then by lane, idx and word.
+    // Exchanges after, by tiles (so Pass is the first exchange).
+    // Words compare tile by tile, so played-through (0) sorts first and
+    // blanks sort after all letters.
+    pub fn cmp_canonical(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (
+                Play::Place {
+                    down,
+                    lane,
+                    idx,
+                    word,
+                    ..
+                },
+                Play::Place {
+                    down: other_down,
+                    lane: other_lane,
+                    idx: other_idx,
+                    word: other_word,
+                    ..
+                },
+            ) => down
+                .cmp(other_down)
+                .then_with(|| lane.cmp(other_lane))
+                .then_with(|| idx.cmp(other_idx))
+                .then_with(|| word.cmp(other_word)),
+            (Play::Place { .. }, Play::Exchange { .. }) => std::cmp::Ordering::Less,
+            (Play::Exchange { .. }, Play::Place { .. }) => std::cmp::Ordering::Greater,
+            (Play::Exchange { tiles }, Play::Exchange { tiles: other_tiles }) => {
+                tiles.cmp(other_tiles)
+            }
+        }
+    }
 }
 
+// Generated plays are sorted by descending equity (f32::total_cmp, so a
+// positive NaN sorts first), then descending score, then canonical play order.
 pub struct GenMovesParams<'a> {
     pub board_snapshot: &'a BoardSnapshot<'a>,
     pub rack: &'a [u8],
     pub max_gen: usize,
     pub always_include_pass: bool,
+    pub num_tiles_in_bag: Option<i16>, // None to infer from board and rack sizes
+    pub constraints: Option<&'a Constraints>,
 }
 
-// KurniaMoveGenerator can only be reused for the same game_config and kwg.
-// (Refer to note at WorkingBuffer.)
-// This is not enforced.
-pub struct KurniaMoveGenerator {
-    working_buffer: WorkingBuffer,
-    pub plays: Vec<ValuedMove>,
+// Restricts place moves, pruning the search instead of filtering afterwards.
+// Exchanges are only generated when the constraints just close a lane.
+#[derive(Clone, Default)]
+pub struct Constraints {
+    pub use_all_tiles: bool,
+    pub cover_square: Option<(i8, i8)>, // (row, col) must be in the main word
+    pub hit_tws: bool,                  // must place a tile on a 3x (or more) word square
+    pub min_word_len: i8,               // of the main word
+    pub closed_lane: Option<(bool, i8)>, // (down, lane) gets no tile on or next to it
 }
 
-impl Clone for KurniaMoveGenerator {
-    #[inline(always)]
-    fn clone(&self) -> Self {
-        Self {
-            working_buffer: self.working_buffer.clone(),
-            plays: self.plays.clone(),
-        }
+impl Constraints {
+    // whether an exchange satisfies the constraints, as it places no tiles.
+    pub fn allows_exchanges(&self) -> bool {
+        !self.use_all_tiles
+            && self.cover_square.is_none()
+            && !self.hit_tws
+            && self.min_word_len <= 0
     }
 
-    #[inline(always)]
-    fn clone_from(&mut self, source: &Self) {
-        self.working_buffer.clone_from(&source.working_buffer);
-        self.plays.clone_from(&source.plays);
+    // None if no play along this strip can satisfy the constraints.
+    fn for_strip(
+        &self,
+        down: bool,
+        lane: i8,
+        remaining_word_multipliers_strip: &[i8],
+        num_tiles_on_rack: u8,
+    ) -> Option<StripConstraints> {
+        let cover_idx = match self.cover_square {
+            Some((row, col)) => {
+                let (cover_lane, cover_idx) = if down { (col, row) } else { (row, col) };
+                if cover_lane != lane {
+                    return None;
+                }
+                cover_idx
+            }
+            None => -1,
+        };
+        let strip_constraints = StripConstraints {
+            num_played: if self.use_all_tiles {
+                num_tiles_on_rack as i8
+            } else {
+                0
+            },
+            cover_idx,
+            hit_tws: self.hit_tws,
+            min_word_len: self.min_word_len,
+        };
+        if strip_constraints.may_fit(
+            0,
+            remaining_word_multipliers_strip.len() as i8,
+            remaining_word_multipliers_strip,
+        ) {
+            Some(strip_constraints)
+        } else {
+            None
+        }
     }
 }
 
-impl KurniaMoveGenerator {
-    pub fn new(game_config: &game_config::GameConfig) -> Self {
+// what a StaticEvaluator can see besides the play itself.
+pub struct StaticEvalContext<'a> {
+    pub board_snapshot: &'a BoardSnapshot<'a>,
+    pub unseen_tally: &'a [u8], // opponent racks and bag, indexed like rack tally
+    pub num_tiles_on_board: u16,
+    pub num_tiles_on_rack: u8,
+    pub num_tiles_in_bag: i16, // negative when players also have less than full racks
+    pub play_out_bonus: i16,   // only meaningful when num_tiles_in_bag <= 0
+}
+
+// Assigns equity to plays during move generation.
+// leave_value is also used to bound the equity of unexplored placements,
+// so place_equity must not exceed score + leave_value for the same leave.
+// exchange_equity defaults to leave_value. Exchanges put tiles back instead of
+// drawing, so override it if leave_value depends on what the play draws.
+pub trait StaticEvaluator {
+    fn leave_value(&self, eval_context: &StaticEvalContext<'_>, leave_tally: &[u8]) -> f32;
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline(always)]
+    fn place_equity(
+        &self,
+        eval_context: &StaticEvalContext<'_>,
+        down: bool,
+        _lane: i8,
+        idx: i8,
+        word: &[u8],
+        score: i16,
+        leave_tally: &[u8],
+    ) -> f32 {
+        score as f32
+            + self.leave_value(eval_context, leave_tally)
+            + first_move_vowel_penalty(eval_context, down, idx, word)
+    }
+
+    #[inline(always)]
+    fn exchange_equity(
+        &self,
+        eval_context: &StaticEvalContext<'_>,
+        _exchanged_tiles: &[u8],
+        leave_tally: &[u8],
+    ) -> f32 {
+        self.leave_value(eval_context, leave_tally)
+    }
+}
+
+// penalize vowels next to premium squares on an empty board.
+pub fn first_move_vowel_penalty(
+    eval_context: &StaticEvalContext<'_>,
+    down: bool,
+    idx: i8,
+    word: &[u8],
+) -> f32 {
+    if eval_context.num_tiles_on_board != 0 {
+        return 0.0;
+    }
+    let alphabet = eval_context.board_snapshot.game_config.alphabet();
+    let board_layout = eval_context.board_snapshot.game_config.board_layout();
+    (idx..)
+        .zip(word)
+        .filter(|(i, &tile)| {
+            tile != 0
+                && alphabet.is_vowel(tile)
+                && if down {
+                    board_layout.danger_star_down(*i)
+                } else {
+                    board_layout.danger_star_across(*i)
+                }
+        })
+        .count() as f32
+        * -0.7
+}
+
+// The default evaluator switches on the number of tiles in the bag.
+// - empty bag: leaves are worth nothing, going out earns twice the
+//   opponent's rack, otherwise the kept tiles are a liability.
+// - the placement draws the last tiles: the opponent may go out first, so
+//   the kept and expected drawn tiles are a liability, plus bag_emptying_bonus.
+// - otherwise: the klv, scaled by leave_scale.
+// Exchanges never draw the bag out, so they use the klv unless the bag is
+// empty, where the only exchange is a pass.
+pub struct KlvEvaluator {
+    pub leave_scale: f32, // must be between 0.0 and 1.0
+    pub bag_emptying_bonus: f32,
+}
+
+impl Default for KlvEvaluator {
+    #[inline(always)]
+    fn default() -> Self {
+        Self {
+            leave_scale: 1.0,
+            bag_emptying_bonus: 0.0,
+        }
+    }
+}
+
+impl StaticEvaluator for KlvEvaluator {
+    #[inline(always)]
+    fn leave_value(&self, eval_context: &StaticEvalContext<'_>, leave_tally: &[u8]) -> f32 {
+        let alphabet = eval_context.board_snapshot.game_config.alphabet();
+        if eval_context.num_tiles_in_bag <= 0 {
+            let played_out = leave_tally.iter().all(|&num| num == 0);
+            (if played_out {
+                eval_context.play_out_bonus
+            } else {
+                -10 - 2 * alphabet.rack_tally_score(leave_tally)
+            }) as f32
+        } else {
+            let num_kept = leave_tally.iter().map(|&num| num as i16).sum::<i16>();
+            let num_played = eval_context.num_tiles_on_rack as i16 - num_kept;
+            if num_played >= eval_context.num_tiles_in_bag {
+                let (num_unseen, unseen_score) = (0u8..).zip(eval_context.unseen_tally).fold(
+                    (0i16, 0i16),
+                    |(n, sc), (tile, &num)| {
+                        (
+                            n + num as i16,
+                            sc + num as i16 * alphabet.score(tile) as i16,
+                        )
+                    },
+                );
+                let expected_drawn_score = if num_unseen > 0 {
+                    eval_context.num_tiles_in_bag as f32 * unseen_score as f32 / num_unseen as f32
+                } else {
+                    0.0
+                };
+                self.bag_emptying_bonus
+                    - alphabet.rack_tally_score(leave_tally) as f32
+                    - expected_drawn_score
+            } else {
+                self.klv_leave_value(eval_context, leave_tally)
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn exchange_equity(
+        &self,
+        eval_context: &StaticEvalContext<'_>,
+        _exchanged_tiles: &[u8],
+        leave_tally: &[u8],
+    ) -> f32 {
+        if eval_context.num_tiles_in_bag <= 0 {
+            self.leave_value(eval_context, leave_tally)
+        } else {
+            self.klv_leave_value(eval_context, leave_tally)
+        }
+    }
+}
+
+impl KlvEvaluator {
+    #[inline(always)]
+    fn klv_leave_value(&self, eval_context: &StaticEvalContext<'_>, leave_tally: &[u8]) -> f32 {
+        self.leave_scale
+            * eval_context
+                .board_snapshot
+                .klv
+                .leave_value_from_tally(leave_tally, eval_context.num_tiles_in_bag)
+    }
+}
+
+// Cross sets need one bit per tile, including the blank.
+enum AnyWorkingBuffer {
+    Bits64(WorkingBuffer<u64>),
+    Bits128(WorkingBuffer<u128>),
+}
+
+impl Clone for AnyWorkingBuffer {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        match self {
+            AnyWorkingBuffer::Bits64(x) => AnyWorkingBuffer::Bits64(x.clone()),
+            AnyWorkingBuffer::Bits128(x) => AnyWorkingBuffer::Bits128(x.clone()),
+        }
+    }
+
+    #[inline(always)]
+    fn clone_from(&mut self, source: &Self) {
+        match (self, source) {
+            (AnyWorkingBuffer::Bits64(x), AnyWorkingBuffer::Bits64(y)) => x.clone_from(y),
+            (AnyWorkingBuffer::Bits128(x), AnyWorkingBuffer::Bits128(y)) => x.clone_from(y),
+            (x, y) => *x = y.clone(),
+        }
+    }
+}
+
+impl AnyWorkingBuffer {
+    fn new(game_config: &game_config::GameConfig) -> Self {
+        let alphabet_len = game_config.alphabet().len() as usize;
+        if alphabet_len <= <u64 as bits::Bits>::NUM_BITS {
+            AnyWorkingBuffer::Bits64(WorkingBuffer::new(game_config))
+        } else if alphabet_len <= <u128 as bits::Bits>::NUM_BITS {
+            AnyWorkingBuffer::Bits128(WorkingBuffer::new(game_config))
+        } else {
+            panic!("too many tiles in alphabet");
+        }
+    }
+}
+
+// This is not a closure, the body is expanded once for each bitset width.
+macro_rules! with_working_buffer {
+    ($any_working_buffer:expr, |$working_buffer:ident| $body:expr) => {
+        match $any_working_buffer {
+            AnyWorkingBuffer::Bits64($working_buffer) => $body,
+            AnyWorkingBuffer::Bits128($working_buffer) => $body,
+        }
+    };
+}
+
+// KurniaMoveGenerator can only be reused for the same game_config and kwg.
+// (Refer to note at WorkingBuffer.)
+// This is not enforced.
+pub struct KurniaMoveGenerator {
+    working_buffer: AnyWorkingBuffer,
+    pub plays: Vec<ValuedMove>,
+}
+
+impl Clone for KurniaMoveGenerator {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self {
+            working_buffer: self.working_buffer.clone(),
+            plays: self.plays.clone(),
+        }
+    }
+
+    #[inline(always)]
+    fn clone_from(&mut self, source: &Self) {
+        self.working_buffer.clone_from(&source.working_buffer);
+        self.plays.clone_from(&source.plays);
+    }
+}
+
+impl KurniaMoveGenerator {
+    pub fn new(game_config: &game_config::GameConfig) -> Self {
         Self {
-            working_buffer: WorkingBuffer::new(game_config),
+            working_buffer: AnyWorkingBuffer::new(game_config),
             plays: Vec::new(),
         }
     }
 
+    // In incremental mode the cross sets and perpendicular scores are kept
+    // between generations, and only lanes touched by plays reported through
+    // note_play are recomputed.
+    // The caller must report every play made on the board since the last
+    // generation, or call invalidate_cross_sets before using another board.
+    // Taking tiles back off the board is reported by noting the same play again.
+    // Anchors and shadow bounds are still found on every lane, as they depend
+    // on the rack, which usually changes between generations.
+    pub fn set_incremental(&mut self, incremental: bool) {
+        with_working_buffer!(&mut self.working_buffer, |working_buffer| {
+            working_buffer.incremental = incremental;
+            working_buffer.cross_sets_valid = false;
+        });
+    }
+
+    // Panics if an incremental update differs from a full recompute.
+    // This repeats the full recompute, so it is only for testing.
+    pub fn set_verify_incremental(&mut self, verify_incremental: bool) {
+        with_working_buffer!(&mut self.working_buffer, |working_buffer| {
+            working_buffer.verify_incremental = verify_incremental;
+        });
+    }
+
+    // Looks up arc lists of kwg as tile sets instead of walking them, this
+    // speeds up classic cross sets at the cost of two bitsets per kwg node.
+    // Clones share the table.
+    pub fn build_letter_sets(&mut self, kwg: &kwg::Kwg) {
+        with_working_buffer!(&mut self.working_buffer, |working_buffer| {
+            working_buffer.letter_sets =
+                Some(std::sync::Arc::new(letter_sets::LetterSets::new(kwg)));
+        });
+    }
+
+    pub fn invalidate_cross_sets(&mut self) {
+        with_working_buffer!(&mut self.working_buffer, |working_buffer| {
+            working_buffer.cross_sets_valid = false;
+        });
+    }
+
+    pub fn note_play(&mut self, play: &Play) {
+        if let Play::Place {
+            down,
+            lane,
+            idx,
+            word,
+            ..
+        } = play
+        {
+            with_working_buffer!(&mut self.working_buffer, |working_buffer| {
+                let (dirty_for_placed, dirty_for_lane) = if *down {
+                    (
+                        &mut working_buffer.dirty_rows,
+                        &mut working_buffer.dirty_cols,
+                    )
+                } else {
+                    (
+                        &mut working_buffer.dirty_cols,
+                        &mut working_buffer.dirty_rows,
+                    )
+                };
+                dirty_for_lane[*lane as usize] = true;
+                for (i, &tile) in (*idx..).zip(word.iter()) {
+                    if tile != 0 {
+                        dirty_for_placed[i as usize] = true;
+                    }
+                }
+            });
+        }
+    }
+
     // skip equity computation and sorting
     #[allow(dead_code)]
     pub fn gen_all_raw_moves_unsorted<'a>(
@@ -2156,63 +2992,103 @@ impl KurniaMoveGenerator {
 
         let vec_moves = std::cell::RefCell::new(std::mem::take(&mut self.plays));
 
-        let mut working_buffer = &mut self.working_buffer;
-        working_buffer.init(board_snapshot, rack, &|leave_value: f32| leave_value);
+        with_working_buffer!(&mut self.working_buffer, |working_buffer| {
+            working_buffer.init(board_snapshot, rack, None, &KlvEvaluator::default());
 
-        let found_place_move =
-            |down: bool, lane: i8, idx: i8, word: &[u8], score: i16, _rack_tally: &[u8]| {
+            let found_place_move =
+                |down: bool, lane: i8, idx: i8, word: &[u8], score: i16, _rack_tally: &[u8]| {
+                    vec_moves.borrow_mut().push(ValuedMove {
+                        equity: 0.0,
+                        play: Play::Place {
+                            down,
+                            lane,
+                            idx,
+                            word: word.into(),
+                            score,
+                        },
+                    });
+                };
+
+            let found_exchange_move = |_rack_tally: &[u8], exchanged_tiles: &[u8]| {
                 vec_moves.borrow_mut().push(ValuedMove {
                     equity: 0.0,
-                    play: Play::Place {
-                        down,
-                        lane,
-                        idx,
-                        word: word.into(),
-                        score,
+                    play: Play::Exchange {
+                        tiles: exchanged_tiles.into(),
                     },
                 });
             };
 
-        let found_exchange_move = |_rack_tally: &[u8], exchanged_tiles: &[u8]| {
-            vec_moves.borrow_mut().push(ValuedMove {
-                equity: 0.0,
-                play: Play::Exchange {
-                    tiles: exchanged_tiles.into(),
-                },
-            });
-        };
-
-        kurnia_gen_place_moves_iter(
-            true,
-            board_snapshot,
-            &mut working_buffer,
-            found_place_move,
-            |_best_possible_equity: f32| true,
-        )
-        .for_each(|_| ());
-        kurnia_gen_nonplace_moves_except_pass(
-            board_snapshot,
-            &mut working_buffer,
-            found_exchange_move,
-        );
-        if always_include_pass || vec_moves.borrow().is_empty() {
-            found_exchange_move(&working_buffer.rack_tally, &working_buffer.exchange_buffer);
-        }
+            kurnia_gen_place_moves_iter(
+                true,
+                board_snapshot,
+                working_buffer,
+                None,
+                found_place_move,
+                |_best_possible_equity: f32| true,
+            )
+            .for_each(|_| ());
+            kurnia_gen_nonplace_moves_except_pass(
+                board_snapshot,
+                working_buffer,
+                found_exchange_move,
+            );
+            if always_include_pass || vec_moves.borrow().is_empty() {
+                found_exchange_move(&working_buffer.rack_tally, &working_buffer.exchange_buffer);
+            }
+        });
 
         self.plays = vec_moves.into_inner();
     }
 
+    // Streams every legal placement to found_place_move as
+    // (down, lane, idx, word, score, leave tally) without allocating plays.
+    // The word and leave tally are only valid during the call.
+    // found_place_move returns false to stop early.
+    // Returns false iff stopped early.
+    pub fn gen_place_moves_raw<
+        'a,
+        FoundPlaceMove: FnMut(bool, i8, i8, &[u8], i16, &[u8]) -> bool,
+    >(
+        &mut self,
+        board_snapshot: &'a BoardSnapshot<'a>,
+        rack: &'a [u8],
+        mut found_place_move: FoundPlaceMove,
+    ) -> bool {
+        let stopped = std::cell::Cell::new(false);
+
+        with_working_buffer!(&mut self.working_buffer, |working_buffer| {
+            working_buffer.init(board_snapshot, rack, None, &KlvEvaluator::default());
+
+            kurnia_gen_place_moves_iter(
+                true,
+                board_snapshot,
+                working_buffer,
+                None,
+                |down: bool, lane: i8, idx: i8, word: &[u8], score: i16, rack_tally: &[u8]| {
+                    // the rest of the current placement is skipped.
+                    if !stopped.get() && !found_place_move(down, lane, idx, word, score, rack_tally)
+                    {
+                        stopped.set(true);
+                    }
+                },
+                |_best_possible_equity: f32| !stopped.get(),
+            )
+            .for_each(|_| ());
+        });
+        !stopped.get()
+    }
+
     pub async fn async_gen_moves_filtered<
         'a,
         PlaceMovePredicate: FnMut(bool, i8, i8, &[u8], i16, &[u8]) -> bool,
-        AdjustLeaveValue: Fn(f32) -> f32,
+        Evaluator: StaticEvaluator + ?Sized,
         EquityPredicate: FnMut(f32, &Play) -> bool,
         BreatheFuture: std::future::Future,
     >(
         &mut self,
         params: &'a GenMovesParams<'a>,
         mut place_move_predicate: PlaceMovePredicate,
-        adjust_leave_value: AdjustLeaveValue,
+        evaluator: &Evaluator,
         equity_predicate: EquityPredicate,
         mut breathe: impl FnMut() -> BreatheFuture,
     ) {
@@ -2221,9 +3097,6 @@ impl KurniaMoveGenerator {
             return;
         }
 
-        let alphabet = params.board_snapshot.game_config.alphabet();
-        let board_layout = params.board_snapshot.game_config.board_layout();
-
         let found_moves = std::cell::RefCell::new(std::collections::BinaryHeap::from(
             std::mem::take(&mut self.plays),
         ));
@@ -2238,111 +3111,116 @@ impl KurniaMoveGenerator {
             mut construct_play: F,
         ) {
             let mut borrowed = found_moves.borrow_mut();
-            if borrowed.len() >= max_gen && borrowed.peek().unwrap().equity >= equity {
+            if borrowed.len() >= max_gen
+                && borrowed.peek().unwrap().equity.total_cmp(&equity).is_gt()
+            {
+                return;
+            }
+            // ties on equity are broken by the rest of the order.
+            let valued_move = ValuedMove {
+                equity,
+                play: construct_play(),
+            };
+            if borrowed.len() >= max_gen && *borrowed.peek().unwrap() <= valued_move {
                 return;
             }
-            let play = construct_play();
-            if equity_pred.borrow_mut()(equity, &play) {
+            if equity_pred.borrow_mut()(equity, &valued_move.play) {
                 if borrowed.len() >= max_gen {
                     borrowed.pop();
                 }
-                borrowed.push(ValuedMove { equity, play });
+                borrowed.push(valued_move);
             }
         }
 
-        let mut working_buffer = &mut self.working_buffer;
-        working_buffer.init(params.board_snapshot, params.rack, &adjust_leave_value);
-        let num_tiles_on_board = working_buffer.num_tiles_on_board;
-        let num_tiles_in_bag = working_buffer.num_tiles_in_bag;
-        let play_out_bonus = working_buffer.play_out_bonus;
-
-        let leave_value_from_tally = |rack_tally: &[u8]| {
-            if num_tiles_in_bag <= 0 {
-                let played_out = rack_tally.iter().all(|&num| num == 0);
-                (if played_out {
-                    play_out_bonus
-                } else {
-                    -10 - 2
-                        * (0u8..)
-                            .zip(rack_tally)
-                            .map(|(tile, num)| *num as i16 * alphabet.score(tile) as i16)
-                            .sum::<i16>()
-                }) as f32
-            } else {
-                // note: adjust_leave_value(f) must return between 0.0 and f
-                adjust_leave_value(params.board_snapshot.klv.leave_value_from_tally(rack_tally))
-            }
-        };
+        with_working_buffer!(&mut self.working_buffer, |working_buffer| {
+            working_buffer.init(
+                params.board_snapshot,
+                params.rack,
+                params.num_tiles_in_bag,
+                evaluator,
+            );
+            let unseen_tally = std::mem::take(&mut working_buffer.unseen_tally);
+            let eval_context = StaticEvalContext {
+                board_snapshot: params.board_snapshot,
+                unseen_tally: &unseen_tally,
+                num_tiles_on_board: working_buffer.num_tiles_on_board,
+                num_tiles_on_rack: working_buffer.num_tiles_on_rack,
+                num_tiles_in_bag: working_buffer.num_tiles_in_bag,
+                play_out_bonus: working_buffer.play_out_bonus,
+            };
 
-        let found_place_move =
-            |down: bool, lane: i8, idx: i8, word: &[u8], score: i16, rack_tally: &[u8]| {
-                if place_move_predicate(down, lane, idx, word, score, rack_tally) {
-                    let leave_value = leave_value_from_tally(rack_tally);
-                    let other_adjustments = if num_tiles_on_board == 0 {
-                        (idx..)
-                            .zip(word)
-                            .filter(|(i, &tile)| {
-                                tile != 0
-                                    && alphabet.is_vowel(tile)
-                                    && if down {
-                                        board_layout.danger_star_down(*i)
-                                    } else {
-                                        board_layout.danger_star_across(*i)
-                                    }
-                            })
-                            .count() as f32
-                            * -0.7
-                    } else {
-                        0.0
-                    };
-                    let equity = score as f32 + leave_value + other_adjustments;
-                    push_move(&found_moves, &equity_pred, params.max_gen, equity, || {
-                        Play::Place {
+            let found_place_move =
+                |down: bool, lane: i8, idx: i8, word: &[u8], score: i16, rack_tally: &[u8]| {
+                    if place_move_predicate(down, lane, idx, word, score, rack_tally) {
+                        let equity = evaluator.place_equity(
+                            &eval_context,
                             down,
                             lane,
                             idx,
-                            word: word.into(),
+                            word,
                             score,
-                        }
-                    });
-                }
-            };
+                            rack_tally,
+                        );
+                        push_move(&found_moves, &equity_pred, params.max_gen, equity, || {
+                            Play::Place {
+                                down,
+                                lane,
+                                idx,
+                                word: word.into(),
+                                score,
+                            }
+                        });
+                    }
+                };
 
-        let found_exchange_move = |rack_tally: &[u8], exchanged_tiles: &[u8]| {
-            push_move(
-                &found_moves,
-                &equity_pred,
-                params.max_gen,
-                leave_value_from_tally(rack_tally),
-                || Play::Exchange {
-                    tiles: exchanged_tiles.into(),
-                },
-            );
-        };
+            let found_exchange_move = |rack_tally: &[u8], exchanged_tiles: &[u8]| {
+                push_move(
+                    &found_moves,
+                    &equity_pred,
+                    params.max_gen,
+                    evaluator.exchange_equity(&eval_context, exchanged_tiles, rack_tally),
+                    || Play::Exchange {
+                        tiles: exchanged_tiles.into(),
+                    },
+                );
+            };
 
-        let can_accept = |best_possible_equity: f32| {
-            let borrowed = found_moves.borrow();
-            return !(borrowed.len() >= params.max_gen
-                && borrowed.peek().unwrap().equity >= best_possible_equity);
-        };
+            let can_accept = |best_possible_equity: f32| {
+                let borrowed = found_moves.borrow();
+                return !(borrowed.len() >= params.max_gen
+                    && borrowed
+                        .peek()
+                        .unwrap()
+                        .equity
+                        .total_cmp(&best_possible_equity)
+                        .is_gt());
+            };
 
-        for _ in kurnia_gen_place_moves_iter(
-            false,
-            params.board_snapshot,
-            &mut working_buffer,
-            found_place_move,
-            can_accept,
-        ) {
-            breathe().await;
-        }
-        kurnia_gen_nonplace_moves_except_pass(
-            params.board_snapshot,
-            &mut working_buffer,
-            found_exchange_move,
-        );
-        if params.always_include_pass || found_moves.borrow().is_empty() {
-            found_exchange_move(&working_buffer.rack_tally, &working_buffer.exchange_buffer);
-        }
+            for _ in kurnia_gen_place_moves_iter(
+                false,
+                params.board_snapshot,
+                working_buffer,
+                params.constraints,
+                found_place_move,
+                can_accept,
+            ) {
+                breathe().await;
+            }
+            if match params.constraints {
+                Some(constraints) => constraints.allows_exchanges(),
+                None => true,
+            } {
+                kurnia_gen_nonplace_moves_except_pass(
+                    params.board_snapshot,
+                    working_buffer,
+                    found_exchange_move,
+                );
+            }
+            if params.always_include_pass || found_moves.borrow().is_empty() {
+                found_exchange_move(&working_buffer.rack_tally, &working_buffer.exchange_buffer);
+            }
+            working_buffer.unseen_tally = unseen_tally;
+        });
 
         self.plays = found_moves.into_inner().into_vec();
         self.plays.sort_unstable();
@@ -2351,13 +3229,13 @@ impl KurniaMoveGenerator {
     pub fn gen_moves_filtered<
         'a,
         PlaceMovePredicate: FnMut(bool, i8, i8, &[u8], i16, &[u8]) -> bool,
-        AdjustLeaveValue: Fn(f32) -> f32,
+        Evaluator: StaticEvaluator + ?Sized,
         EquityPredicate: FnMut(f32, &Play) -> bool,
     >(
         &mut self,
         params: &'a GenMovesParams<'a>,
         mut place_move_predicate: PlaceMovePredicate,
-        adjust_leave_value: AdjustLeaveValue,
+        evaluator: &Evaluator,
         equity_predicate: EquityPredicate,
     ) {
         self.plays.clear();
@@ -2365,9 +3243,6 @@ impl KurniaMoveGenerator {
             return;
         }
 
-        let alphabet = params.board_snapshot.game_config.alphabet();
-        let board_layout = params.board_snapshot.game_config.board_layout();
-
         let found_moves = std::cell::RefCell::new(std::collections::BinaryHeap::from(
             std::mem::take(&mut self.plays),
         ));
@@ -2382,110 +3257,115 @@ impl KurniaMoveGenerator {
             mut construct_play: F,
         ) {
             let mut borrowed = found_moves.borrow_mut();
-            if borrowed.len() >= max_gen && borrowed.peek().unwrap().equity >= equity {
+            if borrowed.len() >= max_gen
+                && borrowed.peek().unwrap().equity.total_cmp(&equity).is_gt()
+            {
                 return;
             }
-            let play = construct_play();
-            if equity_pred.borrow_mut()(equity, &play) {
+            // ties on equity are broken by the rest of the order.
+            let valued_move = ValuedMove {
+                equity,
+                play: construct_play(),
+            };
+            if borrowed.len() >= max_gen && *borrowed.peek().unwrap() <= valued_move {
+                return;
+            }
+            if equity_pred.borrow_mut()(equity, &valued_move.play) {
                 if borrowed.len() >= max_gen {
                     borrowed.pop();
                 }
-                borrowed.push(ValuedMove { equity, play });
+                borrowed.push(valued_move);
             }
         }
 
-        let mut working_buffer = &mut self.working_buffer;
-        working_buffer.init(params.board_snapshot, params.rack, &adjust_leave_value);
-        let num_tiles_on_board = working_buffer.num_tiles_on_board;
-        let num_tiles_in_bag = working_buffer.num_tiles_in_bag;
-        let play_out_bonus = working_buffer.play_out_bonus;
-
-        let leave_value_from_tally = |rack_tally: &[u8]| {
-            if num_tiles_in_bag <= 0 {
-                let played_out = rack_tally.iter().all(|&num| num == 0);
-                (if played_out {
-                    play_out_bonus
-                } else {
-                    -10 - 2
-                        * (0u8..)
-                            .zip(rack_tally)
-                            .map(|(tile, num)| *num as i16 * alphabet.score(tile) as i16)
-                            .sum::<i16>()
-                }) as f32
-            } else {
-                // note: adjust_leave_value(f) must return between 0.0 and f
-                adjust_leave_value(params.board_snapshot.klv.leave_value_from_tally(rack_tally))
-            }
-        };
+        with_working_buffer!(&mut self.working_buffer, |working_buffer| {
+            working_buffer.init(
+                params.board_snapshot,
+                params.rack,
+                params.num_tiles_in_bag,
+                evaluator,
+            );
+            let unseen_tally = std::mem::take(&mut working_buffer.unseen_tally);
+            let eval_context = StaticEvalContext {
+                board_snapshot: params.board_snapshot,
+                unseen_tally: &unseen_tally,
+                num_tiles_on_board: working_buffer.num_tiles_on_board,
+                num_tiles_on_rack: working_buffer.num_tiles_on_rack,
+                num_tiles_in_bag: working_buffer.num_tiles_in_bag,
+                play_out_bonus: working_buffer.play_out_bonus,
+            };
 
-        let found_place_move =
-            |down: bool, lane: i8, idx: i8, word: &[u8], score: i16, rack_tally: &[u8]| {
-                if place_move_predicate(down, lane, idx, word, score, rack_tally) {
-                    let leave_value = leave_value_from_tally(rack_tally);
-                    let other_adjustments = if num_tiles_on_board == 0 {
-                        (idx..)
-                            .zip(word)
-                            .filter(|(i, &tile)| {
-                                tile != 0
-                                    && alphabet.is_vowel(tile)
-                                    && if down {
-                                        board_layout.danger_star_down(*i)
-                                    } else {
-                                        board_layout.danger_star_across(*i)
-                                    }
-                            })
-                            .count() as f32
-                            * -0.7
-                    } else {
-                        0.0
-                    };
-                    let equity = score as f32 + leave_value + other_adjustments;
-                    push_move(&found_moves, &equity_pred, params.max_gen, equity, || {
-                        Play::Place {
+            let found_place_move =
+                |down: bool, lane: i8, idx: i8, word: &[u8], score: i16, rack_tally: &[u8]| {
+                    if place_move_predicate(down, lane, idx, word, score, rack_tally) {
+                        let equity = evaluator.place_equity(
+                            &eval_context,
                             down,
                             lane,
                             idx,
-                            word: word.into(),
+                            word,
                             score,
-                        }
-                    });
-                }
-            };
+                            rack_tally,
+                        );
+                        push_move(&found_moves, &equity_pred, params.max_gen, equity, || {
+                            Play::Place {
+                                down,
+                                lane,
+                                idx,
+                                word: word.into(),
+                                score,
+                            }
+                        });
+                    }
+                };
 
-        let found_exchange_move = |rack_tally: &[u8], exchanged_tiles: &[u8]| {
-            push_move(
-                &found_moves,
-                &equity_pred,
-                params.max_gen,
-                leave_value_from_tally(rack_tally),
-                || Play::Exchange {
-                    tiles: exchanged_tiles.into(),
-                },
-            );
-        };
+            let found_exchange_move = |rack_tally: &[u8], exchanged_tiles: &[u8]| {
+                push_move(
+                    &found_moves,
+                    &equity_pred,
+                    params.max_gen,
+                    evaluator.exchange_equity(&eval_context, exchanged_tiles, rack_tally),
+                    || Play::Exchange {
+                        tiles: exchanged_tiles.into(),
+                    },
+                );
+            };
 
-        let can_accept = |best_possible_equity: f32| {
-            let borrowed = found_moves.borrow();
-            return !(borrowed.len() >= params.max_gen
-                && borrowed.peek().unwrap().equity >= best_possible_equity);
-        };
+            let can_accept = |best_possible_equity: f32| {
+                let borrowed = found_moves.borrow();
+                return !(borrowed.len() >= params.max_gen
+                    && borrowed
+                        .peek()
+                        .unwrap()
+                        .equity
+                        .total_cmp(&best_possible_equity)
+                        .is_gt());
+            };
 
-        kurnia_gen_place_moves_iter(
-            false,
-            params.board_snapshot,
-            &mut working_buffer,
-            found_place_move,
-            can_accept,
-        )
-        .for_each(|_| ());
-        kurnia_gen_nonplace_moves_except_pass(
-            params.board_snapshot,
-            &mut working_buffer,
-            found_exchange_move,
-        );
-        if params.always_include_pass || found_moves.borrow().is_empty() {
-            found_exchange_move(&working_buffer.rack_tally, &working_buffer.exchange_buffer);
-        }
+            kurnia_gen_place_moves_iter(
+                false,
+                params.board_snapshot,
+                working_buffer,
+                params.constraints,
+                found_place_move,
+                can_accept,
+            )
+            .for_each(|_| ());
+            if match params.constraints {
+                Some(constraints) => constraints.allows_exchanges(),
+                None => true,
+            } {
+                kurnia_gen_nonplace_moves_except_pass(
+                    params.board_snapshot,
+                    working_buffer,
+                    found_exchange_move,
+                );
+            }
+            if params.always_include_pass || found_moves.borrow().is_empty() {
+                found_exchange_move(&working_buffer.rack_tally, &working_buffer.exchange_buffer);
+            }
+            working_buffer.unseen_tally = unseen_tally;
+        });
 
         self.plays = found_moves.into_inner().into_vec();
         self.plays.sort_unstable();
@@ -2496,15 +3376,19 @@ impl KurniaMoveGenerator {
         self.gen_moves_filtered(
             params,
             |_down: bool, _lane: i8, _idx: i8, _word: &[u8], _score: i16, _rack_tally: &[u8]| true,
-            |leave_value: f32| leave_value,
+            &KlvEvaluator::default(),
             |_equity: f32, _play: &Play| true,
         );
     }
 }
 
-fn kurnia_gen_nonplace_moves_except_pass<'a, FoundExchangeMove: FnMut(&[u8], &[u8])>(
+fn kurnia_gen_nonplace_moves_except_pass<
+    'a,
+    B: bits::Bits,
+    FoundExchangeMove: FnMut(&[u8], &[u8]),
+>(
     board_snapshot: &'a BoardSnapshot<'a>,
-    working_buffer: &mut WorkingBuffer,
+    working_buffer: &mut WorkingBuffer<B>,
     found_exchange_move: FoundExchangeMove,
 ) {
     working_buffer.exchange_buffer.clear(); // should be no-op
@@ -2554,12 +3438,14 @@ fn kurnia_gen_nonplace_moves_except_pass<'a, FoundExchangeMove: FnMut(&[u8], &[u
 
 fn kurnia_gen_place_moves_iter<
     'a,
+    B: bits::Bits,
     FoundPlaceMove: 'a + FnMut(bool, i8, i8, &[u8], i16, &[u8]),
     CanAccept: 'a + Fn(f32) -> bool,
 >(
     want_raw: bool,
     board_snapshot: &'a BoardSnapshot<'a>,
-    working_buffer: &'a mut WorkingBuffer,
+    working_buffer: &'a mut WorkingBuffer<B>,
+    constraints: Option<&'a Constraints>,
     mut found_place_move: FoundPlaceMove,
     can_accept: CanAccept,
 ) -> impl 'a + Iterator {
@@ -2568,56 +3454,86 @@ fn kurnia_gen_place_moves_iter<
     let dim = board_layout.dim();
     let max_rack_size = game_config.rack_size() as u8;
 
-    // striped by row
-    for col in 0..dim.cols {
-        let strip_range_start = (col as isize * dim.rows as isize) as usize;
-        let strip_range_end = strip_range_start + dim.rows as usize;
-        gen_cross_set(
-            &board_snapshot,
-            &working_buffer.transposed_board_tiles[strip_range_start..strip_range_end],
-            &mut working_buffer.cross_set_for_across_plays,
-            dim.down(col),
-            &mut working_buffer.cross_set_buffer,
-            &mut working_buffer.cached_cross_set_for_across_plays
-                [strip_range_start..strip_range_end],
-            &mut working_buffer.used_letters_tally,
-        );
-    }
+    let mut only_dirty = working_buffer.update_cross_sets(board_snapshot);
     let transposed_dim = matrix::Dim {
         rows: dim.cols,
         cols: dim.rows,
     };
-    // striped by columns for better cache locality
-    for row in 0..dim.rows {
-        let strip_range_start = (row as isize * dim.cols as isize) as usize;
-        let strip_range_end = strip_range_start + dim.cols as usize;
-        gen_cross_set(
-            &board_snapshot,
-            &board_snapshot.board_tiles[strip_range_start..strip_range_end],
-            &mut working_buffer.cross_set_for_down_plays,
-            transposed_dim.down(row),
-            &mut working_buffer.cross_set_buffer,
-            &mut working_buffer.cached_cross_set_for_down_plays[strip_range_start..strip_range_end],
-            &mut working_buffer.used_letters_tally,
-        );
-    }
     if working_buffer.num_tiles_on_board == 0 {
-        // empty board activates star
-        let star_row = board_layout.star_row();
-        let star_col = board_layout.star_col();
-        if !board_layout.is_symmetric() {
-            working_buffer.cross_set_for_down_plays
-                [transposed_dim.at_row_col(star_col, star_row)] = CrossSet { bits: !1, score: 0 };
+        // empty board activates start squares, or every square if none.
+        // the next generation recomputes all cross sets to undo this.
+        working_buffer.cross_sets_valid = false;
+        only_dirty = false;
+        let mut activate = |row: i8, col: i8| {
+            if board_layout.is_blocked(row, col) {
+                return;
+            }
+            if !board_layout.is_symmetric() {
+                working_buffer.cross_set_for_down_plays[transposed_dim.at_row_col(col, row)] =
+                    CrossSet {
+                        bits: !B::ONE,
+                        score: 0,
+                    };
+            }
+            working_buffer.cross_set_for_across_plays[dim.at_row_col(row, col)] = CrossSet {
+                bits: !B::ONE,
+                score: 0,
+            };
+        };
+        let start_squares = board_layout.start_squares();
+        if start_squares.is_empty() {
+            for row in 0..dim.rows {
+                for col in 0..dim.cols {
+                    activate(row, col);
+                }
+            }
+        } else {
+            for &(row, col) in start_squares {
+                activate(row, col);
+            }
         }
-        working_buffer.cross_set_for_across_plays[dim.at_row_col(star_row, star_col)] =
-            CrossSet { bits: !1, score: 0 };
     }
-    working_buffer.init_after_cross_sets(board_snapshot);
+    if let Some(Constraints {
+        closed_lane: Some((down, lane)),
+        ..
+    }) = constraints
+    {
+        // no tile can be placed where the cross set is only bit 0.
+        working_buffer.cross_sets_valid = false;
+        only_dirty = false;
+        let num_lanes = if *down { dim.cols } else { dim.rows };
+        for lane in std::cmp::max(0, lane - 1)..std::cmp::min(num_lanes, lane + 2) {
+            let strider = dim.lane(*down, lane);
+            for i in 0..strider.len() {
+                let (row, col) = if *down { (i, lane) } else { (lane, i) };
+                if board_snapshot.board_tiles[dim.at_row_col(row, col)] == 0 {
+                    working_buffer.cross_set_for_across_plays[dim.at_row_col(row, col)].bits =
+                        B::ONE;
+                    working_buffer.cross_set_for_down_plays[transposed_dim.at_row_col(col, row)]
+                        .bits = B::ONE;
+                }
+            }
+        }
+    }
+    working_buffer.init_after_cross_sets(board_snapshot, only_dirty);
     let mut found_placements = std::mem::take(&mut working_buffer.found_placements);
     found_placements.clear();
     for row in 0..dim.rows {
         let strip_range_start = (row as isize * dim.cols as isize) as usize;
         let strip_range_end = strip_range_start + dim.cols as usize;
+        let strip_constraints = match constraints {
+            Some(constraints) => match constraints.for_strip(
+                false,
+                row,
+                &working_buffer.remaining_word_multipliers_for_across_plays
+                    [strip_range_start..strip_range_end],
+                working_buffer.num_tiles_on_rack,
+            ) {
+                Some(x) => Some(x),
+                None => continue,
+            },
+            None => None,
+        };
         gen_place_placements(
             want_raw,
             &mut GenPlacePlacementsParams {
@@ -2641,6 +3557,7 @@ fn kurnia_gen_place_moves_iter<
                     .indexes_to_descending_square_multiplier_buffer,
                 best_leave_values: &working_buffer.best_leave_values,
                 num_max_played: max_rack_size,
+                constraints: strip_constraints,
             },
             &working_buffer.remaining_tile_multipliers_for_across_plays
                 [strip_range_start..strip_range_end],
@@ -2663,6 +3580,19 @@ fn kurnia_gen_place_moves_iter<
     for col in 0..dim.cols {
         let strip_range_start = (col as isize * dim.rows as isize) as usize;
         let strip_range_end = strip_range_start + dim.rows as usize;
+        let strip_constraints = match constraints {
+            Some(constraints) => match constraints.for_strip(
+                true,
+                col,
+                &working_buffer.remaining_word_multipliers_for_down_plays
+                    [strip_range_start..strip_range_end],
+                working_buffer.num_tiles_on_rack,
+            ) {
+                Some(x) => Some(x),
+                None => continue,
+            },
+            None => None,
+        };
         gen_place_placements(
             want_raw,
             &mut GenPlacePlacementsParams {
@@ -2686,6 +3616,7 @@ fn kurnia_gen_place_moves_iter<
                     .indexes_to_descending_square_multiplier_buffer,
                 best_leave_values: &working_buffer.best_leave_values,
                 num_max_played: max_rack_size,
+                constraints: strip_constraints,
             },
             &working_buffer.remaining_tile_multipliers_for_down_plays
                 [strip_range_start..strip_range_end],
@@ -2707,11 +3638,8 @@ fn kurnia_gen_place_moves_iter<
     }
     if !want_raw {
         // this will be iterated in reverse order, so sort by best_possible_equity increasing.
-        found_placements.sort_unstable_by(|a, b| {
-            a.best_possible_equity
-                .partial_cmp(&b.best_possible_equity)
-                .unwrap()
-        });
+        found_placements
+            .sort_unstable_by(|a, b| a.best_possible_equity.total_cmp(&b.best_possible_equity));
     }
     working_buffer.found_placements = found_placements;
     std::iter::from_fn(move || match working_buffer.found_placements.pop() {
@@ -2722,6 +3650,7 @@ fn kurnia_gen_place_moves_iter<
                     working_buffer,
                     &placement,
                     max_rack_size,
+                    constraints,
                     &mut found_place_move,
                 );
                 Some(())
@@ -2734,3 +3663,292 @@ fn kurnia_gen_place_moves_iter<
         None => None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::{build, game_config};
+    use super::*;
+
+    fn make_kwg() -> kwg::Kwg {
+        let mut machine_words = "AA AB AD AE AI AR AS AT BA BE DA DE ED ER ES ET ID IS IT RE \
+            TA TI AID AIR AIT ARE ART ATE DIE EAR EAT ERA ETA IRE RAD RAT RED SAD SAT SEA SET \
+            SIR SIT TAD TAE TAR TEA TED TIE AIDE AIRS ARES ARTS DARE DATE DIET EARS EAST EATS \
+            EDIT IDEA RAID RATE READ REST RIDE RITE SAID SATE SEAT SIDE SITE STAR TEAR TEAS \
+            TIDE TIER TIRE ARISE ASIDE ASTER IRATE RAISE RATED RATES READS SATED SIRED STARE \
+            TEARS TIRED TRIED AIDERS ASTRIDE DIREST RAITAS SAETER TERAIS TIRADES"
+            .split_whitespace()
+            .map(|word| word.bytes().map(|c| c - b'A' + 1).collect::<Vec<u8>>()[..].into())
+            .collect::<Vec<bites::Bites>>();
+        machine_words.sort_unstable();
+        machine_words.dedup();
+        kwg::Kwg::from_bytes_alloc(
+            &build::build(build::BuildFormat::Gaddawg, &machine_words).unwrap(),
+        )
+    }
+
+    fn board_tiles_len(game_config: &game_config::GameConfig<'_>) -> usize {
+        let dim = game_config.board_layout().dim();
+        dim.rows as usize * dim.cols as usize
+    }
+
+    // RATES across from H6, EAT down from H9.
+    fn make_board_tiles(game_config: &game_config::GameConfig<'_>) -> Vec<u8> {
+        let dim = game_config.board_layout().dim();
+        let mut board_tiles = vec![0u8; board_tiles_len(game_config)];
+        for (col, &tile) in (5..).zip(&[18, 1, 20, 5, 19]) {
+            board_tiles[dim.at_row_col(7, col)] = tile;
+        }
+        for (row, &tile) in (8..).zip(&[1, 20]) {
+            board_tiles[dim.at_row_col(row, 8)] = tile;
+        }
+        board_tiles
+    }
+
+    fn gen_plays(
+        move_generator: &mut KurniaMoveGenerator,
+        board_snapshot: &BoardSnapshot<'_>,
+        rack: &[u8],
+        max_gen: usize,
+        constraints: Option<&Constraints>,
+    ) -> Vec<Play> {
+        move_generator.gen_moves_unfiltered(&GenMovesParams {
+            board_snapshot,
+            rack,
+            max_gen,
+            always_include_pass: false,
+            num_tiles_in_bag: None,
+            constraints,
+        });
+        move_generator
+            .plays
+            .iter()
+            .map(|valued_move| valued_move.play.clone())
+            .collect()
+    }
+
+    fn satisfies(
+        board_snapshot: &BoardSnapshot<'_>,
+        rack: &[u8],
+        constraints: &Constraints,
+        play: &Play,
+    ) -> bool {
+        match play {
+            Play::Exchange { .. } => constraints.allows_exchanges(),
+            Play::Place {
+                down,
+                lane,
+                idx,
+                word,
+                ..
+            } => {
+                let board_layout = board_snapshot.game_config.board_layout();
+                let dim = board_layout.dim();
+                let placed = (*idx..)
+                    .zip(word.iter())
+                    .filter(|(_, &tile)| tile != 0)
+                    .map(|(i, _)| if *down { (i, *lane) } else { (*lane, i) })
+                    .collect::<Vec<_>>();
+                let word_len = word.len() as i8;
+                (!constraints.use_all_tiles || placed.len() == rack.len())
+                    && match constraints.cover_square {
+                        Some((row, col)) => {
+                            let (cover_lane, cover_idx) =
+                                if *down { (col, row) } else { (row, col) };
+                            cover_lane == *lane && *idx <= cover_idx && cover_idx < idx + word_len
+                        }
+                        None => true,
+                    }
+                    && word_len >= constraints.min_word_len
+                    && (!constraints.hit_tws
+                        || placed.iter().any(|&(row, col)| {
+                            board_layout.premiums()[dim.at_row_col(row, col)].word_multiplier >= 3
+                        }))
+                    && match constraints.closed_lane {
+                        Some((closed_down, closed_lane)) => placed.iter().all(|&(row, col)| {
+                            ((if closed_down { col } else { row }) - closed_lane).abs() > 1
+                        }),
+                        None => true,
+                    }
+            }
+        }
+    }
+
+    #[test]
+    fn test_constraints_match_filter() {
+        let game_config = game_config::make_common_english_game_config();
+        let kwg = make_kwg();
+        let klv = klv::Klv::from_bytes_alloc(klv::EMPTY_KLV_BYTES).unwrap();
+        let board_tiles = make_board_tiles(&game_config);
+        let board_snapshot = BoardSnapshot {
+            board_tiles: &board_tiles,
+            game_config: &game_config,
+            kwg: &kwg,
+            klv: &klv,
+        };
+        let mut move_generator = KurniaMoveGenerator::new(&game_config);
+        let not_pass = |play: &Play| !matches!(play, Play::Exchange { tiles } if tiles.is_empty());
+        let all_constraints = [
+            // covering the R of RATES, or a square left of it.
+            Constraints {
+                cover_square: Some((7, 5)),
+                min_word_len: 7,
+                ..Default::default()
+            },
+            Constraints {
+                cover_square: Some((7, 2)),
+                ..Default::default()
+            },
+            // covering a square above or below EAT, or its A.
+            Constraints {
+                cover_square: Some((6, 8)),
+                ..Default::default()
+            },
+            Constraints {
+                cover_square: Some((10, 8)),
+                ..Default::default()
+            },
+            Constraints {
+                cover_square: Some((8, 8)),
+                min_word_len: 4,
+                ..Default::default()
+            },
+            Constraints {
+                use_all_tiles: true,
+                ..Default::default()
+            },
+            Constraints {
+                use_all_tiles: true,
+                cover_square: Some((7, 11)),
+                ..Default::default()
+            },
+            Constraints {
+                min_word_len: 6,
+                ..Default::default()
+            },
+            Constraints {
+                hit_tws: true,
+                ..Default::default()
+            },
+            Constraints {
+                closed_lane: Some((true, 8)),
+                ..Default::default()
+            },
+        ];
+        for rack in [&[1, 4, 5, 9, 18, 19, 20][..], &[0, 1, 5, 9, 20], &[0, 0, 4]] {
+            let unconstrained =
+                gen_plays(&mut move_generator, &board_snapshot, rack, usize::MAX, None);
+            for constraints in all_constraints.iter() {
+                let expected = unconstrained
+                    .iter()
+                    .filter(|&play| not_pass(play))
+                    .filter(|&play| satisfies(&board_snapshot, rack, constraints, play))
+                    .map(|play| play.fmt(&board_snapshot).to_string())
+                    .collect::<Vec<_>>();
+                let constrained = gen_plays(
+                    &mut move_generator,
+                    &board_snapshot,
+                    rack,
+                    usize::MAX,
+                    Some(constraints),
+                )
+                .iter()
+                .filter(|&play| not_pass(play))
+                .map(|play| play.fmt(&board_snapshot).to_string())
+                .collect::<Vec<_>>();
+                assert_eq!(constrained, expected);
+            }
+        }
+    }
+
+    fn put_play(game_config: &game_config::GameConfig<'_>, board_tiles: &mut [u8], play: &Play) {
+        if let Play::Place {
+            down,
+            lane,
+            idx,
+            word,
+            ..
+        } = play
+        {
+            let dim = game_config.board_layout().dim();
+            for (i, &tile) in (*idx..).zip(word.iter()) {
+                if tile != 0 {
+                    let (row, col) = if *down { (i, *lane) } else { (*lane, i) };
+                    let square = &mut board_tiles[dim.at_row_col(row, col)];
+                    // the same play is noted again when taken back.
+                    *square = if *square == 0 { tile } else { 0 };
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_incremental_matches_fresh() {
+        let game_config = game_config::make_common_english_game_config();
+        let kwg = make_kwg();
+        let klv = klv::Klv::from_bytes_alloc(klv::EMPTY_KLV_BYTES).unwrap();
+        let mut board_tiles = vec![0u8; board_tiles_len(&game_config)];
+        let mut move_generator = KurniaMoveGenerator::new(&game_config);
+        move_generator.set_incremental(true);
+        move_generator.set_verify_incremental(true);
+        let racks = [
+            &[1, 4, 5, 9, 18, 19, 20][..],
+            &[0, 1, 5, 9, 20],
+            &[1, 5, 5, 18, 19, 20, 20],
+            &[0, 1, 4, 5, 9, 9, 19],
+        ];
+        let mut num_placed = 0;
+        for turn in 0..12 {
+            let board_snapshot = BoardSnapshot {
+                board_tiles: &board_tiles,
+                game_config: &game_config,
+                kwg: &kwg,
+                klv: &klv,
+            };
+            let rack = racks[turn % racks.len()];
+            let plays = gen_plays(&mut move_generator, &board_snapshot, rack, 10, None);
+            let fresh_plays = gen_plays(
+                &mut KurniaMoveGenerator::new(&game_config),
+                &board_snapshot,
+                rack,
+                10,
+                None,
+            );
+            assert!(plays == fresh_plays, "turn {}", turn);
+            let play = &plays[0];
+            if let Play::Place { .. } = play {
+                num_placed += 1;
+            }
+            put_play(&game_config, &mut board_tiles, play);
+            move_generator.note_play(play);
+            if turn % 4 == 3 {
+                // take the play back.
+                put_play(&game_config, &mut board_tiles, play);
+                move_generator.note_play(play);
+            }
+        }
+        assert!(num_placed >= 6);
+    }
+
+    #[test]
+    fn test_max_gen_keeps_best_prefix() {
+        let game_config = game_config::make_common_english_game_config();
+        let kwg = make_kwg();
+        // without leaves, many plays tie on equity.
+        let klv = klv::Klv::from_bytes_alloc(klv::EMPTY_KLV_BYTES).unwrap();
+        let board_tiles = make_board_tiles(&game_config);
+        let board_snapshot = BoardSnapshot {
+            board_tiles: &board_tiles,
+            game_config: &game_config,
+            kwg: &kwg,
+            klv: &klv,
+        };
+        let mut move_generator = KurniaMoveGenerator::new(&game_config);
+        for rack in [&[1, 4, 5, 9, 18, 19, 20][..], &[0, 1, 5, 9, 20], &[0, 0, 4]] {
+            let all_plays = gen_plays(&mut move_generator, &board_snapshot, rack, usize::MAX, None);
+            for max_gen in [1, 2, 3, 5, 10, 50] {
+                let plays = gen_plays(&mut move_generator, &board_snapshot, rack, max_gen, None);
+                assert!(plays[..] == all_plays[..max_gen], "max_gen {}", max_gen);
+            }
+        }
+    }
+}
@@ -120,6 +120,9 @@ impl PlayScorer {
                         if board_tile != 0 {
                             return_error!("cannot place a tile onto an occupied square".into());
                         }
+                        if board_layout.blocked()[strider.at(i)] {
+                            return_error!("cannot place a tile onto a blocked square".into());
+                        }
                         if tile & 0x7f > alphabet_len_without_blank || tile == 0x80 {
                             return_error!("placed tile not in alphabet".into());
                         }
@@ -159,17 +162,19 @@ impl PlayScorer {
                 }
 
                 if !game_state.board_tiles.iter().any(|&tile| tile != 0) {
-                    if !if *down {
-                        *lane == board_layout.star_col() && {
-                            let star_idx = board_layout.star_row();
-                            *idx <= star_idx && star_idx < end_idx_exclusive as i8
-                        }
-                    } else {
-                        *lane == board_layout.star_row() && {
-                            let star_idx = board_layout.star_col();
-                            *idx <= star_idx && star_idx < end_idx_exclusive as i8
-                        }
-                    } {
+                    let start_squares = board_layout.start_squares();
+                    if !start_squares.is_empty()
+                        && !start_squares.iter().any(|&(start_row, start_col)| {
+                            let (start_lane, start_idx) = if *down {
+                                (start_col, start_row)
+                            } else {
+                                (start_row, start_col)
+                            };
+                            *lane == start_lane
+                                && *idx <= start_idx
+                                && start_idx < end_idx_exclusive as i8
+                        })
+                    {
                         return_error!("word does not cover starting square".into());
                     }
                 } else if !attaches_to_existing_tile {
@@ -436,12 +441,13 @@ impl PlayScorer {
     }
 
     // Assume recounted_score came from compute_score().
-    pub fn compute_equity(
+    // With tiles in the bag, the leave is valued by the evaluator.
+    pub fn compute_equity<Evaluator: movegen::StaticEvaluator + ?Sized>(
         &mut self,
         board_snapshot: &movegen::BoardSnapshot,
         game_state: &game_state::GameState,
         play: &movegen::Play,
-        leave_scale: f32,
+        evaluator: &Evaluator,
         recounted_score: i16,
     ) -> f32 {
         let game_config = board_snapshot.game_config;
@@ -490,8 +496,41 @@ impl PlayScorer {
                 recounted_equity += unplayed_tiles_bonus as f32;
             }
         } else {
-            let leave_value = board_snapshot.klv.leave_value_from_tally(&self.rack_tally);
-            recounted_equity += leave_scale * leave_value;
+            // the unseen tiles are the opponents' racks and the bag.
+            let mut unseen_tally = vec![0u8; self.rack_tally.len()];
+            for (player_idx, player) in (0u8..).zip(game_state.players.iter()) {
+                if player_idx != game_state.turn {
+                    player
+                        .rack
+                        .iter()
+                        .for_each(|&tile| unseen_tally[tile as usize] += 1);
+                }
+            }
+            game_state
+                .bag
+                .0
+                .iter()
+                .for_each(|&tile| unseen_tally[tile as usize] += 1);
+            let eval_context = movegen::StaticEvalContext {
+                board_snapshot,
+                unseen_tally: &unseen_tally,
+                num_tiles_on_board: game_state
+                    .board_tiles
+                    .iter()
+                    .filter(|&&tile| tile != 0)
+                    .count() as u16,
+                num_tiles_on_rack: game_state.current_player().rack.len() as u8,
+                num_tiles_in_bag: game_state.bag.0.len() as i16,
+                play_out_bonus: 0,
+            };
+            recounted_equity += match play {
+                movegen::Play::Exchange { tiles } => {
+                    evaluator.exchange_equity(&eval_context, tiles, &self.rack_tally)
+                }
+                movegen::Play::Place { .. } => {
+                    evaluator.leave_value(&eval_context, &self.rack_tally)
+                }
+            };
             if !game_state.board_tiles.iter().any(|&tile| tile != 0) {
                 match play {
                     movegen::Play::Exchange { .. } => {}
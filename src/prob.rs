@@ -1,6 +1,6 @@
 // Copyright (C) 2020-2021 Andy Kurnia.
 
-use super::{alphabet, kwg};
+use super::{alphabet, bites, kwg};
 
 #[derive(Clone)]
 struct Pascal {
@@ -119,3 +119,51 @@ impl WordProbability {
         self.get_max_probs_by_len_iter(&kwg, &mut Vec::new(), &mut v, kwg[0].arc_index());
     }
 }
+
+// machine_words must be sorted and deduplicated. returns the 1-based index of
+// each word among words of its length, by probability descending, then by
+// alphagram ascending, then by machine word.
+pub fn probability_indexes_by_length(
+    alphabet: &alphabet::Alphabet<'_>,
+    machine_words: &[bites::Bites],
+) -> Box<[u32]> {
+    let num_words = machine_words.len();
+    let mut word_prob = WordProbability::new(alphabet);
+    let mut tmp_vec = Vec::new();
+    let for_sorting = machine_words
+        .iter()
+        .map(|word| {
+            tmp_vec.clear();
+            tmp_vec.extend_from_slice(word);
+            tmp_vec.sort_unstable();
+            let alphagram: bites::Bites = tmp_vec[..].into();
+            (alphagram, word_prob.count_ways(word))
+        })
+        .collect::<Box<_>>();
+    let mut iter_indexes = (0..num_words as u32).collect::<Box<_>>();
+    iter_indexes.sort_unstable_by(|&a_idx, &b_idx| {
+        for_sorting[b_idx as usize]
+            .1
+            .cmp(&for_sorting[a_idx as usize].1)
+            .then_with(|| {
+                for_sorting[a_idx as usize]
+                    .0
+                    .cmp(&for_sorting[b_idx as usize].0)
+                    .then_with(|| a_idx.cmp(&b_idx))
+            })
+    });
+    drop(for_sorting);
+    let max_len = machine_words
+        .iter()
+        .map(|word| word.len())
+        .max()
+        .unwrap_or(0);
+    let mut assigned_indexes = vec![0u32; max_len + 1];
+    let mut probability_indexes = vec![0u32; num_words];
+    for &idx in iter_indexes.iter() {
+        let len = machine_words[idx as usize].len();
+        assigned_indexes[len] += 1;
+        probability_indexes[idx as usize] = assigned_indexes[len];
+    }
+    probability_indexes.into_boxed_slice()
+}
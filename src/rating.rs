@@ -0,0 +1,274 @@
+// Copyright (C) 2020-2021 Andy Kurnia.
+
+use super::{error, fash, stats};
+
+// elo points per unit of log odds.
+pub const ELO_SCALE: f64 = 400.0 / std::f64::consts::LN_10;
+
+// games between players numbered 0..num_players, draws count half.
+pub struct Results {
+    num_players: usize,
+    // [first, second] -> (games, points scored by first).
+    outcomes: fash::MyHashMap<[usize; 2], (f64, f64)>,
+}
+
+impl Results {
+    pub fn new(num_players: usize) -> Self {
+        Self {
+            num_players,
+            outcomes: fash::MyHashMap::default(),
+        }
+    }
+
+    pub fn num_players(&self) -> usize {
+        self.num_players
+    }
+
+    pub fn add_game(&mut self, first: usize, second: usize, first_score: i16, second_score: i16) {
+        let entry = self.outcomes.entry([first, second]).or_insert((0.0, 0.0));
+        entry.0 += 1.0;
+        entry.1 += match first_score.cmp(&second_score) {
+            std::cmp::Ordering::Greater => 1.0,
+            std::cmp::Ordering::Equal => 0.5,
+            std::cmp::Ordering::Less => 0.0,
+        };
+    }
+
+    // (games, points) of each player.
+    pub fn totals(&self) -> Vec<(f64, f64)> {
+        let mut ret = vec![(0.0, 0.0); self.num_players];
+        for (&[first, second], &(games, points)) in self.outcomes.iter() {
+            ret[first].0 += games;
+            ret[first].1 += points;
+            ret[second].0 += games;
+            ret[second].1 += games - points;
+        }
+        ret
+    }
+
+    // of ratings theta (in log odds, followed by the first player advantage),
+    // including the prior games.
+    fn log_likelihood(&self, theta: &[f64], prior_games: f64) -> f64 {
+        let adv = self.num_players;
+        let mut ret = 0.0;
+        for &x in &theta[..self.num_players] {
+            ret -= prior_games * 0.5 * (log_one_plus_exp(-x) + log_one_plus_exp(x));
+        }
+        for (&[first, second], &(games, points)) in self.outcomes.iter() {
+            if first == second {
+                continue;
+            }
+            let x = theta[first] - theta[second] + theta[adv];
+            ret -= points * log_one_plus_exp(-x) + (games - points) * log_one_plus_exp(x);
+        }
+        ret
+    }
+}
+
+// the fitted model is P(first beats second) = 1 / (1 + 10^((r2 - r1 - a) / 400)),
+// where a is the advantage of going first.
+pub struct Ratings {
+    // num_players ratings followed by the first player advantage, in elo.
+    params: Box<[f64]>,
+    // of params, row major.
+    covariance: Box<[f64]>,
+}
+
+impl Ratings {
+    // maximum likelihood by newton's method. every player also gets prior_games
+    // virtual games against a 0-rated player, half of them won, which keeps
+    // ratings finite when a player wins or loses every game, and ties together
+    // players that never met.
+    pub fn fit(results: &Results, prior_games: f64) -> error::Returns<Self> {
+        let num_players = results.num_players;
+        let n = num_players + 1;
+        let adv = num_players;
+        let mut theta = vec![0.0; n];
+        let mut new_theta = vec![0.0; n];
+        let mut hessian = vec![0.0; n * n];
+        let mut step = vec![0.0; n];
+        let mut log_likelihood = results.log_likelihood(&theta, prior_games);
+        for _ in 0..100 {
+            // negated, so hessian is positive definite.
+            hessian.iter_mut().for_each(|m| *m = 0.0);
+            step.iter_mut().for_each(|m| *m = 0.0);
+            for i in 0..num_players {
+                let p = logistic(theta[i]);
+                step[i] += prior_games * (0.5 - p);
+                hessian[i * n + i] += prior_games * p * (1.0 - p);
+            }
+            for (&[first, second], &(games, points)) in results.outcomes.iter() {
+                if first == second {
+                    continue;
+                }
+                let p = logistic(theta[first] - theta[second] + theta[adv]);
+                let gradient = points - games * p;
+                let curvature = games * p * (1.0 - p);
+                for &(i, xi) in &[(first, 1.0), (second, -1.0), (adv, 1.0)] {
+                    step[i] += gradient * xi;
+                    for &(j, xj) in &[(first, 1.0), (second, -1.0), (adv, 1.0)] {
+                        hessian[i * n + j] += curvature * xi * xj;
+                    }
+                }
+            }
+            if !stats::cholesky(&mut hessian, n) {
+                return_error!("ratings cannot be fitted, increase prior".into());
+            }
+            stats::cholesky_solve(&hessian, n, &mut step);
+            // a full step can overshoot, halve it until it does not make things
+            // worse (beyond rounding, which dominates near the maximum).
+            let tolerance = 1e-12 * (1.0 + log_likelihood.abs());
+            let mut scale = 1.0;
+            for _ in 0..30 {
+                for i in 0..n {
+                    new_theta[i] = theta[i] + scale * step[i];
+                }
+                let new_log_likelihood = results.log_likelihood(&new_theta, prior_games);
+                if new_log_likelihood >= log_likelihood - tolerance {
+                    log_likelihood = new_log_likelihood;
+                    break;
+                }
+                scale *= 0.5;
+            }
+            let mut max_change = 0.0f64;
+            for (param, new_param) in theta.iter_mut().zip(new_theta.iter()) {
+                max_change = max_change.max((new_param - *param).abs());
+                *param = *new_param;
+            }
+            if max_change < 1e-9 {
+                break;
+            }
+        }
+        // the inverse of the (last) hessian estimates the covariance.
+        let mut covariance = vec![0.0; n * n];
+        for i in 0..n {
+            step.iter_mut().for_each(|m| *m = 0.0);
+            step[i] = 1.0;
+            stats::cholesky_solve(&hessian, n, &mut step);
+            for j in 0..n {
+                covariance[j * n + i] = step[j] * ELO_SCALE * ELO_SCALE;
+            }
+        }
+        Ok(Self {
+            params: theta.iter().map(|x| x * ELO_SCALE).collect(),
+            covariance: covariance.into_boxed_slice(),
+        })
+    }
+
+    pub fn num_players(&self) -> usize {
+        self.params.len() - 1
+    }
+
+    // these are only meaningful relative to each other.
+    pub fn rating(&self, player: usize) -> f64 {
+        self.params[player]
+    }
+
+    pub fn first_advantage(&self) -> (f64, f64) {
+        let adv = self.num_players();
+        (self.params[adv], self.variance(&[(adv, 1.0)]).sqrt())
+    }
+
+    // (rating of player minus rating of other, standard error).
+    pub fn difference(&self, player: usize, other: usize) -> (f64, f64) {
+        (
+            self.params[player] - self.params[other],
+            self.variance(&[(player, 1.0), (other, -1.0)]).sqrt(),
+        )
+    }
+
+    // (rating of player minus mean rating, standard error).
+    pub fn relative_to_mean(&self, player: usize) -> (f64, f64) {
+        let num_players = self.num_players();
+        let w = 1.0 / num_players as f64;
+        let mut coefs = (0..num_players).map(|i| (i, -w)).collect::<Vec<_>>();
+        coefs[player].1 += 1.0;
+        (
+            coefs.iter().map(|&(i, c)| c * self.params[i]).sum(),
+            self.variance(&coefs).sqrt(),
+        )
+    }
+
+    // of a linear combination of params.
+    fn variance(&self, coefs: &[(usize, f64)]) -> f64 {
+        let n = self.params.len();
+        let mut ret = 0.0;
+        for &(i, ci) in coefs {
+            for &(j, cj) in coefs {
+                ret += ci * cj * self.covariance[i * n + j];
+            }
+        }
+        ret.max(0.0)
+    }
+}
+
+#[inline(always)]
+fn logistic(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+// ln(1 + e^x) without overflow, so -ln(logistic(x)) is log_one_plus_exp(-x).
+#[inline(always)]
+fn log_one_plus_exp(x: f64) -> f64 {
+    x.max(0.0) + (-x.abs()).exp().ln_1p()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_games(results: &mut Results, first: usize, second: usize, wins: u32, losses: u32) {
+        for _ in 0..wins {
+            results.add_game(first, second, 1, 0);
+        }
+        for _ in 0..losses {
+            results.add_game(first, second, 0, 1);
+        }
+    }
+
+    // of the log-likelihood at the fitted ratings, should be 0.
+    fn max_gradient(results: &Results, ratings: &Ratings, prior_games: f64) -> f64 {
+        let n = results.num_players + 1;
+        let theta = ratings
+            .params
+            .iter()
+            .map(|x| x / ELO_SCALE)
+            .collect::<Vec<_>>();
+        let mut gradient = vec![0.0; n];
+        for i in 0..results.num_players {
+            gradient[i] += prior_games * (0.5 - logistic(theta[i]));
+        }
+        for (&[first, second], &(games, points)) in results.outcomes.iter() {
+            let p = logistic(theta[first] - theta[second] + theta[n - 1]);
+            gradient[first] += points - games * p;
+            gradient[second] -= points - games * p;
+            gradient[n - 1] += points - games * p;
+        }
+        gradient.iter().fold(0.0, |a, x| a.max(x.abs()))
+    }
+
+    #[test]
+    fn test_fit_win_rate() {
+        // 0 wins 75% whether going first or second.
+        let mut results = Results::new(2);
+        add_games(&mut results, 0, 1, 750, 250);
+        add_games(&mut results, 1, 0, 250, 750);
+        let ratings = Ratings::fit(&results, 0.01).unwrap();
+        let (difference, standard_error) = ratings.difference(0, 1);
+        assert!((difference - ELO_SCALE * 3.0f64.ln()).abs() < 0.1);
+        assert!(standard_error > 0.0 && standard_error < 20.0);
+        assert!(ratings.first_advantage().0.abs() < 1e-6);
+        assert!(max_gradient(&results, &ratings, 0.01) < 1e-6);
+    }
+
+    #[test]
+    fn test_fit_all_wins() {
+        // only the prior keeps this finite.
+        let mut results = Results::new(2);
+        add_games(&mut results, 0, 1, 1000, 0);
+        add_games(&mut results, 1, 0, 0, 1000);
+        let ratings = Ratings::fit(&results, 0.01).unwrap();
+        assert!(ratings.difference(0, 1).0 > 1000.0);
+        assert!(max_gradient(&results, &ratings, 0.01) < 1e-6);
+    }
+}
@@ -50,11 +50,16 @@ pub struct Simmer {
     // simulate() reuses these internally
     move_generator: movegen::KurniaMoveGenerator,
     rack_tally: Box<[u8]>,
+    last_plays: Vec<movegen::Play>, // made in the previous iteration
+    num_last_plays: usize,
 }
 
 impl Simmer {
     // The other methods must be called with the same game_config.
     pub fn new(game_config: &game_config::GameConfig) -> Self {
+        let mut move_generator = movegen::KurniaMoveGenerator::new(game_config);
+        // each rollout only changes the board through the plays it makes.
+        move_generator.set_incremental(true);
         Self {
             initial_game_state: game_state::GameState::new(game_config),
             initial_score_spread: 0,
@@ -68,8 +73,10 @@ impl Simmer {
                 .into_boxed_slice(),
             final_scores: vec![0; game_config.num_players() as usize].into_boxed_slice(),
 
-            move_generator: movegen::KurniaMoveGenerator::new(game_config),
+            move_generator,
             rack_tally: vec![0u8; game_config.alphabet().len() as usize].into_boxed_slice(),
+            last_plays: Vec::new(),
+            num_last_plays: 0,
         }
     }
 
@@ -82,6 +89,8 @@ impl Simmer {
     ) {
         self.initial_game_state.clone_from(&game_state);
         self.game_state.clone_from(&game_state);
+        self.move_generator.invalidate_cross_sets();
+        self.num_last_plays = 0;
         self.initial_score_spread = game_state.current_player().score
             - (0..)
                 .zip(game_state.players.iter())
@@ -132,6 +141,11 @@ impl Simmer {
         candidate_play: &movegen::Play,
     ) -> bool {
         self.game_state.clone_from(&self.initial_game_state);
+        // taking back the previous iteration's plays touches the same lanes.
+        for play in &self.last_plays[..self.num_last_plays] {
+            self.move_generator.note_play(play);
+        }
+        self.num_last_plays = 0;
         // reset leave values from previous iteration
         self.last_seen_leave_values
             .iter_mut()
@@ -154,6 +168,8 @@ impl Simmer {
                         rack: &self.game_state.current_player().rack,
                         max_gen: 1,
                         always_include_pass: false,
+                        num_tiles_in_bag: Some(self.game_state.bag.0.len() as i16),
+                        constraints: None,
                     });
                 &self.move_generator.plays[0].play
             });
@@ -163,12 +179,19 @@ impl Simmer {
                 &next_play,
             );
             self.last_seen_leave_values[self.game_state.turn as usize] =
-                klv.leave_value_from_tally(&self.rack_tally);
+                klv.leave_value_from_tally(&self.rack_tally, self.game_state.bag.0.len() as i16);
             RNG.with(|rng| {
                 self.game_state
                     .play(&game_config, &mut *rng.borrow_mut(), &next_play)
                     .unwrap();
             });
+            self.move_generator.note_play(&next_play);
+            if self.num_last_plays < self.last_plays.len() {
+                self.last_plays[self.num_last_plays].clone_from(&next_play);
+            } else {
+                self.last_plays.push(next_play.clone());
+            }
+            self.num_last_plays += 1;
             match self
                 .game_state
                 .check_game_ended(&game_config, &mut self.final_scores)
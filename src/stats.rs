@@ -80,4 +80,51 @@ impl Stats {
     pub fn ci_max(&self, z: f64) -> f64 {
         self.mean + z * (self.variance() / self.count).sqrt()
     }
+
+    #[inline(always)]
+    pub fn ci_min(&self, z: f64) -> f64 {
+        self.mean - z * (self.variance() / self.count).sqrt()
+    }
+}
+
+// a is a symmetric positive definite n*n matrix, row major, of which only the
+// lower triangle is read. it is replaced by l, where a = l * l^T. returns
+// false if a is not positive definite.
+pub fn cholesky(a: &mut [f64], n: usize) -> bool {
+    for i in 0..n {
+        for j in 0..=i {
+            let mut sum = a[i * n + j];
+            for k in 0..j {
+                sum -= a[i * n + k] * a[j * n + k];
+            }
+            if i == j {
+                if sum <= 0.0 {
+                    return false;
+                }
+                a[i * n + i] = sum.sqrt();
+            } else {
+                a[i * n + j] = sum / a[j * n + j];
+            }
+        }
+    }
+    true
+}
+
+// l is from cholesky. solves a * x = b in place, b goes in, x comes out.
+pub fn cholesky_solve(l: &[f64], n: usize, x: &mut [f64]) {
+    // l * z = b, then l^T * x = z.
+    for i in 0..n {
+        let mut sum = x[i];
+        for k in 0..i {
+            sum -= l[i * n + k] * x[k];
+        }
+        x[i] = sum / l[i * n + i];
+    }
+    for i in (0..n).rev() {
+        let mut sum = x[i];
+        for k in i + 1..n {
+            sum -= l[k * n + i] * x[k];
+        }
+        x[i] = sum / l[i * n + i];
+    }
 }
@@ -0,0 +1,416 @@
+// Copyright (C) 2020-2021 Andy Kurnia.
+
+use super::{
+    autoplay_log, error, game_config, game_state, klv, kwg, move_filter, move_picker, movegen,
+    stats,
+};
+use rand::prelude::*;
+
+#[derive(Clone, Copy)]
+pub enum Picker {
+    Hasty,
+    Simmer(move_picker::SimmerSettings),
+    RandomTopN(usize),
+}
+
+pub enum Filter {
+    Unfiltered,
+    // see move_filter::Tilt.
+    Tilt(i8),
+    // only words in this kwg, see move_filter::LimitedVocab.
+    LimitedVocab(std::sync::Arc<kwg::Kwg>),
+}
+
+// All bots in a tournament share the game_config, but each has its own kwg
+// (moves are only checked against the mover's kwg) and klv.
+pub struct Bot {
+    pub name: String,
+    pub kwg: std::sync::Arc<kwg::Kwg>,
+    pub klv: std::sync::Arc<klv::Klv>,
+    pub filter: Filter,
+    pub picker: Picker,
+}
+
+pub struct TournamentOptions {
+    // rounded up to even, each bag is played twice with the first player swapped.
+    pub games_per_pairing: u64,
+    pub num_threads: usize,
+    // the same seed deals the same bags, and plays the same games unless a
+    // bot sims.
+    pub seed: u64,
+}
+
+#[derive(Default)]
+pub struct BotTotals {
+    pub num_games: u64,
+    pub num_bingos: u64,
+    pub num_moves: u64,
+    pub move_time: std::time::Duration,
+}
+
+impl BotTotals {
+    pub fn merge(&mut self, other: &Self) {
+        self.num_games += other.num_games;
+        self.num_bingos += other.num_bingos;
+        self.num_moves += other.num_moves;
+        self.move_time += other.move_time;
+    }
+
+    pub fn bingos_per_game(&self) -> f64 {
+        self.num_bingos as f64 / self.num_games as f64
+    }
+
+    pub fn ms_per_move(&self) -> f64 {
+        self.move_time.as_secs_f64() * 1000.0 / self.num_moves as f64
+    }
+}
+
+// From the point of view of bots[0]. The games of a pair share a bag, so
+// the confidence intervals come from the per-pair means.
+pub struct PairingResults {
+    pub bots: [usize; 2],
+    pub wins: u64,
+    pub draws: u64,
+    pub losses: u64,
+    pub pair_wins: stats::Stats,
+    pub pair_spreads: stats::Stats,
+    pub totals: [BotTotals; 2],
+}
+
+impl PairingResults {
+    fn new(bots: [usize; 2]) -> Self {
+        Self {
+            bots,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            pair_wins: stats::Stats::new(),
+            pair_spreads: stats::Stats::new(),
+            totals: [BotTotals::default(), BotTotals::default()],
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+        self.pair_wins.update_bulk(&other.pair_wins);
+        self.pair_spreads.update_bulk(&other.pair_spreads);
+        self.totals[0].merge(&other.totals[0]);
+        self.totals[1].merge(&other.totals[1]);
+    }
+
+    pub fn num_games(&self) -> u64 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+// both games of pair pair_idx of a seeded run deal from this rng, so the same
+// tiles are dealt until the plays make a difference.
+pub fn pair_bag_rng(seed: u64, pair_idx: u64) -> rand_chacha::ChaCha20Rng {
+    let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(seed);
+    rng.set_stream(pair_idx);
+    rng
+}
+
+// appends the id of game game_idx (0 or 1) of pair pair_idx, such as "1f-3-2".
+pub fn push_pair_game_id(s: &mut String, seed: u64, pair_idx: u64, game_idx: u8) {
+    use std::fmt::Write;
+    write!(s, "{:x}-{}-{}", seed, pair_idx, game_idx + 1).unwrap();
+}
+
+// every unordered pair of bots, in order, for a round robin.
+pub fn pairings(num_bots: usize) -> Vec<[usize; 2]> {
+    let mut ret = Vec::new();
+    for a in 0..num_bots {
+        for b in a + 1..num_bots {
+            ret.push([a, b]);
+        }
+    }
+    ret
+}
+
+struct Player<'a> {
+    filtered_movegen: move_filter::GenMoves<'a>,
+    move_picker: move_picker::MovePicker<'a>,
+}
+
+impl<'a> Player<'a> {
+    fn new(game_config: &'a game_config::GameConfig<'a>, bot: &'a Bot) -> Self {
+        Self {
+            filtered_movegen: match &bot.filter {
+                Filter::Unfiltered => move_filter::GenMoves::Unfiltered,
+                Filter::Tilt(bot_level) => move_filter::GenMoves::Tilt {
+                    tilt: move_filter::Tilt::new(
+                        game_config,
+                        &bot.kwg,
+                        move_filter::Tilt::length_importances(),
+                    ),
+                    bot_level: *bot_level,
+                },
+                Filter::LimitedVocab(vocab_kwg) => {
+                    move_filter::GenMoves::LimitedVocab(move_filter::LimitedVocab::new(vocab_kwg))
+                }
+            },
+            move_picker: match bot.picker {
+                Picker::Hasty => move_picker::MovePicker::Hasty,
+                Picker::Simmer(settings) => {
+                    let mut simmer = move_picker::Simmer::new(game_config, &bot.kwg, &bot.klv);
+                    simmer.settings = settings;
+                    move_picker::MovePicker::Simmer(simmer)
+                }
+                Picker::RandomTopN(top_n) => move_picker::MovePicker::RandomTopN(top_n),
+            },
+        }
+    }
+}
+
+// seats[i] is the bot of player i, player 0 goes first.
+// game_row.scores and game_row.bingos are by player.
+#[allow(clippy::too_many_arguments)]
+fn play_game(
+    game_config: &game_config::GameConfig,
+    bots: &[Bot],
+    players: &mut [Player],
+    seats: [usize; 2],
+    move_generator: &mut movegen::KurniaMoveGenerator,
+    game_state: &mut game_state::GameState,
+    bag_rng: &mut dyn RngCore,
+    rng: &mut dyn RngCore,
+    runtime: &tokio::runtime::Runtime,
+    game_row: &mut autoplay_log::GameRow,
+    totals: &mut [BotTotals; 2],
+) -> error::Returns<()> {
+    game_row.bingos.iter_mut().for_each(|m| *m = 0);
+    game_state.reset_and_draw_tiles(game_config, bag_rng);
+    loop {
+        let turn = game_state.turn as usize;
+        let bot = &bots[seats[turn]];
+        let player = &mut players[seats[turn]];
+        if let move_filter::GenMoves::Tilt { tilt, bot_level } = &mut player.filtered_movegen {
+            tilt.tilt_by_rng(rng, *bot_level);
+        }
+        let board_snapshot = &movegen::BoardSnapshot {
+            board_tiles: &game_state.board_tiles,
+            game_config,
+            kwg: &bot.kwg,
+            klv: &bot.klv,
+        };
+        let t0 = std::time::Instant::now();
+        runtime.block_on(player.move_picker.pick_a_move_async(
+            &mut player.filtered_movegen,
+            move_generator,
+            board_snapshot,
+            game_state,
+            &game_state.current_player().rack,
+            rng,
+        ));
+        totals[turn].move_time += t0.elapsed();
+        totals[turn].num_moves += 1;
+        let play = &move_generator.plays[0].play; // assume at least there's always Pass
+        if let movegen::Play::Place { word, .. } = play {
+            if word.iter().filter(|&&tile| tile != 0).count() >= game_config.rack_size() as usize {
+                game_row.bingos[turn] += 1;
+                totals[turn].num_bingos += 1;
+            }
+        }
+        game_state.play(game_config, bag_rng, play)?;
+        match game_state.check_game_ended(game_config, &mut game_row.scores) {
+            game_state::CheckGameEnded::PlayedOut | game_state::CheckGameEnded::ZeroScores => {
+                break;
+            }
+            game_state::CheckGameEnded::NotEnded => {}
+        }
+        game_state.next_turn();
+    }
+    totals[0].num_games += 1;
+    totals[1].num_games += 1;
+    Ok(())
+}
+
+// Plays every pairing, each bag twice with the first player swapped.
+// Each pairing's games are written to its games_writer (p1 is bots[0]).
+pub fn run<W: std::io::Write + Send + 'static>(
+    game_config: std::sync::Arc<game_config::GameConfig<'static>>,
+    bots: std::sync::Arc<Vec<Bot>>,
+    pairings: Vec<[usize; 2]>,
+    options: &TournamentOptions,
+    games_writers: Vec<autoplay_log::Writer<W>>,
+) -> error::Returns<Vec<PairingResults>> {
+    if game_config.num_players() != 2 {
+        return_error!("tournaments need two players".into());
+    }
+    if pairings
+        .iter()
+        .any(|&[a, b]| a == b || a >= bots.len() || b >= bots.len())
+    {
+        return_error!("invalid pairing".into());
+    }
+    if games_writers.len() != pairings.len() {
+        return_error!(format!("need {} games writers", pairings.len()));
+    }
+    let num_pairs_per_pairing = options.games_per_pairing / 2 + options.games_per_pairing % 2;
+    let num_work = pairings.len() as u64 * num_pairs_per_pairing;
+    let seed = options.seed;
+    let next_work = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let num_completed_games = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let t0 = std::time::Instant::now();
+    struct MutexedStuffs<W: std::io::Write> {
+        games_writers: Vec<autoplay_log::Writer<W>>,
+        tick_periods: move_picker::Periods,
+    }
+    let mutexed_stuffs = std::sync::Arc::new(std::sync::Mutex::new(MutexedStuffs {
+        games_writers,
+        tick_periods: move_picker::Periods(0),
+    }));
+    let mut threads = vec![];
+
+    for _ in 0..options.num_threads {
+        let game_config = std::sync::Arc::clone(&game_config);
+        let bots = std::sync::Arc::clone(&bots);
+        let pairings = pairings.clone();
+        let next_work = std::sync::Arc::clone(&next_work);
+        let num_completed_games = std::sync::Arc::clone(&num_completed_games);
+        let mutexed_stuffs = std::sync::Arc::clone(&mutexed_stuffs);
+        threads.push(std::thread::spawn(move || {
+            // pick_a_move would build a runtime for every move.
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap();
+            let mut players = bots
+                .iter()
+                .map(|bot| Player::new(&game_config, bot))
+                .collect::<Vec<_>>();
+            let mut move_generator = movegen::KurniaMoveGenerator::new(&game_config);
+            let mut game_state = game_state::GameState::new(&game_config);
+            let mut game_rows = [
+                autoplay_log::GameRow {
+                    scores: vec![0; 2],
+                    bingos: vec![0; 2],
+                    ..Default::default()
+                },
+                autoplay_log::GameRow {
+                    scores: vec![0; 2],
+                    bingos: vec![0; 2],
+                    ..Default::default()
+                },
+            ];
+            let mut results = pairings
+                .iter()
+                .map(|&pairing| PairingResults::new(pairing))
+                .collect::<Vec<_>>();
+            loop {
+                let work = next_work.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                if work >= num_work {
+                    break;
+                }
+                let pairing_idx = (work / num_pairs_per_pairing) as usize;
+                let [a, b] = pairings[pairing_idx];
+                let result = &mut results[pairing_idx];
+                let mut pair_win = 0.0;
+                let mut pair_spread = 0.0;
+                for (game_idx, game_row) in game_rows.iter_mut().enumerate() {
+                    let mut bag_rng = pair_bag_rng(seed, work);
+                    // tilts and random picks draw from a stream of their own.
+                    let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(seed);
+                    rng.set_stream(!work);
+                    let mut totals = [BotTotals::default(), BotTotals::default()];
+                    let seats = if game_idx == 0 { [a, b] } else { [b, a] };
+                    play_game(
+                        &game_config,
+                        &bots,
+                        &mut players,
+                        seats,
+                        &mut move_generator,
+                        &mut game_state,
+                        &mut bag_rng,
+                        &mut rng,
+                        &runtime,
+                        game_row,
+                        &mut totals,
+                    )
+                    .unwrap();
+                    if game_idx != 0 {
+                        // by player, so p1 is always a.
+                        game_row.scores.swap(0, 1);
+                        game_row.bingos.swap(0, 1);
+                        totals.swap(0, 1);
+                    }
+                    game_row.game_id.clear();
+                    push_pair_game_id(&mut game_row.game_id, seed, work, game_idx as u8);
+                    game_row.first = game_idx as u8;
+                    let spread = game_row.scores[0] as i32 - game_row.scores[1] as i32;
+                    match spread.cmp(&0) {
+                        std::cmp::Ordering::Greater => {
+                            result.wins += 1;
+                            pair_win += 0.5;
+                        }
+                        std::cmp::Ordering::Equal => {
+                            result.draws += 1;
+                            pair_win += 0.25;
+                        }
+                        std::cmp::Ordering::Less => {
+                            result.losses += 1;
+                        }
+                    }
+                    pair_spread += spread as f64 * 0.5;
+                    result.totals[0].merge(&totals[0]);
+                    result.totals[1].merge(&totals[1]);
+                }
+                result.pair_wins.update(pair_win);
+                result.pair_spreads.update(pair_spread);
+                let num_completed_games =
+                    num_completed_games.fetch_add(2, std::sync::atomic::Ordering::Relaxed) + 2;
+                let elapsed_time_secs = t0.elapsed().as_secs();
+                let tick_changed = {
+                    let mut mutex_guard = mutexed_stuffs.lock().unwrap();
+                    for game_row in game_rows.iter() {
+                        mutex_guard.games_writers[pairing_idx]
+                            .write_game_row(game_row)
+                            .unwrap();
+                    }
+                    mutex_guard.tick_periods.update(elapsed_time_secs)
+                };
+                if tick_changed {
+                    println!(
+                        "After {} seconds, have played {} of {} games",
+                        elapsed_time_secs,
+                        num_completed_games,
+                        2 * num_work
+                    );
+                }
+            }
+            results
+        }));
+    }
+
+    let mut results = pairings
+        .iter()
+        .map(|&pairing| PairingResults::new(pairing))
+        .collect::<Vec<_>>();
+    for thread in threads {
+        match thread.join() {
+            Ok(thread_results) => {
+                for (result, thread_result) in results.iter_mut().zip(thread_results.iter()) {
+                    result.merge(thread_result);
+                }
+            }
+            Err(e) => println!("{:?}", e),
+        }
+    }
+    let mutexed_stuffs = match std::sync::Arc::try_unwrap(mutexed_stuffs) {
+        Ok(mutexed_stuffs) => mutexed_stuffs.into_inner().unwrap(),
+        Err(_) => {
+            return_error!("threads still running".into());
+        }
+    };
+    for games_writer in mutexed_stuffs.games_writers {
+        games_writer.into_inner()?.flush()?;
+    }
+    println!(
+        "After {} seconds, have played {} games",
+        t0.elapsed().as_secs(),
+        num_completed_games.load(std::sync::atomic::Ordering::Relaxed),
+    );
+    Ok(results)
+}